@@ -1,7 +1,8 @@
+use types::roles::USERS_READ;
 use yew::prelude::*;
 use yew_router::prelude::*;
 
-use crate::{components::{auth::{admin_route::AdminRoute, protected_route::ProtectedRoute}, footer::Footer, header::Header}, views::{admin_view::AdminView, chat::Chat, home::Home, login::Login, not_found::NotFound, register::Register, request_reset::RequestReset, reset::Reset, user_view::UserView}};
+use crate::{components::{auth::{admin_route::AdminRoute, protected_route::ProtectedRoute}, footer::Footer, header::Header}, views::{admin_view::AdminView, chat::Chat, home::Home, login::Login, not_found::NotFound, register::Register, request_reset::RequestReset, reset::Reset, user_view::UserView, verify::Verify}};
 use crate::hooks::use_user_info;
 
 /// App routes
@@ -23,6 +24,8 @@ pub enum AppRoute {
     Reset,
     #[at("/reset/request")]
     RequestReset,
+    #[at("/verify")]
+    Verify,
     #[not_found]
     #[at("/404")]
     NotFound,
@@ -31,13 +34,14 @@ pub enum AppRoute {
 pub fn switch(route: AppRoute) -> Html {
     match route {
         AppRoute::Home => html! {<Home />},
-        AppRoute::AdminPanel => html! {<AdminRoute><AdminView /></AdminRoute>},
+        AppRoute::AdminPanel => html! {<AdminRoute permissions={vec![USERS_READ]}><AdminView /></AdminRoute>},
         AppRoute::Chat => html! {<ProtectedRoute><Chat /></ProtectedRoute>},
         AppRoute::UserPanel => html! {<ProtectedRoute><UserView /></ProtectedRoute>},
         AppRoute::Login => html! {<Login />},
         AppRoute::Register => html! {<Register />},
         AppRoute::Reset => html! {<Reset />},
         AppRoute::RequestReset => html! {<RequestReset />},
+        AppRoute::Verify => html! {<Verify />},
         AppRoute::NotFound => html! { <NotFound /> },
     }
 }
@@ -5,14 +5,19 @@ use crate::hooks::use_user_info;
 #[derive(Properties, PartialEq)]
 pub struct Props {
     pub children: ChildrenRenderer<VNode>,
+    // permissions the user must all hold to view the route
+    #[prop_or_default]
+    pub permissions: Vec<&'static str>,
 }
 
 #[function_component(AdminRoute)]
 pub fn admin_route(props: &Props) -> Html {
     let user_info = use_user_info();
+    let permissions = props.permissions.clone();
 
     use_effect(move || {
-        if user_info.uuid == String::new() || !user_info.is_admin  {
+        let permitted = permissions.iter().all(|permission| user_info.has_permission(permission));
+        if user_info.uuid == String::new() || !permitted  {
             BrowserHistory::new().push("/login");
         }
     });
@@ -23,4 +28,4 @@ pub fn admin_route(props: &Props) -> Html {
             { props.children.clone() }
         </>
     }
-}
\ No newline at end of file
+}
@@ -1,5 +1,5 @@
 use gloo_console::error;
-use types::user::LoginUser;
+use types::{auth::MfaLogin, error::ApiError, user::LoginUser};
 use web_sys::HtmlInputElement;
 use yew::UseStateHandle;
 use yew::{function_component, html, use_state, Callback, Html, InputEvent, SubmitEvent, TargetCast};
@@ -11,16 +11,18 @@ use yewdux::prelude::*;
 
 use crate::components::error_message::ErrorMessage;
 use crate::hooks::StoredUserInfo;
-use crate::services::AuthError;
+use crate::services::auth::LoginResult;
 use crate::{services, components::{buttons::button::Button, input::Input}};
 
 #[function_component(LoginForm)]
 pub fn login_form() -> Html {
     let (_user_state, user_dispatch) = use_store::<StoredUserInfo>();
     let login_user = use_state(LoginUser::default);
-    let error_state = use_state(|| None::<AuthError>);
+    let error_state = use_state(|| None::<ApiError>);
+    let mfa_token = use_state(|| None::<String>);
+    let mfa_code = use_state(String::new);
 
-    let oninput = |key, error_state: &UseStateHandle<Option<AuthError>>| {
+    let oninput = |key, error_state: &UseStateHandle<Option<ApiError>>| {
         let error_state = (*error_state).clone();
         let login_user = login_user.clone();
         Callback::from(move |e: InputEvent| {
@@ -37,17 +39,43 @@ pub fn login_form() -> Html {
         })
     };
 
+    let mfa_oninput = {
+        let error_state = error_state.clone();
+        let mfa_code = mfa_code.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            mfa_code.set(input.value());
+        })
+    };
+
     let handle_login = {
         let error_state = error_state.clone();
         let login_user = login_user.clone();
+        let mfa_token = mfa_token.clone();
+        let mfa_code = mfa_code.clone();
+        let user_dispatch = user_dispatch.clone();
         use_async(async move {
-            let response = services::auth::login_user((*login_user).clone()).await;
+            // exchange MFA pending token and code once password login succeeded
+            let response = match (*mfa_token).clone() {
+                Some(token) => services::auth::login_mfa(MfaLogin { mfa_token: token, code: (*mfa_code).clone() }).await
+                    .map(LoginResult::LoggedIn),
+                None => services::auth::login_user((*login_user).clone()).await
+            };
             match response {
-                Ok(user_info) => {
+                Ok(LoginResult::LoggedIn(user_info)) => {
                     user_dispatch.set(StoredUserInfo {user_info: user_info.clone()});
                     login_user.set(LoginUser::default());
+                    mfa_token.set(None);
+                    mfa_code.set(String::new());
                     BrowserHistory::new().push("/");
-                    Ok(user_info)
+                    Ok(())
+                },
+                Ok(LoginResult::MfaRequired(challenge)) => {
+                    mfa_token.set(Some(challenge.mfa_token));
+                    Ok(())
                 },
                 Err(error) => {
                     error_state.set(Some(error.to_owned()));
@@ -77,9 +105,15 @@ pub fn login_form() -> Html {
             if let Some(error) = (*error_state).to_owned() {
                 <ErrorMessage message={error.body().message} />
             }
-            <Input input_type="text" placeholder="Username" oninput={oninput.clone()("username", &error_state)} value={login_user.username.to_owned()} />
-            <Input input_type="password" placeholder="Password" oninput={oninput.clone()("pass", &error_state)} value={login_user.pass.to_owned()} />
-            <Button onclick={login_onclick} label="Login" />
+            if mfa_token.is_some() {
+                <p class="text-center text-slate-800 dark:text-slate-100">{"Enter the code from your authenticator app or a recovery code"}</p>
+                <Input input_type="text" placeholder="Verification code" oninput={mfa_oninput} value={(*mfa_code).to_owned()} />
+                <Button onclick={login_onclick} label="Verify" />
+            } else {
+                <Input input_type="text" placeholder="Username" oninput={oninput.clone()("username", &error_state)} value={login_user.username.to_owned()} />
+                <Input input_type="password" placeholder="Password" oninput={oninput.clone()("pass", &error_state)} value={login_user.pass.to_owned()} />
+                <Button onclick={login_onclick} label="Login" />
+            }
         </form>
     }
 }
\ No newline at end of file
@@ -2,5 +2,7 @@ pub mod login_form;
 pub mod register_form;
 pub mod reset_form;
 pub mod request_reset_form;
+pub mod verify_email_form;
 pub mod admin_route;
-pub mod protected_route;
\ No newline at end of file
+pub mod protected_route;
+pub mod totp_setup;
\ No newline at end of file
@@ -1,4 +1,4 @@
-use types::user::RegisterUser;
+use types::{error::ApiError, user::RegisterUser};
 use web_sys::HtmlInputElement;
 use yew::{function_component, html, use_state, Callback, Html, InputEvent, SubmitEvent, TargetCast, UseStateHandle};
 use yew_hooks::use_async;
@@ -6,15 +6,15 @@ use gloo_console::error;
 use yew_router::history::{History, BrowserHistory};
 use yewdux::prelude::*;
 
-use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, hooks::StoredUserInfo, services::{self, AuthError}};
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, hooks::StoredUserInfo, services};
 
 #[function_component(RegisterForm)]
 pub fn register_form() -> Html {
     let (_user_state, user_dispatch) = use_store::<StoredUserInfo>();
-    let error_state = use_state(|| None::<AuthError>);
+    let error_state = use_state(|| None::<ApiError>);
     let register_user = use_state(RegisterUser::default);
 
-    let oninput = |key, error_state: &UseStateHandle<Option<AuthError>>| {
+    let oninput = |key, error_state: &UseStateHandle<Option<ApiError>>| {
         let error_state = error_state.clone();
         let register_user = register_user.clone();
         Callback::from(move |e: InputEvent| {
@@ -40,7 +40,12 @@ pub fn register_form() -> Html {
                 Ok(user_info) => {
                     user_dispatch.set(StoredUserInfo {user_info: user_info.clone()});
                     register_user.set(RegisterUser::default());
-                    BrowserHistory::new().push("/login");
+                    // send new accounts to check their email for the verification link
+                    if user_info.email_verified {
+                        BrowserHistory::new().push("/login");
+                    } else {
+                        BrowserHistory::new().push("/verify");
+                    }
                     Ok(user_info)
                 },
                 Err(error) => {
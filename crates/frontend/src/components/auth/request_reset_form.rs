@@ -1,16 +1,17 @@
+use types::error::ApiError;
 use web_sys::HtmlInputElement;
 use yew::{function_component, html, use_state, Callback, Html, InputEvent, SubmitEvent, TargetCast, UseStateHandle};
 use yew_hooks::use_async;
 use yew_router::history::{BrowserHistory, History};
 
-use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services::{self, AuthError, AuthStorage}};
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services::{self, AuthStorage}};
 
 #[function_component(RequestResetForm)]
 pub fn request_reset_form() -> Html {
-    let error_state = use_state(|| None::<AuthError>);
+    let error_state = use_state(|| None::<ApiError>);
     let reset_email = use_state(|| String::new());
 
-    let oninput = |error_state: &UseStateHandle<Option<AuthError>>| {
+    let oninput = |error_state: &UseStateHandle<Option<ApiError>>| {
         let error_state = error_state.clone();
         let reset_email = reset_email.clone();
         Callback::from(move |e: InputEvent| {
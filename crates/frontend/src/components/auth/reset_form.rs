@@ -1,6 +1,6 @@
 use email_address::EmailAddress;
 use serde::Deserialize;
-use types::{auth::AuthErrorType, user::{ResetUser, UserInfo}};
+use types::{auth::AuthErrorType, error::ApiError, user::{ResetUser, UserInfo}};
 use web_sys::HtmlInputElement;
 use yew::{function_component, html, use_state, Callback, Html, InputEvent, SubmitEvent, TargetCast, UseStateHandle};
 use yew_hooks::use_async;
@@ -8,7 +8,7 @@ use gloo_console::error;
 use yew_router::{history::{BrowserHistory, History}, hooks::use_location};
 use yewdux::functional::use_store;
 
-use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, hooks::StoredUserInfo, services::{self, AuthError, AuthStorage}};
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, hooks::StoredUserInfo, services::{self, AuthStorage}};
 
 #[derive(Deserialize, Debug)]
 struct QueryParams {
@@ -21,15 +21,15 @@ pub fn reset_form() -> Html {
     let location = use_location().unwrap();
     let query_params = location.query::<QueryParams>().unwrap();
     let initial_user = ResetUser {
-        email_address: query_params.email,
+        email_address: query_params.email.to_string(),
         pass: String::new()
     };
     let (_user_state, user_dispatch) = use_store::<StoredUserInfo>();
-    let error_state = use_state(|| None::<AuthError>);
+    let error_state = use_state(|| None::<ApiError>);
     let reset_user = use_state(|| initial_user.clone());
     let confirm_pass = use_state(|| String::new());
 
-    let oninput = |key, error_state: &UseStateHandle<Option<AuthError>>| {
+    let oninput = |key, error_state: &UseStateHandle<Option<ApiError>>| {
         let error_state = error_state.clone();
         let reset_user = reset_user.clone();
         Callback::from(move |e: InputEvent| {
@@ -46,14 +46,14 @@ pub fn reset_form() -> Html {
         })
     };
 
-    let on_confirm_input = |error_state: &UseStateHandle<Option<AuthError>>| {
+    let on_confirm_input = |error_state: &UseStateHandle<Option<ApiError>>| {
         let error_state = error_state.clone();
         let reset_user = reset_user.clone();
         let confirm_pass = confirm_pass.clone();
         Callback::from(move |e: InputEvent| {
             let confirm_pass_value = e.target_unchecked_into::<HtmlInputElement>().value();
             if confirm_pass_value != reset_user.pass {
-                error_state.set(Some(AuthError::from_error_type(AuthErrorType::PasswordDoesNotMatch)));
+                error_state.set(Some(ApiError::auth(AuthErrorType::PasswordDoesNotMatch)));
             } else {
                 error_state.set(None);
             }
@@ -67,8 +67,8 @@ pub fn reset_form() -> Html {
         use_async(async move {
             let response = services::auth::reset_user((*reset_user).clone(), query_params.key).await;
             if let Some(error) = (*error_state).to_owned() {
-                match error.body().error_type {
-                    AuthErrorType::PasswordDoesNotMatch => return Err(error),
+                match error.auth_type() {
+                    Some(AuthErrorType::PasswordDoesNotMatch) => return Err(error),
                     _ => {()}
                 }
             }
@@ -0,0 +1,157 @@
+use types::{auth::{MfaCode, TotpEnrollment}, error::ApiError};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_hooks::prelude::*;
+
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services};
+
+#[function_component(TotpSetup)]
+pub fn totp_setup() -> Html {
+    let error_state = use_state(|| None::<ApiError>);
+    let enabled = use_state(|| false);
+    let enrollment = use_state(|| None::<TotpEnrollment>);
+    let recovery_codes = use_state(|| None::<Vec<String>>);
+    let code = use_state(String::new);
+
+    // Load current TOTP status on mount
+    {
+        let enabled = enabled.clone();
+        use_async_with_options(
+            async move {
+                match services::auth::get_totp_status().await {
+                    Ok(totp_status) => {
+                        enabled.set(totp_status.enabled);
+                        Ok(())
+                    },
+                    Err(error) => Err(error)
+                }
+            },
+            UseAsyncOptions::enable_auto(),
+        );
+    }
+
+    let oninput = {
+        let error_state = error_state.clone();
+        let code = code.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            code.set(input.value());
+        })
+    };
+
+    let handle_enroll = {
+        let error_state = error_state.clone();
+        let enrollment = enrollment.clone();
+        let recovery_codes = recovery_codes.clone();
+        use_async(async move {
+            match services::auth::enroll_totp().await {
+                Ok(totp_enrollment) => {
+                    recovery_codes.set(None);
+                    enrollment.set(Some(totp_enrollment));
+                    Ok(())
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let handle_confirm = {
+        let error_state = error_state.clone();
+        let enabled = enabled.clone();
+        let enrollment = enrollment.clone();
+        let recovery_codes = recovery_codes.clone();
+        let code = code.clone();
+        use_async(async move {
+            match services::auth::confirm_totp(MfaCode { code: (*code).clone() }).await {
+                Ok(codes) => {
+                    enabled.set(true);
+                    enrollment.set(None);
+                    code.set(String::new());
+                    recovery_codes.set(Some(codes.codes));
+                    Ok(())
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let handle_disable = {
+        let error_state = error_state.clone();
+        let enabled = enabled.clone();
+        let recovery_codes = recovery_codes.clone();
+        let code = code.clone();
+        use_async(async move {
+            match services::auth::disable_totp(MfaCode { code: (*code).clone() }).await {
+                Ok(status) => {
+                    enabled.set(false);
+                    code.set(String::new());
+                    recovery_codes.set(None);
+                    Ok(status)
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let enroll_onclick = {
+        let handle_enroll = handle_enroll.clone();
+        Callback::from(move |_| {
+            handle_enroll.run();
+        })
+    };
+
+    let confirm_onclick = {
+        let handle_confirm = handle_confirm.clone();
+        Callback::from(move |_| {
+            handle_confirm.run();
+        })
+    };
+
+    let disable_onclick = {
+        let handle_disable = handle_disable.clone();
+        Callback::from(move |_| {
+            handle_disable.run();
+        })
+    };
+
+    html! {
+        <div class="flex flex-col w-96 space-y-2 text-center text-slate-800 dark:text-slate-100">
+            <p>{"Two-factor authentication"}</p>
+            if let Some(error) = (*error_state).to_owned() {
+                <ErrorMessage message={error.body().message} />
+            }
+            if let Some(codes) = (*recovery_codes).to_owned() {
+                <p>{"Store these recovery codes somewhere safe, each can be used once instead of a code"}</p>
+                <div class="font-mono">
+                    { for codes.iter().map(|recovery_code| html! { <p>{ recovery_code }</p> }) }
+                </div>
+            }
+            if *enabled {
+                <Input input_type="text" placeholder="Verification or recovery code" oninput={oninput} value={(*code).to_owned()} />
+                <Button onclick={disable_onclick} label="Disable two-factor" />
+            } else if let Some(totp_enrollment) = (*enrollment).to_owned() {
+                <p>{"Add this account to your authenticator app, then enter the code it shows"}</p>
+                <a class="break-all text-blue-600 dark:text-blue-400 underline" href={totp_enrollment.otpauth_uri.clone()}>
+                    { totp_enrollment.otpauth_uri }
+                </a>
+                <p class="font-mono break-all">{ format!("Secret: {}", totp_enrollment.secret) }</p>
+                <Input input_type="text" placeholder="Verification code" oninput={oninput} value={(*code).to_owned()} />
+                <Button onclick={confirm_onclick} label="Confirm" />
+            } else {
+                <Button onclick={enroll_onclick} label="Enable two-factor" />
+            }
+        </div>
+    }
+}
@@ -0,0 +1,109 @@
+use serde::Deserialize;
+use types::error::ApiError;
+use web_sys::HtmlInputElement;
+use yew::{function_component, html, use_state, Callback, Html, InputEvent, SubmitEvent, TargetCast, UseStateHandle};
+use yew_hooks::{use_async, use_async_with_options, UseAsyncOptions};
+use yew_router::hooks::use_location;
+
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services};
+
+#[derive(Deserialize, Debug)]
+struct QueryParams {
+    key: Option<String>
+}
+
+#[function_component(VerifyEmailForm)]
+pub fn verify_email_form() -> Html {
+    let location = use_location().unwrap();
+    let verify_key = location.query::<QueryParams>().ok().and_then(|query_params| query_params.key);
+    let error_state = use_state(|| None::<ApiError>);
+    let message = use_state(|| None::<String>);
+    let resend_email = use_state(|| String::new());
+
+    // Verify key from emailed link on mount
+    {
+        let error_state = error_state.clone();
+        let message = message.clone();
+        use_async_with_options(
+            async move {
+                let Some(verify_key) = verify_key else {
+                    return Ok(());
+                };
+                match services::auth::verify_email(verify_key).await {
+                    Ok(_) => {
+                        message.set(Some(String::from("Your email address is verified, you can now log in")));
+                        Ok(())
+                    },
+                    Err(error) => {
+                        error_state.set(Some(error.to_owned()));
+                        Err(error)
+                    }
+                }
+            },
+            UseAsyncOptions::enable_auto(),
+        );
+    }
+
+    let oninput = |error_state: &UseStateHandle<Option<ApiError>>| {
+        let error_state = error_state.clone();
+        let resend_email = resend_email.clone();
+        Callback::from(move |e: InputEvent| {
+            let error_state = error_state.clone();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            let input: HtmlInputElement = e.target_unchecked_into();
+            resend_email.set(input.value());
+        })
+    };
+
+    let handle_resend = {
+        let resend_email = resend_email.clone();
+        let error_state = error_state.clone();
+        let message = message.clone();
+        use_async(async move {
+            let response = services::auth::resend_verification((*resend_email).to_owned()).await;
+            match response {
+                Ok(status) => {
+                    resend_email.set(String::new());
+                    message.set(Some(String::from("A new verification link has been sent")));
+                    Ok(status)
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let resend_onclick = {
+        let handle_resend = handle_resend.clone();
+        Callback::from(move |_| {
+            handle_resend.run();
+        })
+    };
+
+    let resend_onsubmit = {
+        let handle_resend = handle_resend.clone();
+        Callback::from(move |ev: SubmitEvent| {
+            ev.prevent_default();
+            handle_resend.run();
+        })
+    };
+
+    html! {
+        <form class="flex flex-col w-64 space-y-2 text-center
+            text-slate-800 dark:text-slate-100" onsubmit={resend_onsubmit}>
+            if let Some(message) = (*message).to_owned() {
+                <p>{message}</p>
+            }
+            if let Some(error) = (*error_state).to_owned() {
+                <ErrorMessage message={error.body().message} />
+            }
+            <p>{"Enter your email to receive a new verification link"}</p>
+            <Input input_type="email" placeholder="Email" oninput={oninput(&error_state)} value={(*resend_email).to_owned()} />
+            <Button onclick={resend_onclick} label="Resend" />
+        </form>
+    }
+}
@@ -1,14 +1,33 @@
+use gloo_console::error;
 use tauri_sys::tauri::invoke;
-use web_sys::{HtmlInputElement, WebSocket};
+use types::chat::{ClientMessage, Presence, PresenceStatus, ServerMessage, GENERAL_ROOM_ID};
+use web_sys::{Element, HtmlInputElement, WebSocket};
 use yew::prelude::*;
 use yew_hooks::prelude::*;
 
-use crate::{services::AuthStorage, graphics::icons::send_icon::SendIcon, components::{buttons::button::Button, input::Input}};
+use crate::{services::{self, AuthStorage}, graphics::icons::send_icon::SendIcon, components::{buttons::button::Button, input::Input}};
+
+// messages loaded per scroll to the top of the history
+const HISTORY_PAGE_SIZE: i64 = 50;
+// milliseconds without input before the connection reports itself idle
+const IDLE_AFTER_MS: f64 = 120_000.0;
+
+// what the window shows, a room or the conversation with the user of this uuid
+#[derive(Clone, Debug, PartialEq)]
+pub enum ChatTarget {
+    Room(i64),
+    Direct(String)
+}
 
 #[derive(Clone, Properties, PartialEq)]
 pub struct Props {
     #[prop_or(String::new())]
-    pub class: String
+    pub class: String,
+    #[prop_or(ChatTarget::Room(GENERAL_ROOM_ID))]
+    pub target: ChatTarget,
+    // a direct message arrived or a conversation was read, so unread counts changed
+    #[prop_or_default]
+    pub on_direct_activity: Callback<()>
 }
 
 #[function_component(ChatWindow)]
@@ -17,7 +36,16 @@ pub fn chat_windows(props: &Props) -> Html {
     let chat_disabled = use_state(|| true);
     let chat_message = use_state(|| String::new());
 
-    let history = use_list(vec![]);
+    let history = use_list(Vec::<ServerMessage>::new());
+    let history_complete = use_state(|| false);
+    // users online or idle, kept sorted by username
+    let online = use_list(Vec::<Presence>::new());
+    let idle = use_mut_ref(|| false);
+    let last_activity = use_mut_ref(js_sys::Date::now);
+    // target the window shows, read by the socket callbacks which outlive renders
+    let active_target = use_mut_ref(|| props.target.clone());
+    let on_direct_activity = use_mut_ref(|| props.on_direct_activity.clone());
+    *on_direct_activity.borrow_mut() = props.on_direct_activity.clone();
 
     // Manually connect to websocket with custom options.
     let ws = {
@@ -34,6 +62,11 @@ pub fn chat_windows(props: &Props) -> Html {
         );
 
         let history = history.clone();
+        let online = online.clone();
+        let idle_for_open = idle.clone();
+        let active_target_for_open = active_target.clone();
+        let active_target_for_message = active_target.clone();
+        let on_direct_activity = on_direct_activity.clone();
         let mut port = port.data.clone().unwrap_or_default();
         if cfg!(debug_assertions) && port == "" {
             port = "3001".to_string();
@@ -46,7 +79,15 @@ pub fn chat_windows(props: &Props) -> Html {
                 onopen: Some(Box::new(move |event| {
                     let socket = event.target_dyn_into::<WebSocket>().unwrap();
                     if let Ok(token) = AuthStorage::get_requester_token() {
-                        socket.send_with_str(&token.access_token).unwrap();
+                        let auth = ClientMessage::Auth { token: token.access_token };
+                        socket.send_with_str(&serde_json::to_string(&auth).unwrap()).unwrap();
+                        // new connections start out active
+                        *idle_for_open.borrow_mut() = false;
+                        // conversations are loaded over HTTP, their messages arrive without joining
+                        if let ChatTarget::Room(room) = *active_target_for_open.borrow() {
+                            let join = ClientMessage::Join { room };
+                            socket.send_with_str(&serde_json::to_string(&join).unwrap()).unwrap();
+                        }
                         chat_disabled_for_open.set(false);
                     } else {
                         socket.close().unwrap();
@@ -54,7 +95,63 @@ pub fn chat_windows(props: &Props) -> Html {
                 })),
                 // Receive message by callback `onmessage`.
                 onmessage: Some(Box::new(move |message| {
-                    history.push(format!("{}", message));
+                    let active_target = active_target_for_message.borrow().clone();
+                    match serde_json::from_str::<ServerMessage>(&message) {
+                        Ok(ServerMessage::PresenceSnapshot { users }) => online.set(users),
+                        Ok(ServerMessage::Presence(presence)) => {
+                            let mut users: Vec<Presence> = online.current().iter()
+                                .filter(|user| user.user_uuid != presence.user_uuid)
+                                .cloned()
+                                .collect();
+                            if presence.status != PresenceStatus::Offline {
+                                users.push(presence);
+                                users.sort_by_key(|user| user.username.to_lowercase());
+                            }
+                            online.set(users);
+                        },
+                        Ok(ServerMessage::Direct(direct_message)) => {
+                            let on_direct_activity = on_direct_activity.borrow().clone();
+                            let partner_uuid = match &active_target {
+                                ChatTarget::Direct(user_uuid) if *user_uuid == direct_message.sender_uuid || *user_uuid == direct_message.recipient_uuid => user_uuid.clone(),
+                                // messages of other conversations only change the conversation list
+                                _ => {
+                                    on_direct_activity.emit(());
+                                    return;
+                                }
+                            };
+                            let from_partner = direct_message.sender_uuid == partner_uuid;
+                            let known = history.current().iter().any(|entry| chat_id(entry) == Some(direct_message.id));
+                            if !known {
+                                history.push(ServerMessage::Direct(direct_message));
+                            }
+                            // the open conversation is read as messages arrive
+                            if from_partner {
+                                yew::platform::spawn_local(async move {
+                                    let _ = services::chat::mark_read(&partner_uuid).await;
+                                    on_direct_activity.emit(());
+                                });
+                            } else {
+                                on_direct_activity.emit(());
+                            }
+                        },
+                        // frames of the room that was just left can still arrive while switching
+                        Ok(server_message) if !belongs_to(&server_message, &active_target) => {},
+                        // acks only confirm our own message, which arrives as a chat frame anyway
+                        Ok(ServerMessage::Ack { .. }) => {},
+                        // messages accepted while joining arrive in the history and as chat frames
+                        Ok(ServerMessage::Chat(chat_message)) => {
+                            let known = history.current().iter().any(|entry| chat_id(entry) == Some(chat_message.id));
+                            if !known {
+                                history.push(ServerMessage::Chat(chat_message));
+                            }
+                        },
+                        Ok(ServerMessage::History { messages, .. }) => {
+                            let entries = merge_history(messages.into_iter().map(ServerMessage::Chat).collect(), &history.current());
+                            history.set(entries);
+                        },
+                        Ok(server_message) => history.push(server_message),
+                        Err(parse_error) => error!(format!("Invalid chat frame: {parse_error}"))
+                    }
                 })),
                 onclose: Some(Box::new(move |_event| {
                     chat_disabled_for_close.set(true);
@@ -65,6 +162,93 @@ pub fn chat_windows(props: &Props) -> Html {
         )
     };
 
+    // switch rooms on the open socket, onopen joins the active room if it is not open yet
+    {
+        let ws = ws.clone();
+        let history = history.clone();
+        let history_complete = history_complete.clone();
+        let active_target = active_target.clone();
+        let on_direct_activity = on_direct_activity.clone();
+        use_effect_with(props.target.clone(), move |target| {
+            let previous = active_target.replace(target.clone());
+            if previous != *target {
+                history.clear();
+                history_complete.set(false);
+                if let ChatTarget::Room(room) = previous {
+                    ws.send(serde_json::to_string(&ClientMessage::Leave { room }).unwrap());
+                }
+                if let ChatTarget::Room(room) = *target {
+                    ws.send(serde_json::to_string(&ClientMessage::Join { room }).unwrap());
+                }
+            }
+            // conversations load their latest page over HTTP and are read once opened
+            if let ChatTarget::Direct(user_uuid) = target.clone() {
+                yew::platform::spawn_local(async move {
+                    match services::chat::get_direct_messages(&user_uuid, None, HISTORY_PAGE_SIZE).await {
+                        Ok(messages) => {
+                            // another target was opened while loading
+                            if *active_target.borrow() != ChatTarget::Direct(user_uuid.clone()) {
+                                return;
+                            }
+                            if (messages.len() as i64) < HISTORY_PAGE_SIZE {
+                                history_complete.set(true);
+                            }
+                            let entries = merge_history(messages.into_iter().map(ServerMessage::Direct).collect(), &history.current());
+                            history.set(entries);
+                            let _ = services::chat::mark_read(&user_uuid).await;
+                            on_direct_activity.borrow().emit(());
+                        },
+                        Err(load_error) => error!(format!("Error loading conversation: {}", load_error.body().message))
+                    }
+                });
+            }
+        });
+    }
+
+    let load_older = {
+        let history = history.clone();
+        let history_complete = history_complete.clone();
+        let active_target = active_target.clone();
+        use_async(async move {
+            let target = active_target.borrow().clone();
+            let oldest = history.current().iter().find_map(chat_id);
+            let Some(before) = oldest else {
+                return Ok(());
+            };
+            let older = match target {
+                ChatTarget::Room(room) => services::chat::get_messages(room, Some(before), HISTORY_PAGE_SIZE).await
+                    .map(|messages| messages.into_iter().map(ServerMessage::Chat).collect::<Vec<ServerMessage>>()),
+                ChatTarget::Direct(user_uuid) => services::chat::get_direct_messages(&user_uuid, Some(before), HISTORY_PAGE_SIZE).await
+                    .map(|messages| messages.into_iter().map(ServerMessage::Direct).collect::<Vec<ServerMessage>>())
+            };
+            match older {
+                Ok(mut entries) => {
+                    if (entries.len() as i64) < HISTORY_PAGE_SIZE {
+                        history_complete.set(true);
+                    }
+                    entries.extend(history.current().iter().cloned());
+                    history.set(entries);
+                    Ok(())
+                },
+                Err(load_error) => {
+                    error!(format!("Error loading chat history: {}", load_error.body().message));
+                    Err(load_error)
+                }
+            }
+        })
+    };
+
+    // load older messages once the history is scrolled to the top
+    let onscroll = {
+        let load_older = load_older.clone();
+        Callback::from(move |e: Event| {
+            let element: Element = e.target_unchecked_into();
+            if element.scroll_top() <= 0 && !load_older.loading && !*history_complete {
+                load_older.run();
+            }
+        })
+    };
+
     let oninput = {
         let chat_message = chat_message.clone();
         Callback::from(move |e: InputEvent| {
@@ -76,11 +260,12 @@ pub fn chat_windows(props: &Props) -> Html {
     let send_chat = {
         let ws = ws.clone();
         let chat_message = chat_message.clone();
+        let active_target = active_target.clone();
         Callback::from(move |_| {
                 if *chat_message == String::new() {
                     return;
                 }
-                ws.send(chat_message.to_string());
+                ws.send(chat_frame(&active_target.borrow(), chat_message.to_string()));
                 chat_message.set(String::new());
         })
     };
@@ -90,11 +275,38 @@ pub fn chat_windows(props: &Props) -> Html {
         let chat_message = chat_message.clone();
         Callback::from(move |e: SubmitEvent| {
                 e.prevent_default();
-                ws.send(chat_message.to_string());
+                if *chat_message == String::new() {
+                    return;
+                }
+                ws.send(chat_frame(&active_target.borrow(), chat_message.to_string()));
                 chat_message.set(String::new());
         })
     };
 
+    // report the connection idle after a while without input and active again on the next input
+    {
+        let ws = ws.clone();
+        let idle = idle.clone();
+        let last_activity = last_activity.clone();
+        let on_activity = move |_: Event| {
+            *last_activity.borrow_mut() = js_sys::Date::now();
+            if idle.replace(false) {
+                ws.send(serde_json::to_string(&ClientMessage::Active).unwrap());
+            }
+        };
+        use_event_with_window("mousemove", on_activity.clone());
+        use_event_with_window("keydown", on_activity);
+    }
+    {
+        let ws = ws.clone();
+        use_interval(move || {
+            if !*idle.borrow() && js_sys::Date::now() - *last_activity.borrow() > IDLE_AFTER_MS {
+                *idle.borrow_mut() = true;
+                ws.send(serde_json::to_string(&ClientMessage::Idle).unwrap());
+            }
+        }, 10_000);
+    }
+
     use_effect_once(move || {
         ws.open();
         move || {ws.close()}
@@ -102,19 +314,27 @@ pub fn chat_windows(props: &Props) -> Html {
 
     html! {
         <div class={props.class}>
-            <div class="h-full px-4 py-2 py-2 
-            bg-slate-100 text-slate-800
-            border-slate-300 dark:border-slate-700 border
-            dark:bg-slate-900 dark:text-slate-100
-            rounded-md ring-offset-background disabled:pointer-events-none
-            overflow-y-auto text-wrap shadow-md">
-                {
-                    for history.current().iter().map(|message| {
-                        html! {
-                            <p>{ message }</p>
-                        }
-                    })
-                }
+            <div class="flex flex-row h-full min-h-0 space-x-2">
+                <div class="h-full w-full px-4 py-2 py-2 
+                bg-slate-100 text-slate-800
+                border-slate-300 dark:border-slate-700 border
+                dark:bg-slate-900 dark:text-slate-100
+                rounded-md ring-offset-background disabled:pointer-events-none
+                overflow-y-auto text-wrap shadow-md" onscroll={onscroll}>
+                    {
+                        for history.current().iter().map(render_message)
+                    }
+                </div>
+                <div class="h-full w-48 shrink-0 px-4 py-2
+                bg-slate-100 text-slate-800
+                border-slate-300 dark:border-slate-700 border
+                dark:bg-slate-900 dark:text-slate-100
+                rounded-md overflow-y-auto shadow-md">
+                    <p class="font-semibold">{ format!("Online ({})", online.current().len()) }</p>
+                    {
+                        for online.current().iter().map(render_presence)
+                    }
+                </div>
             </div>
             <form class="flex flex-row h-12 w-full space-x-2" onsubmit={send_chat_submit}>
                 <Input input_type="text" placeholder="Message..." oninput={oninput} value={(*chat_message).to_owned()} />
@@ -122,4 +342,83 @@ pub fn chat_windows(props: &Props) -> Html {
             </form>
         </div>
     }
-}
\ No newline at end of file
+}
+
+// room and direct message ids are separate, but the history only ever holds one target
+fn chat_id(entry: &ServerMessage) -> Option<i64> {
+    match entry {
+        ServerMessage::Chat(chat_message) => Some(chat_message.id),
+        ServerMessage::Direct(direct_message) => Some(direct_message.id),
+        _ => None
+    }
+}
+
+// loaded messages followed by the entries received meanwhile, without duplicates
+fn merge_history(mut entries: Vec<ServerMessage>, current: &[ServerMessage]) -> Vec<ServerMessage> {
+    let known: Vec<i64> = entries.iter().filter_map(chat_id).collect();
+    entries.extend(current.iter()
+        .filter(|entry| chat_id(entry).is_none_or(|id| !known.contains(&id)))
+        .cloned());
+    entries
+}
+
+// whether a frame is shown for the target, frames of the connection itself always are
+fn belongs_to(message: &ServerMessage, target: &ChatTarget) -> bool {
+    match message {
+        ServerMessage::Chat(chat_message) => *target == ChatTarget::Room(chat_message.room_id),
+        ServerMessage::History { room, .. } | ServerMessage::Join { room, .. } | ServerMessage::Leave { room, .. } => *target == ChatTarget::Room(*room),
+        ServerMessage::Direct(direct_message) => matches!(target,
+            ChatTarget::Direct(user_uuid) if *user_uuid == direct_message.sender_uuid || *user_uuid == direct_message.recipient_uuid),
+        _ => true
+    }
+}
+
+fn chat_frame(target: &ChatTarget, text: String) -> String {
+    let message = match target {
+        ChatTarget::Room(room) => ClientMessage::Chat { room: *room, text, client_id: None },
+        ChatTarget::Direct(user_uuid) => ClientMessage::Direct { to: user_uuid.clone(), text, client_id: None }
+    };
+    serde_json::to_string(&message).unwrap()
+}
+
+// local time of a unix timestamp in seconds
+fn format_time(timestamp: i64) -> String {
+    let date = js_sys::Date::new(&((timestamp * 1000) as f64).into());
+    format!("{:02}:{:02}", date.get_hours(), date.get_minutes())
+}
+
+fn render_message(message: &ServerMessage) -> Html {
+    match message {
+        ServerMessage::Chat(chat_message) => html! {
+            <p><span class="text-slate-500">{ format_time(chat_message.sent_at) }</span>{ format!(" {}: {}", chat_message.username, chat_message.text) }</p>
+        },
+        ServerMessage::Direct(direct_message) => html! {
+            <p><span class="text-slate-500">{ format_time(direct_message.sent_at) }</span>{ format!(" {}: {}", direct_message.username, direct_message.text) }</p>
+        },
+        ServerMessage::Join { username, at, .. } => html! {
+            <p class="italic"><span class="text-slate-500">{ format_time(*at) }</span>{ format!(" {username} joined.") }</p>
+        },
+        ServerMessage::Leave { username, at, .. } => html! {
+            <p class="italic"><span class="text-slate-500">{ format_time(*at) }</span>{ format!(" {username} left.") }</p>
+        },
+        ServerMessage::System { text, at } => html! {
+            <p class="italic"><span class="text-slate-500">{ format_time(*at) }</span>{ format!(" {text}") }</p>
+        },
+        ServerMessage::Error { message, .. } => html! {
+            <p class="text-red-600 dark:text-red-400">{ message }</p>
+        },
+        ServerMessage::History { .. } | ServerMessage::Ack { .. }
+            | ServerMessage::Presence(_) | ServerMessage::PresenceSnapshot { .. } => html! {}
+    }
+}
+
+fn render_presence(presence: &Presence) -> Html {
+    match presence.status {
+        PresenceStatus::Idle => html! {
+            <p class="text-slate-500">{ format!("{} (idle)", presence.username) }</p>
+        },
+        _ => html! {
+            <p>{ presence.username.clone() }</p>
+        }
+    }
+}
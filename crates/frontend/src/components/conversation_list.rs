@@ -0,0 +1,118 @@
+use types::{chat::StartConversation, error::ApiError};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_hooks::prelude::*;
+
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services};
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct Props {
+    // uuid of the user whose conversation is open
+    pub selected: Option<String>,
+    pub on_select: Callback<String>,
+    // bumped by the parent whenever direct messages arrive or are read
+    #[prop_or_default]
+    pub refresh: u32,
+    #[prop_or(String::new())]
+    pub class: String
+}
+
+#[function_component(ConversationList)]
+pub fn conversation_list(props: &Props) -> Html {
+    let props = props.clone();
+    let error_state = use_state(|| None::<ApiError>);
+    let username = use_state(String::new);
+
+    // Load conversations on mount and after changes
+    let conversations = {
+        let error_state = error_state.clone();
+        use_async_with_options(
+            async move {
+                match services::chat::get_conversations().await {
+                    Ok(conversations) => Ok(conversations),
+                    Err(error) => {
+                        error_state.set(Some(error.to_owned()));
+                        Err(error)
+                    }
+                }
+            },
+            UseAsyncOptions::enable_auto(),
+        )
+    };
+
+    {
+        let conversations = conversations.clone();
+        use_effect_with(props.refresh, move |refresh| {
+            if *refresh > 0 {
+                conversations.run();
+            }
+        });
+    }
+
+    let on_username_input = {
+        let error_state = error_state.clone();
+        let username = username.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            username.set(input.value());
+        })
+    };
+
+    let start_onclick = {
+        let error_state = error_state.clone();
+        let username = username.clone();
+        let on_select = props.on_select.clone();
+        Callback::from(move |_| {
+            let error_state = error_state.clone();
+            let username = username.clone();
+            let on_select = on_select.clone();
+            yew::platform::spawn_local(async move {
+                match services::chat::start_conversation(StartConversation { username: (*username).clone() }).await {
+                    Ok(conversation) => {
+                        username.set(String::new());
+                        on_select.emit(conversation.user_uuid);
+                    },
+                    Err(error) => error_state.set(Some(error))
+                }
+            });
+        })
+    };
+
+    let conversation_list = conversations.data.clone().unwrap_or_default();
+
+    html! {
+        <div class={props.class}>
+            if let Some(error) = (*error_state).to_owned() {
+                <ErrorMessage message={error.body().message} />
+            }
+            <div class="flex flex-col space-y-1 overflow-y-auto">
+                {
+                    for conversation_list.iter().map(|conversation| {
+                        let on_select = props.on_select.clone();
+                        let user_uuid = conversation.user_uuid.clone();
+                        let selected = props.selected.as_ref() == Some(&conversation.user_uuid);
+                        let color = if selected {
+                            "bg-slate-300 text-slate-900 dark:bg-slate-700 dark:text-slate-100"
+                        } else {
+                            "bg-slate-100 text-slate-800 hover:bg-slate-200 dark:bg-slate-900 dark:text-slate-100 dark:hover:bg-slate-800"
+                        };
+                        // the open conversation is read as messages arrive
+                        let label = if conversation.unread > 0 && !selected {
+                            format!("@ {} ({})", conversation.username, conversation.unread)
+                        } else {
+                            format!("@ {}", conversation.username)
+                        };
+                        html! {
+                            <Button label={label} onclick={Callback::from(move |_| on_select.emit(user_uuid.clone()))} color={color} />
+                        }
+                    })
+                }
+            </div>
+            <Input input_type="text" placeholder="Message by username or email" oninput={on_username_input} value={(*username).to_owned()} />
+            <Button label="Message" onclick={start_onclick} />
+        </div>
+    }
+}
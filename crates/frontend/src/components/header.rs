@@ -1,3 +1,4 @@
+use types::roles::USERS_READ;
 use yew::prelude::*;
 use crate::{app::AppRoute, components::buttons::nav_button::NavButton, hooks::use_user_info};
 
@@ -17,7 +18,7 @@ pub fn header() -> Html {
             </div>
             <div class="flex flex-row h-full">
                 if user_info.uuid != String::new() {
-                    if user_info.is_admin {
+                    if user_info.has_permission(USERS_READ) {
                         <NavButton label="Admin" destination={AppRoute::AdminPanel} />
                     }
                     <NavButton label={user_info.clone().username} destination={AppRoute::UserPanel} />
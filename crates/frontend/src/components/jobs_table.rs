@@ -0,0 +1,66 @@
+use types::jobs::JobInfo;
+use yew::prelude::*;
+use yew_hooks::{use_async, use_effect_once};
+
+use crate::services;
+
+#[function_component(JobsTable)]
+pub fn jobs_table() -> Html {
+    let jobs = use_state(|| Vec::<JobInfo>::new());
+
+    let handle_get_jobs = {
+        let jobs = jobs.clone();
+        use_async(async move {
+            let response = services::jobs::get_jobs().await;
+            match response {
+                Ok(data) => {
+                    jobs.set(data.1);
+                    Ok(data.0)
+                },
+                Err(error) => {
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let handle_get_jobs_clone = handle_get_jobs.clone();
+    use_effect_once(move || {
+        handle_get_jobs_clone.run();
+        move || {}
+    });
+
+    html! {
+        <div class="w-11/12 flex flex-col h-min
+        rounded-md text-lg font-strong overflow-y-auto
+        border-slate-300 dark:border-slate-700 border
+        h-10 px-4 py-2 my-10
+        bg-slate-100 text-slate-800 shadow-md
+        dark:bg-slate-900 dark:text-slate-100">
+            <table>
+                <thead>
+                    <tr class="text-left">
+                        <th>{"ID"}</th>
+                        <th>{"Kind"}</th>
+                        <th>{"Status"}</th>
+                        <th>{"Attempts"}</th>
+                        <th>{"Last Error"}</th>
+                    </tr>
+                </thead>
+                <tbody>
+                    { (*jobs).clone().into_iter().map(|job: JobInfo| {
+                        html!{
+                            <tr>
+                                <td>{job.id}</td>
+                                <td>{job.kind}</td>
+                                <td>{job.status}</td>
+                                <td>{format!("{}/{}", job.attempts, job.max_attempts)}</td>
+                                <td>{job.last_error.unwrap_or_default()}</td>
+                            </tr>
+                        }
+                    }).collect::<Html>()}
+                </tbody>
+            </table>
+        </div>
+    }
+}
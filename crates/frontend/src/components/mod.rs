@@ -6,4 +6,8 @@ pub mod buttons;
 pub mod user_info_panel;
 pub mod chat_window;
 pub mod users_table;
-pub mod error_message;
\ No newline at end of file
+pub mod jobs_table;
+pub mod error_message;
+pub mod profile_form;
+pub mod room_list;
+pub mod conversation_list;
\ No newline at end of file
@@ -0,0 +1,179 @@
+use gloo_console::error;
+use types::{error::ApiError, user::{ChangePassword, DeleteUser, UpdateUser, UserInfo}};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_hooks::prelude::*;
+use yewdux::functional::use_store;
+
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, hooks::StoredUserInfo, services::{self, AuthStorage}};
+
+#[function_component(ProfileForm)]
+pub fn profile_form() -> Html {
+    let (_user_info, user_info_dispatch) = use_store::<StoredUserInfo>();
+    let error_state = use_state(|| None::<ApiError>);
+    let notice = use_state(|| None::<String>);
+    let update_user = use_state(UpdateUser::default);
+    let change_password = use_state(ChangePassword::default);
+    let delete_pass = use_state(String::new);
+
+    // clear messages of the last submit on any input
+    let reset_messages = {
+        let error_state = error_state.clone();
+        let notice = notice.clone();
+        move || {
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            if let Some(_) = *notice {
+                notice.set(None);
+            }
+        }
+    };
+
+    let on_profile_input = |key: String| {
+        let reset_messages = reset_messages.clone();
+        let update_user = update_user.clone();
+        Callback::from(move |e: InputEvent| {
+            reset_messages();
+            let input: HtmlInputElement = e.target_unchecked_into();
+            match update_user.update_field(&key, input.value()) {
+                Ok(new_update_user) => update_user.set(new_update_user),
+                Err(error) => error!(error)
+            }
+        })
+    };
+
+    let on_password_input = |key: String| {
+        let reset_messages = reset_messages.clone();
+        let change_password = change_password.clone();
+        Callback::from(move |e: InputEvent| {
+            reset_messages();
+            let input: HtmlInputElement = e.target_unchecked_into();
+            match change_password.update_field(&key, input.value()) {
+                Ok(new_change_password) => change_password.set(new_change_password),
+                Err(error) => error!(error)
+            }
+        })
+    };
+
+    let on_delete_input = {
+        let reset_messages = reset_messages.clone();
+        let delete_pass = delete_pass.clone();
+        Callback::from(move |e: InputEvent| {
+            reset_messages();
+            let input: HtmlInputElement = e.target_unchecked_into();
+            delete_pass.set(input.value());
+        })
+    };
+
+    let handle_update = {
+        let error_state = error_state.clone();
+        let notice = notice.clone();
+        let update_user = update_user.clone();
+        let user_info_dispatch = user_info_dispatch.clone();
+        use_async(async move {
+            let email_changed = update_user.email.is_some();
+            match services::user::update_profile((*update_user).clone()).await {
+                Ok(user_info) => {
+                    update_user.set(UpdateUser::default());
+                    user_info_dispatch.set(StoredUserInfo { user_info });
+                    notice.set(Some(if email_changed {
+                        "Profile updated, check your inbox to verify the new email address".to_string()
+                    } else {
+                        "Profile updated".to_string()
+                    }));
+                    Ok(())
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let handle_change_password = {
+        let error_state = error_state.clone();
+        let notice = notice.clone();
+        let change_password = change_password.clone();
+        use_async(async move {
+            match services::user::change_password((*change_password).clone()).await {
+                Ok(status) => {
+                    change_password.set(ChangePassword::default());
+                    notice.set(Some("Password changed".to_string()));
+                    Ok(status)
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let handle_delete = {
+        let error_state = error_state.clone();
+        let delete_pass = delete_pass.clone();
+        use_async(async move {
+            match services::user::delete_account(DeleteUser { pass: (*delete_pass).clone() }).await {
+                Ok(status) => {
+                    // the sessions of the account are gone with it
+                    AuthStorage::clear();
+                    user_info_dispatch.set(StoredUserInfo { user_info: UserInfo::default() });
+                    Ok(status)
+                },
+                Err(error) => {
+                    error_state.set(Some(error.to_owned()));
+                    Err(error)
+                }
+            }
+        })
+    };
+
+    let update_onclick = {
+        let handle_update = handle_update.clone();
+        Callback::from(move |_| {
+            handle_update.run();
+        })
+    };
+
+    let change_password_onclick = {
+        let handle_change_password = handle_change_password.clone();
+        Callback::from(move |_| {
+            handle_change_password.run();
+        })
+    };
+
+    let delete_onclick = {
+        let handle_delete = handle_delete.clone();
+        Callback::from(move |_| {
+            handle_delete.run();
+        })
+    };
+
+    html! {
+        <div class="flex flex-col w-96 space-y-2 text-center text-slate-800 dark:text-slate-100">
+            if let Some(error) = (*error_state).to_owned() {
+                <ErrorMessage message={error.body().message} />
+            }
+            if let Some(message) = (*notice).to_owned() {
+                <p>{ message }</p>
+            }
+            <p>{"Profile"}</p>
+            <Input input_type="text" placeholder="New username" oninput={on_profile_input("username".to_string())}
+                value={update_user.username.clone().unwrap_or_default()} />
+            <Input input_type="email" placeholder="New email" oninput={on_profile_input("email".to_string())}
+                value={update_user.email.clone().unwrap_or_default()} />
+            <Button onclick={update_onclick} label="Update profile" />
+            <p>{"Password"}</p>
+            <Input input_type="password" placeholder="Current password" oninput={on_password_input("current_pass".to_string())}
+                value={change_password.current_pass.clone()} />
+            <Input input_type="password" placeholder="New password" oninput={on_password_input("pass".to_string())}
+                value={change_password.pass.clone()} />
+            <Button onclick={change_password_onclick} label="Change password" />
+            <p>{"Delete account"}</p>
+            <Input input_type="password" placeholder="Password" oninput={on_delete_input} value={(*delete_pass).to_owned()} />
+            <Button onclick={delete_onclick} label="Delete account" />
+        </div>
+    }
+}
@@ -0,0 +1,144 @@
+use types::{chat::{AddMember, CreateRoom, RoomInfo}, error::ApiError};
+use web_sys::HtmlInputElement;
+use yew::prelude::*;
+use yew_hooks::prelude::*;
+
+use crate::{components::{buttons::button::Button, error_message::ErrorMessage, input::Input}, services};
+
+#[derive(Clone, Properties, PartialEq)]
+pub struct Props {
+    // None while a conversation is open instead of a room
+    pub selected: Option<i64>,
+    pub on_select: Callback<i64>,
+    #[prop_or(String::new())]
+    pub class: String
+}
+
+#[function_component(RoomList)]
+pub fn room_list(props: &Props) -> Html {
+    let props = props.clone();
+    let error_state = use_state(|| None::<ApiError>);
+    let room_name = use_state(String::new);
+    let invite_name = use_state(String::new);
+
+    // Load rooms on mount and after changes
+    let rooms = {
+        let error_state = error_state.clone();
+        use_async_with_options(
+            async move {
+                match services::chat::get_rooms().await {
+                    Ok(rooms) => Ok(rooms),
+                    Err(error) => {
+                        error_state.set(Some(error.to_owned()));
+                        Err(error)
+                    }
+                }
+            },
+            UseAsyncOptions::enable_auto(),
+        )
+    };
+
+    let on_name_input = {
+        let error_state = error_state.clone();
+        let room_name = room_name.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            room_name.set(input.value());
+        })
+    };
+
+    let on_invite_input = {
+        let error_state = error_state.clone();
+        let invite_name = invite_name.clone();
+        Callback::from(move |e: InputEvent| {
+            let input: HtmlInputElement = e.target_unchecked_into();
+            if let Some(_) = *error_state {
+                error_state.set(None);
+            }
+            invite_name.set(input.value());
+        })
+    };
+
+    let create_room = |private: bool| {
+        let error_state = error_state.clone();
+        let room_name = room_name.clone();
+        let rooms = rooms.clone();
+        let on_select = props.on_select.clone();
+        Callback::from(move |_| {
+            let error_state = error_state.clone();
+            let room_name = room_name.clone();
+            let rooms = rooms.clone();
+            let on_select = on_select.clone();
+            yew::platform::spawn_local(async move {
+                match services::chat::create_room(CreateRoom { name: (*room_name).clone(), private }).await {
+                    Ok(room) => {
+                        room_name.set(String::new());
+                        rooms.run();
+                        on_select.emit(room.id);
+                    },
+                    Err(error) => error_state.set(Some(error))
+                }
+            });
+        })
+    };
+
+    let invite_onclick = {
+        let error_state = error_state.clone();
+        let invite_name = invite_name.clone();
+        let selected = props.selected;
+        Callback::from(move |_| {
+            let error_state = error_state.clone();
+            let invite_name = invite_name.clone();
+            let Some(selected) = selected else {
+                return;
+            };
+            yew::platform::spawn_local(async move {
+                match services::chat::add_member(selected, AddMember { username: (*invite_name).clone() }).await {
+                    Ok(_) => invite_name.set(String::new()),
+                    Err(error) => error_state.set(Some(error))
+                }
+            });
+        })
+    };
+
+    let room_list = rooms.data.clone().unwrap_or_default();
+    // invites are only needed for private rooms, anyone can join public ones
+    let selected_room: Option<RoomInfo> = room_list.iter().find(|room| Some(room.id) == props.selected).cloned();
+
+    html! {
+        <div class={props.class}>
+            if let Some(error) = (*error_state).to_owned() {
+                <ErrorMessage message={error.body().message} />
+            }
+            <div class="flex flex-col space-y-1 overflow-y-auto">
+                {
+                    for room_list.iter().map(|room| {
+                        let on_select = props.on_select.clone();
+                        let room_id = room.id;
+                        let color = if Some(room.id) == props.selected {
+                            "bg-slate-300 text-slate-900 dark:bg-slate-700 dark:text-slate-100"
+                        } else {
+                            "bg-slate-100 text-slate-800 hover:bg-slate-200 dark:bg-slate-900 dark:text-slate-100 dark:hover:bg-slate-800"
+                        };
+                        html! {
+                            <Button label={if room.private { format!("{} (private)", room.name) } else { format!("# {}", room.name) }}
+                                onclick={Callback::from(move |_| on_select.emit(room_id))} color={color} />
+                        }
+                    })
+                }
+            </div>
+            <Input input_type="text" placeholder="Room name" oninput={on_name_input} value={(*room_name).to_owned()} />
+            <div class="flex flex-row space-x-2">
+                <Button label="Create" onclick={create_room(false)} />
+                <Button label="Create private" onclick={create_room(true)} />
+            </div>
+            if selected_room.is_some_and(|room| room.private) {
+                <Input input_type="text" placeholder="Invite by username or email" oninput={on_invite_input} value={(*invite_name).to_owned()} />
+                <Button label="Invite" onclick={invite_onclick} />
+            }
+        </div>
+    }
+}
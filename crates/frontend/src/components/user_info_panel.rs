@@ -25,7 +25,10 @@ pub fn user_info_panel() -> Html {
                 {format!("Email: {}", user_info.email.clone())}
             </p>
             <p>
-                {format!("Is Admin: {}", user_info.is_admin.clone())}
+                {format!("Email Verified: {}", user_info.email_verified)}
+            </p>
+            <p>
+                {format!("Roles: {}", user_info.roles.join(", "))}
             </p>
         </div>
     }
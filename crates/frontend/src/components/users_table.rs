@@ -66,7 +66,7 @@ pub fn users_table() -> Html {
                         <th>{"UUID"}</th>
                         <th>{"Username"}</th>
                         <th>{"Email"}</th>
-                        <th>{"Admin"}</th>
+                        <th>{"Roles"}</th>
                         <th></th>
                     </tr>
                 </thead>
@@ -79,7 +79,7 @@ pub fn users_table() -> Html {
                                 <td>{user.uuid}</td>
                                 <td>{user.username}</td>
                                 <td>{user.email}</td>
-                                <td>{user.is_admin.to_string()}</td>
+                                <td>{user.roles.join(", ")}</td>
                                 <td><Button color="bg-slate-200 text-slate-800 hover:bg-slate-300 dark:bg-slate-800 dark:text-slate-100 dark:hover:bg-slate-700"
                                         label="Delete" onclick={move |_| {onclick.emit(delete_id.clone());}}/></td>
                             </tr>
@@ -1,17 +1,17 @@
 use gloo_console::{error, log};
 
 use reqwest::StatusCode;
-use types::user::{LoginUser, RegisterUser, ResetUser, UserInfo};
+use types::{auth::{AuthErrorType, MfaChallenge, MfaCode, MfaLogin, RecoveryCodes, TotpEnrollment, TotpStatus}, error::ApiError, user::{LoginUser, RegisterUser, ResetUser, UserInfo}, validation::Validate};
 
-use super::{get_base_url, get_http_client, AuthError, AuthRequest, AuthStorage};
+use super::{error_from_response, get_base_url, get_http_client, AuthRequest, AuthStorage};
 
-pub async fn test_auth_route() -> Result<StatusCode, AuthError> {
+pub async fn test_auth_route() -> Result<StatusCode, ApiError> {
     let request_result = AuthRequest::new(
         get_http_client().get(get_base_url() + "/auth/test")
     ).send().await;
     // Send request to test auth route
     if let Err(_) = request_result {
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
     // Unwrap resonse from request_result
     let response = request_result.unwrap();
@@ -19,14 +19,14 @@ pub async fn test_auth_route() -> Result<StatusCode, AuthError> {
 
     // Check if status is success
     if !status.is_success() {
-        return Err(AuthError::from_response(response).await);
+        return Err(error_from_response(response).await);
     }
 
     // Extract text from body and log to console
     let text_result = response.text().await;
     if let Err(error) = text_result {
         error!(format!("Error with parsing body as text: {error}"));
-        Err(AuthError::default())
+        Err(ApiError::unavailable())
     } else {
         let text = text_result.unwrap();
         log!(format!("{text}"));
@@ -34,7 +34,10 @@ pub async fn test_auth_route() -> Result<StatusCode, AuthError> {
     }
 }
 
-pub async fn register_user(user: RegisterUser) -> Result<UserInfo, AuthError> {
+pub async fn register_user(user: RegisterUser) -> Result<UserInfo, ApiError> {
+    // run the server's validation first, the breached password list is only checked by the server
+    user.validate().map_err(ApiError::validation)?;
+
     // Send register data to server
     let request_result = get_http_client()
         .post(get_base_url() + "/auth/register")
@@ -42,7 +45,7 @@ pub async fn register_user(user: RegisterUser) -> Result<UserInfo, AuthError> {
         .send().await;
     if let Err(error) = request_result {
         error!("Error with request: {}", error.to_string());
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap response from request_result
@@ -51,7 +54,7 @@ pub async fn register_user(user: RegisterUser) -> Result<UserInfo, AuthError> {
 
     // Check if status is success
     if !status.is_success() {
-        return Err(AuthError::from_response(response).await);
+        return Err(error_from_response(response).await);
     }
 
     // Extract auth requester token from headers and store in local browser storage
@@ -62,7 +65,7 @@ pub async fn register_user(user: RegisterUser) -> Result<UserInfo, AuthError> {
     let json_result = response.json::<UserInfo>().await;
     if let Err(error) = json_result {
         error!("Error parsing body: {}", error.to_string());
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap JSON result and return as OK result
@@ -70,12 +73,61 @@ pub async fn register_user(user: RegisterUser) -> Result<UserInfo, AuthError> {
     return Ok(data);
 }
 
-pub async fn login_user(user: LoginUser) -> Result<UserInfo, AuthError>  {
+// Outcome of a password login, which may still require a second factor
+pub enum LoginResult {
+    LoggedIn(UserInfo),
+    MfaRequired(MfaChallenge)
+}
+
+pub async fn login_user(user: LoginUser) -> Result<LoginResult, ApiError>  {
+    user.validate().map_err(ApiError::validation)?;
+
     // Send login data to server
     let request_result = get_http_client().post(get_base_url() + "/auth/login").json(&user).send().await;
     if let Err(error) = request_result {
         error!(format!("Error with request: {}", error.to_string()));
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Extract MFA pending token from json body if a second factor is required
+    if status == StatusCode::ACCEPTED {
+        let json_result = response.json::<MfaChallenge>().await;
+        if let Err(_) = json_result {
+            return Err(ApiError::unavailable());
+        }
+        return Ok(LoginResult::MfaRequired(json_result.unwrap()));
+    }
+
+    // Extract auth requester token from headers and store in local browser storage
+    let headers = response.headers();
+    AuthStorage::store_from_headers(headers);
+
+    // Extract user info from json body
+    let json_result = response.json::<UserInfo>().await;
+    if let Err(_) = json_result {
+        return Err(ApiError::unavailable());
+    }
+
+    // Unwrap JSON result and return as OK result
+    let data = json_result.unwrap();
+    return Ok(LoginResult::LoggedIn(data));
+}
+
+pub async fn login_mfa(mfa_login: MfaLogin) -> Result<UserInfo, ApiError> {
+    // Send MFA pending token and code to server
+    let request_result = get_http_client().post(get_base_url() + "/auth/login/mfa").json(&mfa_login).send().await;
+    if let Err(error) = request_result {
+        error!(format!("Error with request: {}", error.to_string()));
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap response from request_result
@@ -84,7 +136,7 @@ pub async fn login_user(user: LoginUser) -> Result<UserInfo, AuthError>  {
 
     // Check if status is success
     if !status.is_success() {
-        return Err(AuthError::from_response(response).await);
+        return Err(error_from_response(response).await);
     }
 
     // Extract auth requester token from headers and store in local browser storage
@@ -94,7 +146,7 @@ pub async fn login_user(user: LoginUser) -> Result<UserInfo, AuthError>  {
     // Extract user info from json body
     let json_result = response.json::<UserInfo>().await;
     if let Err(_) = json_result {
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap JSON result and return as OK result
@@ -102,11 +154,13 @@ pub async fn login_user(user: LoginUser) -> Result<UserInfo, AuthError>  {
     return Ok(data);
 }
 
-pub async fn reset_user(user: ResetUser, key: String) -> Result<StatusCode, AuthError> {
+pub async fn reset_user(user: ResetUser, key: String) -> Result<StatusCode, ApiError> {
+    user.validate().map_err(ApiError::validation)?;
+
     let request_result = get_http_client().post(get_base_url() + &format!("/auth/reset/{key}")).json(&user).send().await;
     if let Err(error) = request_result {
         error!(format!("Error with request: {}", error.to_string()));
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap response from request_result
@@ -115,7 +169,7 @@ pub async fn reset_user(user: ResetUser, key: String) -> Result<StatusCode, Auth
 
     // Check if status is success
     if !status.is_success() {
-        return Err(AuthError::from_response(response).await);
+        return Err(error_from_response(response).await);
     }
 
     // Clear auth storage
@@ -123,11 +177,11 @@ pub async fn reset_user(user: ResetUser, key: String) -> Result<StatusCode, Auth
     return Ok(status);
 }
 
-pub async fn request_reset(email: String) -> Result<StatusCode, AuthError> {
+pub async fn request_reset(email: String) -> Result<StatusCode, ApiError> {
     let request_result = get_http_client().post(get_base_url() + "/auth/reset").body(email).send().await;
     if let Err(error) = request_result {
         error!(format!("Error with request: {}", error.to_string()));
-        return Err(AuthError::default());
+        return Err(ApiError::unavailable());
     }
 
     // Unwrap response from request_result
@@ -136,7 +190,7 @@ pub async fn request_reset(email: String) -> Result<StatusCode, AuthError> {
 
     // Check if status is success
     if !status.is_success() {
-        return Err(AuthError::from_response(response).await);
+        return Err(error_from_response(response).await);
     }
 
     // Clear auth storage
@@ -144,7 +198,162 @@ pub async fn request_reset(email: String) -> Result<StatusCode, AuthError> {
     return Ok(status);
 }
 
-pub fn logout_user() {
+pub async fn verify_email(key: String) -> Result<StatusCode, ApiError> {
+    let request_result = get_http_client().post(get_base_url() + &format!("/auth/verify/{key}")).send().await;
+    if let Err(error) = request_result {
+        error!(format!("Error with request: {}", error.to_string()));
+        return Err(ApiError::unavailable());
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    return Ok(status);
+}
+
+pub async fn resend_verification(email: String) -> Result<StatusCode, ApiError> {
+    let request_result = get_http_client().post(get_base_url() + "/auth/verify/resend").body(email).send().await;
+    if let Err(error) = request_result {
+        error!(format!("Error with request: {}", error.to_string()));
+        return Err(ApiError::unavailable());
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    return Ok(status);
+}
+
+async fn send_logout(path: &str) -> Result<StatusCode, ApiError> {
+    // Revoke session server side with auth requester token
+    let requester_token = AuthStorage::get_requester_token();
     // Clear local auth storage to remove auth tokens
     AuthStorage::clear();
+    if let Err(_) = requester_token {
+        return Err(ApiError::auth(AuthErrorType::InvalidToken));
+    }
+    let request_result = get_http_client()
+        .post(get_base_url() + path)
+        .bearer_auth(requester_token.unwrap().to_string())
+        .send().await;
+    if let Err(error) = request_result {
+        error!(format!("Error with request: {}", error.to_string()));
+        return Err(ApiError::unavailable());
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(status)
+}
+
+pub async fn logout_user() -> Result<StatusCode, ApiError> {
+    send_logout("/auth/logout").await
+}
+
+pub async fn logout_all_sessions() -> Result<StatusCode, ApiError> {
+    send_logout("/auth/logout-all").await
+}
+
+pub async fn get_totp_status() -> Result<TotpStatus, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().get(get_base_url() + "/mfa/totp")
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+
+    // Check if status is success
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Extract TOTP status from json body
+    match response.json::<TotpStatus>().await {
+        Ok(totp_status) => Ok(totp_status),
+        Err(_) => Err(ApiError::unavailable())
+    }
+}
+
+pub async fn enroll_totp() -> Result<TotpEnrollment, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/mfa/totp/enroll")
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+
+    // Check if status is success
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Extract secret and otpauth uri from json body
+    match response.json::<TotpEnrollment>().await {
+        Ok(enrollment) => Ok(enrollment),
+        Err(_) => Err(ApiError::unavailable())
+    }
+}
+
+pub async fn confirm_totp(mfa_code: MfaCode) -> Result<RecoveryCodes, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/mfa/totp/confirm").json(&mfa_code)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+
+    // Check if status is success
+    if !response.status().is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Extract one-time recovery codes from json body
+    match response.json::<RecoveryCodes>().await {
+        Ok(recovery_codes) => Ok(recovery_codes),
+        Err(_) => Err(ApiError::unavailable())
+    }
+}
+
+pub async fn disable_totp(mfa_code: MfaCode) -> Result<StatusCode, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/mfa/totp/disable").json(&mfa_code)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(status)
 }
\ No newline at end of file
@@ -0,0 +1,217 @@
+use gloo_console::error;
+use reqwest::StatusCode;
+use types::{chat::{AddMember, ChatMessage, Conversation, CreateRoom, DirectMessage, RoomInfo, StartConversation}, error::ApiError, validation::Validate};
+
+use super::{error_from_response, get_base_url, get_http_client, AuthRequest};
+
+// page of room history older than before, oldest first
+pub async fn get_messages(room: i64, before: Option<i64>, limit: i64) -> Result<Vec<ChatMessage>, ApiError> {
+    let mut query = vec![("room", room.to_string()), ("limit", limit.to_string())];
+    if let Some(before) = before {
+        query.push(("before", before.to_string()));
+    }
+    let request_result = AuthRequest::new(
+        get_http_client().get(get_base_url() + "/chat/messages").query(&query)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Vec<ChatMessage>>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+// public rooms and the private rooms the user was added to
+pub async fn get_rooms() -> Result<Vec<RoomInfo>, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().get(get_base_url() + "/chat/rooms")
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Vec<RoomInfo>>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+pub async fn create_room(room: CreateRoom) -> Result<RoomInfo, ApiError> {
+    room.validate().map_err(ApiError::validation)?;
+
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/chat/rooms").json(&room)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<RoomInfo>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+pub async fn add_member(room: i64, member: AddMember) -> Result<StatusCode, ApiError> {
+    member.validate().map_err(ApiError::validation)?;
+
+    let request_result = AuthRequest::new(
+        get_http_client().post(format!("{}/chat/rooms/{room}/members", get_base_url())).json(&member)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(status)
+}
+
+// conversations with other users and their unread counts, most recently active first
+pub async fn get_conversations() -> Result<Vec<Conversation>, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().get(get_base_url() + "/chat/direct")
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Vec<Conversation>>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+pub async fn start_conversation(conversation: StartConversation) -> Result<Conversation, ApiError> {
+    conversation.validate().map_err(ApiError::validation)?;
+
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/chat/direct").json(&conversation)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Conversation>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+// page of a conversation older than before, oldest first
+pub async fn get_direct_messages(user_uuid: &str, before: Option<i64>, limit: i64) -> Result<Vec<DirectMessage>, ApiError> {
+    let mut query = vec![("limit", limit.to_string())];
+    if let Some(before) = before {
+        query.push(("before", before.to_string()));
+    }
+    let request_result = AuthRequest::new(
+        get_http_client().get(format!("{}/chat/direct/{user_uuid}/messages", get_base_url())).query(&query)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Vec<DirectMessage>>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+pub async fn mark_read(user_uuid: &str) -> Result<StatusCode, ApiError> {
+    let request_result = AuthRequest::new(
+        get_http_client().post(format!("{}/chat/direct/{user_uuid}/read", get_base_url()))
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(status)
+}
@@ -0,0 +1,29 @@
+use reqwest::{Method, StatusCode, Url};
+use types::jobs::JobInfo;
+
+use super::{get_base_url, get_http_client, AuthRequest};
+
+pub async fn get_jobs() -> Result<(StatusCode, Vec<JobInfo>), StatusCode> {
+    let mut request = AuthRequest::new(get_http_client()
+        .request(Method::GET, Url::parse(&(get_base_url() + "/jobs")).unwrap()));
+    // Unwrap request and extract status as owned value
+    let response = request.send().await;
+    if let Err(_) = response {
+        return Err(StatusCode::UNAUTHORIZED)
+    }
+    let response = response.unwrap();
+    let status = response.status();
+    if !status.is_success() {
+        return Err(status);
+    }
+
+    // Parse body as JSON
+    let json_result = response.json::<Vec<JobInfo>>().await;
+    if let Err(error) = json_result {
+        return Err(error.status().unwrap_or_default());
+    }
+
+    // Return vec of jobs
+    let jobs = json_result.unwrap();
+    Ok((status, jobs))
+}
@@ -4,11 +4,13 @@ use gloo_console::error;
 use gloo_storage::{Storage, errors::StorageError};
 use once_cell::sync::OnceCell;
 use reqwest::{header::{HeaderMap, AUTHORIZATION}, Client, RequestBuilder, Response, StatusCode, Url};
-use types::auth::{AuthErrorBody, AuthErrorType, AuthToken};
+use types::{auth::{AuthErrorType, AuthToken, REQUESTER_TOKEN_HEADER}, error::{ApiError, ApiErrorBody}};
 
 
 pub mod auth;
 pub mod user;
+pub mod jobs;
+pub mod chat;
 
 static HTTP_CLIENT: OnceCell<Client> = OnceCell::new();
 static BASE_URL: OnceCell<String> = OnceCell::new();
@@ -45,11 +47,11 @@ impl AuthRequest {
             request_builder
         }
     }
-    async fn request_auth_token() -> Result<StatusCode, AuthError> {
+    async fn request_auth_token() -> Result<StatusCode, ApiError> {
         // Build auth header from token
         let auth_token_result = AuthStorage::get_requester_token();
         if let Err(_) = &auth_token_result {
-            return Err(AuthError::from_error_type(AuthErrorType::InvalidToken))
+            return Err(ApiError::auth(AuthErrorType::InvalidToken))
         }
         let auth_token = auth_token_result.unwrap();
     
@@ -60,7 +62,7 @@ impl AuthRequest {
         let request_result = request_builder.send().await;
         if let Err(error) = request_result {
             error!("Error with request: {}", error.to_string());
-            return Err(AuthError::default());
+            return Err(ApiError::unavailable());
         }
     
         // Unwrap response from request_result
@@ -71,36 +73,42 @@ impl AuthRequest {
     
         // Check if status is success
         if !status.is_success() {
-            return Err(AuthError::from_response(response).await);
+            return Err(error_from_response(response).await);
         }
     
         // Extract auth header from headers
         let headers = response.headers();
         let auth_header_result = headers.get(AUTHORIZATION);
         if let None = auth_header_result {
-            return Err(AuthError::from_error_type(AuthErrorType::TokenCreation));
+            return Err(ApiError::auth(AuthErrorType::TokenCreation));
         }
         let header = auth_header_result.unwrap();
         let header_str = header.to_str().unwrap_or("");
     
         // Store auth token
         AuthStorage::store_auth_token(AuthToken::from_string(header_str.to_string()));
+
+        // Store rotated auth requester token, the previous one is no longer valid
+        if let Some(requester_header) = headers.get(REQUESTER_TOKEN_HEADER) {
+            let requester_str = requester_header.to_str().unwrap_or("");
+            AuthStorage::store_requester_token(AuthToken::from_string(requester_str.to_string()));
+        }
         Ok(status)
     }
     
-    async fn refresh_token(&mut self) -> Result<(), AuthError> {
+    async fn refresh_token(&mut self) -> Result<(), ApiError> {
         let auth_request_result = AuthRequest::request_auth_token().await;
         if let Err(auth_error) = auth_request_result {
             return Err(auth_error);
         }
         let token = AuthStorage::get_auth_token();
         if let Err(_storage_error) = token {
-            return Err(AuthError::from_error_type(AuthErrorType::InvalidToken));
+            return Err(ApiError::auth(AuthErrorType::InvalidToken));
         }
         self.token = token.unwrap();
         Ok(())
     }
-    pub async fn send(&mut self) -> Result<Response, AuthError> {
+    pub async fn send(&mut self) -> Result<Response, ApiError> {
         let refresh_result = self.refresh_token().await;
         if let Err(refresh_error) = refresh_result {
             return Err(refresh_error);
@@ -109,7 +117,7 @@ impl AuthRequest {
             .bearer_auth(self.token.clone().to_string())
             .send().await;
         if let Err(_error) = response {
-            return Err(AuthError::from_error_type(AuthErrorType::BadRequest));
+            return Err(ApiError::unavailable());
         }
         Ok(response.unwrap())
     }
@@ -159,36 +167,15 @@ impl AuthStorage {
     }
 }
 
-#[derive(Debug, Clone)]
-pub struct AuthError(types::auth::AuthError);
-
-impl AuthError {
-    pub async fn from_response(response: Response) -> Self {
-        let status: StatusCode = response.status();
-        let error_body = response.json::<AuthErrorBody>().await;
-        if let Err(_) = error_body {
-            return Self {
-                0: types::auth::AuthError::default()
-            }
-        }
-        return Self  {
-            0: types::auth::AuthError {
-                status: http::StatusCode::from_str(status.as_str()).unwrap(),
-                body: error_body.unwrap()
-            }
-        };
-    }
-    pub fn from_error_type(error_type: AuthErrorType) -> Self {
-        Self {
-            0: types::auth::AuthError::from_error_type(error_type)
-        }
-    }
-    pub fn body(&self) -> AuthErrorBody {
-        self.0.body.to_owned()
-    }
-    pub fn default() -> Self {
-        Self {
-            0: types::auth::AuthError::default()
-        }
-    }
+// Read the ApiError body of a failed response, unreadable bodies count as the server being unavailable
+pub async fn error_from_response(response: Response) -> ApiError {
+    let status: StatusCode = response.status();
+    let error_body = response.json::<ApiErrorBody>().await;
+    if let Err(_) = error_body {
+        return ApiError::unavailable();
+    }
+    return ApiError {
+        status: http::StatusCode::from_str(status.as_str()).unwrap(),
+        body: error_body.unwrap()
+    };
 }
\ No newline at end of file
@@ -1,7 +1,8 @@
+use gloo_console::error;
 use reqwest::{Method, StatusCode, Url};
-use types::user::UserInfo;
+use types::{error::ApiError, user::{ChangePassword, DeleteUser, UpdateUser, UserInfo}, validation::Validate};
 
-use super::{get_http_client, AuthRequest};
+use super::{error_from_response, get_base_url, get_http_client, AuthRequest, AuthStorage};
 
 pub async fn get_user_info() -> UserInfo {
     let mut request_builder = AuthRequest::new(get_http_client()
@@ -63,4 +64,78 @@ pub async fn delete_user(uuid: String) -> Result<StatusCode, StatusCode> {
 
     // Return status of response
     Ok(response.status())
-}
\ No newline at end of file
+}
+
+pub async fn update_profile(update_user: UpdateUser) -> Result<UserInfo, ApiError> {
+    update_user.validate().map_err(ApiError::validation)?;
+
+    // Send changed fields of the requesting user
+    let request_result = AuthRequest::new(
+        get_http_client().patch(get_base_url() + "/user").json(&update_user)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Extract updated user info from json body
+    let json_result = response.json::<UserInfo>().await;
+    if let Err(error) = json_result {
+        error!("Error parsing body: {}", error.to_string());
+        return Err(ApiError::unavailable());
+    }
+    Ok(json_result.unwrap())
+}
+
+pub async fn change_password(change_password: ChangePassword) -> Result<StatusCode, ApiError> {
+    change_password.validate().map_err(ApiError::validation)?;
+
+    let request_result = AuthRequest::new(
+        get_http_client().post(get_base_url() + "/user/password").json(&change_password)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+
+    // Other sessions were signed out, keep this one with the new auth requester token
+    AuthStorage::store_from_headers(response.headers());
+    Ok(status)
+}
+
+pub async fn delete_account(delete_user: DeleteUser) -> Result<StatusCode, ApiError> {
+    delete_user.validate().map_err(ApiError::validation)?;
+
+    let request_result = AuthRequest::new(
+        get_http_client().delete(get_base_url() + "/user/me").json(&delete_user)
+    ).send().await;
+    if let Err(error) = request_result {
+        return Err(error);
+    }
+
+    // Unwrap response from request_result
+    let response = request_result.unwrap();
+    let status = response.status();
+
+    // Check if status is success
+    if !status.is_success() {
+        return Err(error_from_response(response).await);
+    }
+    Ok(status)
+}
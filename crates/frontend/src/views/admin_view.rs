@@ -1,13 +1,18 @@
+use types::roles::JOBS_READ;
 use yew::prelude::*;
 
-use crate::components::users_table::UsersTable;
+use crate::{components::{jobs_table::JobsTable, users_table::UsersTable}, hooks::use_user_info};
 
 #[function_component(AdminView)]
 pub fn admin_view() -> Html {
+    let user_info = use_user_info();
 
     html! {
         <main class="col-span-12 row-span-24 flex flex-col items-center">
             <UsersTable />
+            if user_info.has_permission(JOBS_READ) {
+                <JobsTable />
+            }
         </main>
     }
-}
\ No newline at end of file
+}
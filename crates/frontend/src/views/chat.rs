@@ -1,13 +1,51 @@
+use types::chat::GENERAL_ROOM_ID;
 use yew::prelude::*;
-use crate::components::chat_window::ChatWindow;
+use crate::components::{chat_window::{ChatTarget, ChatWindow}, conversation_list::ConversationList, room_list::RoomList};
 
 #[function_component(Chat)]
 pub fn chat() -> Html {
+    let target = use_state(|| ChatTarget::Room(GENERAL_ROOM_ID));
+    // bumped to reload the conversation list and its unread counts
+    let conversations_refresh = use_state(|| 0u32);
+
+    let on_select_room = {
+        let target = target.clone();
+        Callback::from(move |room_id: i64| {
+            target.set(ChatTarget::Room(room_id));
+        })
+    };
+
+    let on_select_conversation = {
+        let target = target.clone();
+        let conversations_refresh = conversations_refresh.clone();
+        Callback::from(move |user_uuid: String| {
+            target.set(ChatTarget::Direct(user_uuid));
+            conversations_refresh.set(*conversations_refresh + 1);
+        })
+    };
+
+    let on_direct_activity = {
+        let conversations_refresh = conversations_refresh.clone();
+        Callback::from(move |_: ()| {
+            conversations_refresh.set(*conversations_refresh + 1);
+        })
+    };
+
+    let (selected_room, selected_conversation) = match &*target {
+        ChatTarget::Room(room_id) => (Some(*room_id), None),
+        ChatTarget::Direct(user_uuid) => (None, Some(user_uuid.clone()))
+    };
+
     html! {
-        <main class="col-span-12 row-span-24 h-full">
+        <main class="col-span-12 row-span-24 h-full flex flex-row">
+            <div class="flex flex-col w-64 h-full overflow-y-auto">
+                <RoomList class="flex flex-col p-4 space-y-2 text-sm" selected={selected_room} on_select={on_select_room} />
+                <ConversationList class="flex flex-col p-4 space-y-2 text-sm" selected={selected_conversation}
+                    on_select={on_select_conversation} refresh={*conversations_refresh} />
+            </div>
             <ChatWindow class="flex shrink flex-col w-full h-full
             ring-offset-background disabled:pointer-events-none
-            p-4 space-y-2 text-sm"/>
+            p-4 space-y-2 text-sm" target={(*target).clone()} on_direct_activity={on_direct_activity}/>
         </main>
     }
-}
\ No newline at end of file
+}
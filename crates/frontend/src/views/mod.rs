@@ -4,6 +4,7 @@ pub mod register;
 pub mod login;
 pub mod reset;
 pub mod request_reset;
+pub mod verify;
 pub mod not_found;
 pub mod admin_view;
 pub mod user_view;
\ No newline at end of file
@@ -1,20 +1,39 @@
-use types::user::UserInfo;
+use gloo_console::error;
+use types::{error::ApiError, user::UserInfo};
 use yew::prelude::*;
 use yew_hooks::use_async;
 use yewdux::functional::use_store;
 
-use crate::{components::{buttons::button::Button, error_message::ErrorMessage, user_info_panel::UserInfoPanel}, services::{self, AuthError}};
+use crate::{components::{auth::totp_setup::TotpSetup, buttons::button::Button, error_message::ErrorMessage, profile_form::ProfileForm, user_info_panel::UserInfoPanel}, services};
 use crate::hooks::StoredUserInfo;
 
 #[function_component(UserView)]
 pub fn user_view() -> Html {
     let (_user_info, user_info_dispatch) = use_store::<StoredUserInfo>();
-    let error_state = use_state(|| None::<AuthError>);
+    let error_state = use_state(|| None::<ApiError>);
 
     let logout_onclick = {
+        let user_info_dispatch = user_info_dispatch.clone();
         Callback::from(move |_| {
-            services::auth::logout_user();
-            user_info_dispatch.set(StoredUserInfo { user_info: UserInfo::default() });
+            let user_info_dispatch = user_info_dispatch.clone();
+            yew::platform::spawn_local(async move {
+                if let Err(error) = services::auth::logout_user().await {
+                    error!(format!("Error revoking session: {}", error.body().message));
+                }
+                user_info_dispatch.set(StoredUserInfo { user_info: UserInfo::default() });
+            });
+        })
+    };
+
+    let logout_all_onclick = {
+        Callback::from(move |_| {
+            let user_info_dispatch = user_info_dispatch.clone();
+            yew::platform::spawn_local(async move {
+                if let Err(error) = services::auth::logout_all_sessions().await {
+                    error!(format!("Error revoking sessions: {}", error.body().message));
+                }
+                user_info_dispatch.set(StoredUserInfo { user_info: UserInfo::default() });
+            });
         })
     };
 
@@ -49,8 +68,11 @@ pub fn user_view() -> Html {
             <UserInfoPanel />
             <div class="flex flex-row space-x-4">
                 <Button label={"Logout"} onclick={logout_onclick} />
+                <Button label={"Logout Everywhere"} onclick={logout_all_onclick} />
                 <Button onclick={test_onclick} label={"Test Auth"} />
             </div>
+            <ProfileForm />
+            <TotpSetup />
         </div>
     }
 }
\ No newline at end of file
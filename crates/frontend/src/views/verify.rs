@@ -0,0 +1,11 @@
+use yew::prelude::*;
+use crate::components::auth::verify_email_form::VerifyEmailForm;
+
+#[function_component(Verify)]
+pub fn verify() -> Html {
+    html! {
+        <div class="col-span-12 row-span-24 flex flex-col justify-center items-center h-full space-y-4">
+            <VerifyEmailForm />
+        </div>
+    }
+}
@@ -0,0 +1,156 @@
+use std::{
+    io::{self, BufRead, Write},
+    sync::Arc
+};
+
+use argh::FromArgs;
+
+use crate::{
+    config::Config,
+    pool::{self, Backend},
+    schema,
+    state::AppState,
+    strategies::{keys, passwords, sessions}
+};
+
+mod users;
+
+#[derive(FromArgs)]
+/// Chat server, serves the API when run without a subcommand.
+pub struct Cli {
+    #[argh(subcommand)]
+    pub command: Option<Command>
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+pub enum Command {
+    Serve(ServeCommand),
+    Migrate(MigrateCommand),
+    User(users::UserCommand),
+    Sessions(SessionsCommand),
+    Config(ConfigCommand)
+}
+
+#[derive(FromArgs)]
+/// Serve the REST and websocket API.
+#[argh(subcommand, name = "serve")]
+pub struct ServeCommand {}
+
+#[derive(FromArgs)]
+/// Apply pending migrations to the configured database.
+#[argh(subcommand, name = "migrate")]
+pub struct MigrateCommand {}
+
+#[derive(FromArgs)]
+/// Manage login sessions.
+#[argh(subcommand, name = "sessions")]
+pub struct SessionsCommand {
+    #[argh(subcommand)]
+    command: SessionsSubcommand
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum SessionsSubcommand {
+    Revoke(RevokeSessionsCommand)
+}
+
+#[derive(FromArgs)]
+/// Revoke every session of a user, forcing them to log in again.
+#[argh(subcommand, name = "revoke")]
+struct RevokeSessionsCommand {
+    /// UUID of the user
+    #[argh(positional)]
+    uuid: String
+}
+
+#[derive(FromArgs)]
+/// Inspect configuration.
+#[argh(subcommand, name = "config")]
+pub struct ConfigCommand {
+    #[argh(subcommand)]
+    command: ConfigSubcommand
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum ConfigSubcommand {
+    Check(CheckConfigCommand)
+}
+
+#[derive(FromArgs)]
+/// Validate configuration and signing keys without starting the server.
+#[argh(subcommand, name = "check")]
+struct CheckConfigCommand {}
+
+// run an admin subcommand, exiting with an error message if it fails
+pub async fn run(command: Command, config: Arc<Config>) {
+    let result = match command {
+        Command::Serve(_) => unreachable!("serve is handled by main"),
+        Command::Migrate(_) => migrate(config).await,
+        Command::User(user_command) => users::run(user_command, config).await,
+        Command::Sessions(sessions_command) => match sessions_command.command {
+            SessionsSubcommand::Revoke(revoke) => revoke_sessions(config, revoke.uuid).await
+        },
+        Command::Config(config_command) => match config_command.command {
+            ConfigSubcommand::Check(_) => check_config(&config)
+        }
+    };
+    if let Err(error) = result {
+        eprintln!("{error}");
+        std::process::exit(1);
+    }
+}
+
+// connect to the configured database, refusing schemas the server would refuse to start with
+async fn connect(config: Arc<Config>, migrate: bool) -> Result<AppState, String> {
+    if config.database.backend == Backend::Memory {
+        return Err("The memory database only lives inside a running server, there is nothing to administer".to_string());
+    }
+    let (pool, users) = pool::create_pool(&config.database).await;
+    schema::prepare_schema(&pool, config.database.backend, migrate).await?;
+    // user commands hash and check passwords like the server does
+    passwords::load_passwords(&config.passwords);
+    Ok(AppState { config, pool, users, presence: Arc::default(), chat_hub: Arc::default() })
+}
+
+// read a password from stdin, so it stays out of shell history and process lists
+fn read_password() -> Result<String, String> {
+    eprint!("Password: ");
+    let _ = io::stderr().flush();
+    let mut pass = String::new();
+    io::stdin().lock().read_line(&mut pass)
+        .map_err(|error| format!("Could not read password: {error}"))?;
+    let pass = pass.trim_end_matches(['\r', '\n']).to_string();
+    if pass.is_empty() {
+        return Err("Password must not be empty".to_string());
+    }
+    Ok(pass)
+}
+
+async fn migrate(config: Arc<Config>) -> Result<(), String> {
+    let state = connect(config, true).await?;
+    let version = schema::current_version(&state.pool).await
+        .map_err(|error| format!("Could not read schema version: {error}"))?;
+    println!("Schema at version {}", version.unwrap_or(0));
+    Ok(())
+}
+
+async fn revoke_sessions(config: Arc<Config>, uuid: String) -> Result<(), String> {
+    let state = connect(config, false).await?;
+    let user = state.users.find_by_uuid(&uuid).await
+        .map_err(|_| format!("No user with UUID {uuid}"))?;
+    let result = sessions::revoke_db_user_sessions(&state.pool, user.uuid.clone()).await
+        .map_err(|error| format!("Could not revoke sessions: {error}"))?;
+    println!("Revoked {} sessions of {}", result.rows_affected(), user.username);
+    Ok(())
+}
+
+// configuration was validated when it loaded, signing keys and the breached password list are checked here without touching the database
+fn check_config(config: &Config) -> Result<(), String> {
+    keys::load_keys(&config.keys);
+    passwords::load_passwords(&config.passwords);
+    println!("Configuration is valid");
+    Ok(())
+}
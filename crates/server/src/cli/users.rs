@@ -0,0 +1,207 @@
+use std::{str::FromStr, sync::Arc};
+
+use argh::FromArgs;
+use types::{error::FieldError, roles::ADMIN_ROLE, user::{RegisterUser, User, UserInfo}, validation::{Validate, Validator}};
+
+use crate::{
+    config::Config,
+    repositories,
+    state::AppState,
+    strategies::{passwords, roles, users}
+};
+
+use super::{connect, read_password};
+
+#[derive(FromArgs)]
+/// Manage user accounts.
+#[argh(subcommand, name = "user")]
+pub struct UserCommand {
+    #[argh(subcommand)]
+    command: UserSubcommand
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum UserSubcommand {
+    Create(CreateCommand),
+    SetPassword(SetPasswordCommand),
+    Promote(PromoteCommand),
+    Demote(DemoteCommand),
+    List(ListCommand)
+}
+
+#[derive(FromArgs)]
+/// Create a verified user, reading the password from stdin.
+#[argh(subcommand, name = "create")]
+struct CreateCommand {
+    /// username of the new user
+    #[argh(option)]
+    username: String,
+    /// email address of the new user
+    #[argh(option)]
+    email: String,
+    /// grant the admin role
+    #[argh(switch)]
+    admin: bool
+}
+
+#[derive(FromArgs)]
+/// Replace the password of a user, reading it from stdin.
+#[argh(subcommand, name = "set-password")]
+struct SetPasswordCommand {
+    /// username or email of the user
+    #[argh(positional)]
+    user: String
+}
+
+#[derive(FromArgs)]
+/// Grant a role to a user.
+#[argh(subcommand, name = "promote")]
+struct PromoteCommand {
+    /// username or email of the user
+    #[argh(positional)]
+    user: String,
+    /// role to grant, defaults to admin
+    #[argh(option, default = "ADMIN_ROLE.to_string()")]
+    role: String
+}
+
+#[derive(FromArgs)]
+/// Remove a role from a user.
+#[argh(subcommand, name = "demote")]
+struct DemoteCommand {
+    /// username or email of the user
+    #[argh(positional)]
+    user: String,
+    /// role to remove, defaults to admin
+    #[argh(option, default = "ADMIN_ROLE.to_string()")]
+    role: String
+}
+
+#[derive(FromArgs)]
+/// List users with their roles.
+#[argh(subcommand, name = "list")]
+struct ListCommand {
+    /// output format, table or json
+    #[argh(option, default = "Format::Table")]
+    format: Format
+}
+
+enum Format {
+    Table,
+    Json
+}
+
+impl FromStr for Format {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "table" => Ok(Format::Table),
+            "json" => Ok(Format::Json),
+            _ => Err("expected table or json".to_string())
+        }
+    }
+}
+
+pub async fn run(command: UserCommand, config: Arc<Config>) -> Result<(), String> {
+    let state = connect(config, false).await?;
+    match command.command {
+        UserSubcommand::Create(create) => create_user(&state, create).await,
+        UserSubcommand::SetPassword(set_password) => set_user_password(&state, set_password.user).await,
+        UserSubcommand::Promote(promote) => promote_user(&state, promote.user, promote.role).await,
+        UserSubcommand::Demote(demote) => demote_user(&state, demote.user, demote.role).await,
+        UserSubcommand::List(list) => list_users(&state, list.format).await
+    }
+}
+
+async fn find_user(state: &AppState, username_or_email: &str) -> Result<User, String> {
+    state.users.find_by_username_or_email(username_or_email).await
+        .map_err(|_| format!("No user with username or email {username_or_email}"))
+}
+
+// same messages the API returns in a 422 response, one line per field
+fn field_errors(fields: Vec<FieldError>) -> String {
+    fields.into_iter().map(|field| format!("{}: {}", field.field, field.message)).collect::<Vec<_>>().join("\n")
+}
+
+async fn create_user(state: &AppState, create: CreateCommand) -> Result<(), String> {
+    let register_user = RegisterUser {
+        username: create.username,
+        pass: read_password()?,
+        email: create.email
+    };
+    register_user.validate_with(passwords::breached_passwords()).map_err(field_errors)?;
+    let user = users::insert_db_user(state.users.as_ref(), register_user).await.map_err(|error| if repositories::is_unique_violation(&error) {
+        "A user with that username or email already exists".to_string()
+    } else {
+        format!("Could not create user: {error}")
+    })?;
+    // accounts created by an operator do not need to confirm their address
+    state.users.set_email_verified(&user.uuid, user.email.as_str(), jsonwebtoken::get_current_timestamp() as i64).await
+        .map_err(|error| format!("Could not verify email of {}: {error}", user.username))?;
+    if create.admin {
+        roles::assign_db_user_role(&state.pool, user.uuid.clone(), ADMIN_ROLE.to_string()).await
+            .map_err(|error| format!("Could not grant admin role to {}: {error}", user.username))?;
+    }
+    println!("Created user {} with UUID {}", user.username, user.uuid);
+    Ok(())
+}
+
+async fn set_user_password(state: &AppState, username_or_email: String) -> Result<(), String> {
+    let mut user = find_user(state, &username_or_email).await?;
+    user.pass = read_password()?;
+    Validator::new()
+        .password("pass", &user.pass, Some(&user.username), passwords::breached_passwords())
+        .finish()
+        .map_err(field_errors)?;
+    let username = user.username.clone();
+    users::update_db_user(state.users.as_ref(), user).await
+        .map_err(|error| format!("Could not update password of {username}: {error}"))?;
+    println!("Updated password of {username}");
+    Ok(())
+}
+
+async fn promote_user(state: &AppState, username_or_email: String, role: String) -> Result<(), String> {
+    let user = find_user(state, &username_or_email).await?;
+    match roles::assign_db_user_role(&state.pool, user.uuid.clone(), role.clone()).await {
+        Ok(true) => {
+            println!("Granted {role} to {}", user.username);
+            Ok(())
+        },
+        Ok(false) => Err(format!("No role named {role}")),
+        Err(error) => Err(format!("Could not grant {role} to {}: {error}", user.username))
+    }
+}
+
+async fn demote_user(state: &AppState, username_or_email: String, role: String) -> Result<(), String> {
+    let user = find_user(state, &username_or_email).await?;
+    match roles::remove_db_user_role(&state.pool, user.uuid.clone(), role.clone()).await {
+        Ok(true) => {
+            println!("Removed {role} from {}", user.username);
+            Ok(())
+        },
+        Ok(false) => Err(format!("{} does not have {role}", user.username)),
+        Err(error) => Err(format!("Could not remove {role} from {}: {error}", user.username))
+    }
+}
+
+async fn list_users(state: &AppState, format: Format) -> Result<(), String> {
+    let all_users = users::get_all_users(state.users.as_ref()).await
+        .map_err(|error| format!("Could not list users: {error}"))?;
+    let mut user_infos: Vec<UserInfo> = Vec::with_capacity(all_users.len());
+    for user_info in all_users {
+        user_infos.push(roles::user_info_with_access(&state.pool, user_info).await
+            .map_err(|error| format!("Could not get roles: {error}"))?);
+    }
+    match format {
+        Format::Json => println!("{}", serde_json::to_string_pretty(&user_infos).unwrap()),
+        Format::Table => {
+            println!("{:<36}  {:<24}  {:<32}  {:<8}  ROLES", "UUID", "USERNAME", "EMAIL", "VERIFIED");
+            for user_info in user_infos {
+                println!("{:<36}  {:<24}  {:<32}  {:<8}  {}",
+                    user_info.uuid, user_info.username, user_info.email, user_info.email_verified, user_info.roles.join(","));
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,424 @@
+use std::{collections::{HashMap, HashSet}, env, fmt::Display, fs, net::SocketAddr, path::Path, str::FromStr, sync::Arc};
+
+use jsonwebtoken::Algorithm;
+use lettre::message::Mailbox;
+use tracing_subscriber::EnvFilter;
+
+use crate::{
+    mailer::{smtp::SmtpSecurity, MailTransport},
+    pool::Backend,
+    strategies::{passwords::HashAlgorithm, verification::VerificationPolicy},
+    telemetry::LogFormat
+};
+
+// config file read when CONFIG_FILE is not set, optional
+const DEFAULT_CONFIG_FILE: &str = "config.toml";
+
+// Server configuration, loaded once at boot
+#[derive(Debug)]
+pub struct Config {
+    pub server: ServerConfig,
+    pub company: CompanyConfig,
+    pub database: DatabaseConfig,
+    pub tokens: TokenConfig,
+    pub keys: KeyConfig,
+    pub passwords: PasswordConfig,
+    pub verification: VerificationConfig,
+    pub mail: MailConfig,
+    pub jobs: JobConfig,
+    pub rate_limit: RateLimitConfig,
+    pub lockout: LockoutConfig,
+    pub log: LogConfig
+}
+
+#[derive(Debug)]
+pub struct ServerConfig {
+    pub bind_address: SocketAddr,
+    // seconds in-flight requests, websockets and background jobs get to finish on shutdown
+    pub shutdown_timeout: u64
+}
+
+#[derive(Debug)]
+pub struct CompanyConfig {
+    // token issuer and name shown in emails
+    pub name: String,
+    // token audience and base of emailed links
+    pub domain: String
+}
+
+#[derive(Debug)]
+pub struct DatabaseConfig {
+    pub url: String,
+    // engine selected by the url scheme
+    pub backend: Backend,
+    // apply pending embedded migrations at boot instead of refusing to start
+    pub migrate: bool
+}
+
+#[derive(Debug)]
+pub struct TokenConfig {
+    // lifetimes in seconds
+    pub auth_expire: u64,
+    pub request_expire: u64,
+    pub mfa_expire: u64,
+    pub email_verify_expire: u64,
+    // seconds a rotated requester token is still accepted
+    pub session_rotation_grace: i64
+}
+
+#[derive(Debug)]
+pub struct KeyConfig {
+    pub algorithm: Algorithm,
+    pub key_id: String,
+    // HS256 secret
+    pub secret: Option<String>,
+    // RS256 and EdDSA PEM paths
+    pub private_key: Option<String>,
+    pub public_key: Option<String>,
+    // kid and PEM path of previously used public keys
+    pub previous_keys: Vec<(String, String)>
+}
+
+#[derive(Debug)]
+pub struct PasswordConfig {
+    pub hasher: HashAlgorithm,
+    // global salt of legacy bcrypt hashes
+    pub legacy_salt: Option<String>,
+    pub reset_expire: u64,
+    pub reset_sweep_interval: u64,
+    // file of breached passwords rejected for new passwords, one per line
+    pub breached_list: Option<String>
+}
+
+#[derive(Debug)]
+pub struct VerificationConfig {
+    pub policy: VerificationPolicy
+}
+
+#[derive(Debug)]
+pub struct MailConfig {
+    pub transport: MailTransport,
+    pub from: Mailbox,
+    pub file_dir: String,
+    pub smtp: SmtpConfig
+}
+
+#[derive(Debug)]
+pub struct SmtpConfig {
+    pub host: String,
+    pub port: u16,
+    pub tls: SmtpSecurity,
+    // credentials, either both or neither are set
+    pub credentials: Option<(String, String)>
+}
+
+#[derive(Debug)]
+pub struct JobConfig {
+    pub max_attempts: i32,
+    pub retry_base: i64,
+    pub poll_interval: u64,
+    // seconds completed and failed jobs are kept before they are purged
+    pub retention: i64,
+    // seconds a worker may run a job before other workers take it back, must outlast the slowest job
+    pub lease: i64
+}
+
+#[derive(Debug)]
+pub struct RateLimitConfig {
+    pub window: u64,
+    pub ip_max: u32,
+    pub target_max: u32
+}
+
+#[derive(Debug)]
+pub struct LockoutConfig {
+    pub threshold: i32,
+    pub base: i64,
+    pub max: i64
+}
+
+#[derive(Debug)]
+pub struct LogConfig {
+    pub format: LogFormat,
+    // tracing filter directives such as info,sqlx=warn
+    pub filter: String
+}
+
+// Reads settings from environment variables first and the config file second, collecting every problem
+struct Loader {
+    file: HashMap<String, String>,
+    used: HashSet<String>,
+    errors: Vec<String>
+}
+
+impl Loader {
+    fn lookup(&mut self, key: &str, env_name: &str) -> Option<String> {
+        self.used.insert(key.to_string());
+        env::var(env_name).ok().or_else(|| self.file.get(key).cloned())
+    }
+    fn parse<T: FromStr>(&mut self, key: &str, env_name: &str, value: &str) -> Option<T>
+    where T::Err: Display {
+        match value.parse() {
+            Ok(parsed) => Some(parsed),
+            Err(error) => {
+                self.errors.push(format!("{key} ({env_name}): cannot parse \"{value}\": {error}"));
+                None
+            }
+        }
+    }
+    // setting which may be left out
+    fn optional<T: FromStr>(&mut self, key: &str, env_name: &str) -> Option<T>
+    where T::Err: Display {
+        let value = self.lookup(key, env_name)?;
+        self.parse(key, env_name, &value)
+    }
+    // setting which must be configured
+    fn required<T: FromStr + Default>(&mut self, key: &str, env_name: &str) -> T
+    where T::Err: Display {
+        match self.lookup(key, env_name) {
+            Some(value) => self.parse(key, env_name, &value).unwrap_or_default(),
+            None => {
+                self.errors.push(format!("{key} ({env_name}): must be configured"));
+                T::default()
+            }
+        }
+    }
+    // setting with a default value
+    fn or<T: FromStr>(&mut self, key: &str, env_name: &str, default: T) -> T
+    where T::Err: Display {
+        self.optional(key, env_name).unwrap_or(default)
+    }
+    fn check(&mut self, valid: bool, message: String) {
+        if !valid {
+            self.errors.push(message);
+        }
+    }
+}
+
+impl Config {
+    // load config file and environment, returning a report of every problem found
+    fn load(path: &str, file: HashMap<String, String>) -> Result<Config, Vec<String>> {
+        let mut loader = Loader { file, used: HashSet::new(), errors: Vec::new() };
+
+        let server = ServerConfig {
+            bind_address: loader.or("server.bind_address", "BIND_ADDRESS", SocketAddr::from(([127, 0, 0, 1], 3001))),
+            shutdown_timeout: loader.or("server.shutdown_timeout", "SHUTDOWN_TIMEOUT", 30)
+        };
+
+        let company = CompanyConfig {
+            name: loader.required("company.name", "COMPANY_NAME"),
+            domain: loader.required("company.domain", "COMPANY_DOMAIN")
+        };
+
+        let url: String = loader.required("database.url", "DATABASE_URL");
+        // a missing url is already reported
+        let backend = if url.is_empty() { None } else { loader.parse("database.url", "DATABASE_URL", &url) };
+        let database = DatabaseConfig {
+            url,
+            backend: backend.unwrap_or(Backend::Memory),
+            migrate: loader.or("database.migrate", "DATABASE_MIGRATE", false)
+        };
+
+        let tokens = TokenConfig {
+            auth_expire: loader.required("tokens.auth_expire", "AUTH_TOKEN_EXPIRE"),
+            request_expire: loader.required("tokens.request_expire", "AUTH_REQUEST_TOKEN_EXPIRE"),
+            mfa_expire: loader.or("tokens.mfa_expire", "MFA_TOKEN_EXPIRE", 300),
+            email_verify_expire: loader.or("tokens.email_verify_expire", "EMAIL_VERIFY_EXPIRE", 3600 * 24),
+            session_rotation_grace: loader.or("tokens.session_rotation_grace", "SESSION_ROTATION_GRACE", 10)
+        };
+
+        let mut keys = KeyConfig {
+            algorithm: loader.or("keys.algorithm", "JWT_ALGORITHM", Algorithm::HS256),
+            key_id: loader.or("keys.key_id", "JWT_KEY_ID", "primary".to_string()),
+            secret: loader.optional("keys.secret", "AUTH_TOKEN_SECRET"),
+            private_key: loader.optional("keys.private_key", "JWT_PRIVATE_KEY"),
+            public_key: loader.optional("keys.public_key", "JWT_PUBLIC_KEY"),
+            previous_keys: Vec::new()
+        };
+        let previous_keys: String = loader.or("keys.previous_keys", "JWT_PREVIOUS_KEYS", String::new());
+        for entry in previous_keys.split(',').map(str::trim).filter(|entry| !entry.is_empty()) {
+            match entry.split_once('=') {
+                Some((kid, path)) => keys.previous_keys.push((kid.to_string(), path.to_string())),
+                None => loader.errors.push(format!("keys.previous_keys (JWT_PREVIOUS_KEYS): entry \"{entry}\" must be formatted as kid=path"))
+            }
+        }
+        match keys.algorithm {
+            Algorithm::HS256 => loader.check(keys.secret.is_some(),
+                "keys.secret (AUTH_TOKEN_SECRET): must be configured for HS256".to_string()),
+            Algorithm::RS256 | Algorithm::EdDSA => {
+                let mut key_files = vec![
+                    ("keys.private_key (JWT_PRIVATE_KEY)", keys.private_key.clone()),
+                    ("keys.public_key (JWT_PUBLIC_KEY)", keys.public_key.clone())
+                ];
+                key_files.extend(keys.previous_keys.iter()
+                    .map(|(_, path)| ("keys.previous_keys (JWT_PREVIOUS_KEYS)", Some(path.clone()))));
+                for (setting, path) in key_files {
+                    match path {
+                        Some(path) => loader.check(Path::new(&path).is_file(),
+                            format!("{setting}: cannot read key file \"{path}\"")),
+                        None => loader.errors.push(format!("{setting}: must be configured for {:?}", keys.algorithm))
+                    }
+                }
+            },
+            algorithm => loader.errors.push(format!("keys.algorithm (JWT_ALGORITHM): unsupported algorithm {:?}", algorithm))
+        }
+
+        let passwords = PasswordConfig {
+            hasher: loader.or("passwords.hasher", "PASSWORD_HASHER", HashAlgorithm::Argon2id),
+            legacy_salt: loader.optional("passwords.legacy_salt", "PASSWORD_SALT"),
+            reset_expire: loader.or("passwords.reset_expire", "PASSWORD_RESET_EXPIRE", 3600 * 24),
+            reset_sweep_interval: loader.or("passwords.reset_sweep_interval", "PASSWORD_RESET_SWEEP_INTERVAL", 3600),
+            breached_list: loader.optional("passwords.breached_list", "PASSWORD_BREACHED_LIST")
+        };
+        if let Some(path) = &passwords.breached_list {
+            loader.check(Path::new(path).is_file(),
+                format!("passwords.breached_list (PASSWORD_BREACHED_LIST): cannot read \"{path}\""));
+        }
+        loader.check(passwords.reset_sweep_interval > 0,
+            "passwords.reset_sweep_interval (PASSWORD_RESET_SWEEP_INTERVAL): must be greater than 0".to_string());
+
+        let verification = VerificationConfig {
+            policy: loader.or("verification.policy", "EMAIL_VERIFICATION_POLICY", VerificationPolicy::None)
+        };
+
+        let transport = loader.or("mail.transport", "MAIL_TRANSPORT", MailTransport::Smtp);
+        // sender defaults to a noreply address at the company domain
+        let from: Option<Mailbox> = match loader.lookup("mail.from", "MAIL_FROM") {
+            Some(from) => loader.parse("mail.from", "MAIL_FROM", &from),
+            // a missing company is already reported
+            None if company.name.is_empty() || company.domain.is_empty() => None,
+            None => loader.parse("mail.from", "MAIL_FROM", &format!("{} <noreply@{}>", company.name, company.domain))
+        };
+        let file_dir = loader.or("mail.file_dir", "MAIL_FILE_DIR", "mail".to_string());
+        let tls = loader.or("mail.smtp.tls", "SMTP_TLS", SmtpSecurity::StartTls);
+        let smtp_host: Option<String> = loader.optional("mail.smtp.host", "SMTP_HOST");
+        let smtp_port = loader.or("mail.smtp.port", "SMTP_PORT", tls.default_port());
+        let credentials = match (loader.optional("mail.smtp.username", "SMTP_USERNAME"), loader.optional("mail.smtp.password", "SMTP_PASSWORD")) {
+            (Some(username), Some(password)) => Some((username, password)),
+            (None, None) => None,
+            _ => {
+                loader.errors.push("mail.smtp.username (SMTP_USERNAME) and mail.smtp.password (SMTP_PASSWORD): must be configured together".to_string());
+                None
+            }
+        };
+        loader.check(transport != MailTransport::Smtp || smtp_host.is_some(),
+            "mail.smtp.host (SMTP_HOST): must be configured for the smtp transport".to_string());
+
+        let jobs = JobConfig {
+            max_attempts: loader.or("jobs.max_attempts", "JOB_MAX_ATTEMPTS", 8),
+            retry_base: loader.or("jobs.retry_base", "JOB_RETRY_BASE", 30),
+            poll_interval: loader.or("jobs.poll_interval", "JOB_POLL_INTERVAL", 5),
+            retention: loader.or("jobs.retention", "JOB_RETENTION", 604800),
+            lease: loader.or("jobs.lease", "JOB_LEASE", 600)
+        };
+        loader.check(jobs.max_attempts > 0, "jobs.max_attempts (JOB_MAX_ATTEMPTS): must be greater than 0".to_string());
+        loader.check(jobs.poll_interval > 0, "jobs.poll_interval (JOB_POLL_INTERVAL): must be greater than 0".to_string());
+        loader.check(jobs.retention >= 0, "jobs.retention (JOB_RETENTION): must not be negative".to_string());
+        loader.check(jobs.lease > 0, "jobs.lease (JOB_LEASE): must be greater than 0".to_string());
+
+        let rate_limit = RateLimitConfig {
+            window: loader.or("rate_limit.window", "RATE_LIMIT_WINDOW", 60),
+            ip_max: loader.or("rate_limit.ip_max", "RATE_LIMIT_IP_MAX", 20),
+            target_max: loader.or("rate_limit.target_max", "RATE_LIMIT_TARGET_MAX", 5)
+        };
+        loader.check(rate_limit.window > 0, "rate_limit.window (RATE_LIMIT_WINDOW): must be greater than 0".to_string());
+
+        let lockout = LockoutConfig {
+            threshold: loader.or("lockout.threshold", "LOCKOUT_THRESHOLD", 5),
+            base: loader.or("lockout.base", "LOCKOUT_BASE", 60),
+            max: loader.or("lockout.max", "LOCKOUT_MAX", 3600 * 24)
+        };
+        loader.check(lockout.threshold > 0, "lockout.threshold (LOCKOUT_THRESHOLD): must be greater than 0".to_string());
+
+        let log = LogConfig {
+            format: loader.or("log.format", "LOG_FORMAT", LogFormat::Pretty),
+            filter: loader.or("log.filter", "LOG_FILTER", "info".to_string())
+        };
+        if let Err(error) = EnvFilter::try_new(&log.filter) {
+            loader.check(false, format!("log.filter (LOG_FILTER): cannot parse \"{}\": {error}", log.filter));
+        }
+
+        // settings in the file nothing reads are most likely typos
+        let mut unknown: Vec<&String> = loader.file.keys().filter(|key| !loader.used.contains(*key)).collect();
+        unknown.sort();
+        let unknown: Vec<String> = unknown.into_iter().map(|key| format!("{key}: unknown setting in {path}")).collect();
+        loader.errors.extend(unknown);
+
+        match (loader.errors.is_empty(), from) {
+            (true, Some(from)) => Ok(Config {
+                server,
+                company,
+                database,
+                tokens,
+                keys,
+                passwords,
+                verification,
+                mail: MailConfig {
+                    transport,
+                    from,
+                    file_dir,
+                    smtp: SmtpConfig { host: smtp_host.unwrap_or_default(), port: smtp_port, tls, credentials }
+                },
+                jobs,
+                rate_limit,
+                lockout,
+                log
+            }),
+            _ => Err(loader.errors)
+        }
+    }
+}
+
+// read config file into dotted keys, a missing default file is treated as empty
+fn read_config_file(path: &str, explicit: bool) -> Result<HashMap<String, String>, String> {
+    let content = match fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(_) if !explicit && !Path::new(path).exists() => return Ok(HashMap::new()),
+        Err(error) => return Err(format!("Cannot read config file {path}: {error}"))
+    };
+    let table: toml::Table = content.parse()
+        .map_err(|error| format!("Cannot parse config file {path}: {error}"))?;
+    let mut values = HashMap::new();
+    flatten_table(String::new(), table, &mut values);
+    Ok(values)
+}
+
+fn flatten_table(prefix: String, table: toml::Table, values: &mut HashMap<String, String>) {
+    for (key, value) in table {
+        let key = if prefix.is_empty() { key } else { format!("{prefix}.{key}") };
+        match value {
+            toml::Value::Table(table) => flatten_table(key, table, values),
+            value => {
+                values.insert(key, flatten_value(value));
+            }
+        }
+    }
+}
+
+// render value the way it would be written in an environment variable
+fn flatten_value(value: toml::Value) -> String {
+    match value {
+        toml::Value::String(string) => string,
+        toml::Value::Array(array) => array.into_iter().map(flatten_value).collect::<Vec<String>>().join(","),
+        value => value.to_string()
+    }
+}
+
+// function for loading the config passed to the server at boot, exits with a report of every problem if invalid
+pub fn load_config() -> Arc<Config> {
+    let explicit_path = env::var("CONFIG_FILE").ok();
+    let path = explicit_path.clone().unwrap_or(DEFAULT_CONFIG_FILE.to_string());
+    let result = read_config_file(&path, explicit_path.is_some())
+        .map_err(|error| vec![error])
+        .and_then(|file| Config::load(&path, file));
+    match result {
+        Ok(config) => Arc::new(config),
+        Err(errors) => {
+            eprintln!("Invalid configuration, found {} problem(s):", errors.len());
+            for error in errors {
+                eprintln!("  - {error}");
+            }
+            std::process::exit(1);
+        }
+    }
+}
@@ -1,290 +1,444 @@
-use std::{collections::HashMap, env, fs, str::FromStr, sync::Arc, time::{Duration, SystemTime}};
+use std::str::FromStr;
 
 use axum::{
-    extract::{Path, Request, State}, http::StatusCode, middleware, routing::{get, post}, Json, Router
+    body::Bytes, extract::{Path, Request, State}, http::StatusCode, middleware, response::{IntoResponse, Response}, routing::{get, post}, Json, Router
 };
-use bcrypt::verify;
 use email_address::EmailAddress;
-use futures::lock::Mutex;
 use http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
-use lettre::{message::header::ContentType, transport::smtp::{authentication::Credentials, client::Tls}, Message, SmtpTransport, Transport};
-use rand::distributions::Alphanumeric;
-use rand::Rng;
-use types::{auth::{AuthErrorType, AuthToken}, user::{LoginUser, RegisterUser, ResetUser, UserInfo}};
+use jsonwebtoken::jwk::JwkSet;
+use types::{auth::{AuthErrorType, AuthToken, MfaChallenge, MfaLogin, REQUESTER_TOKEN_HEADER}, user::{LoginUser, RegisterUser, ResetUser, User, UserInfo}, validation::{Validate, Validator}};
 
-use crate::{middleware::token_authentication, strategies::{authentication::{AuthClaims, AuthError, AuthRequesterClaims, Claims}, users}};
-
-struct TimeStampedEmail {
-    time_stamp: SystemTime,
-    email: EmailAddress
-}
-struct ResetKeysState {
-    keys: Mutex<HashMap<String, TimeStampedEmail>>
-}
+use crate::{config::Config, error::ApiError, repositories, state::AppState, telemetry, middleware::{rate_limit::RateLimitLayer, token_authentication}, strategies::{authentication::{AuthClaims, AuthRequesterClaims, Claims, EmailVerificationClaims, MfaPendingClaims}, email, jobs, keys, lockout, mfa, password_resets, passwords, roles, sessions, users, verification}};
 
 // route function to nest endpoints in router
-pub fn routes() -> Router {
-    let keys = Mutex::new(HashMap::<String, TimeStampedEmail>::new());
-    let key_state = Arc::new(ResetKeysState{keys});
+pub fn routes(state: AppState) -> Router<AppState> {
     // create routes
     Router::new()
         // create nested router for routes requiring AuthClaims
         .nest("/test", Router::new()
             .route("/", get(test_auth_route)))
-            .layer(middleware::from_fn(token_authentication::authenticate_token::<AuthClaims>))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>))
         // create nested router for routes requiring AuthRequesterClaims
         .nest("/request", Router::new()
             .route("/",get(request_auth_token))
-            .layer(middleware::from_fn(token_authentication::authenticate_token::<AuthRequesterClaims>)))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthRequesterClaims>)))
+        .nest("/logout", Router::new()
+            .route("/", post(logout_user))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthRequesterClaims>)))
+        .nest("/logout-all", Router::new()
+            .route("/", post(logout_all_user_sessions))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthRequesterClaims>)))
         // routes that do not need middleware
-        .route("/login", post(login_user))
+        .route("/.well-known/jwks.json", get(get_jwks))
+        .route("/login", post(login_user)
+            .layer(RateLimitLayer::by_target(state.config.clone(), login_target)))
+        .route("/login/mfa", post(login_mfa)
+            .layer(RateLimitLayer::by_target(state.config.clone(), mfa_target)))
         .route("/register", post(register_user))
+        .nest("/verify", Router::new()
+            .route("/resend", post(resend_verification)
+                .layer(RateLimitLayer::by_target(state.config.clone(), email_target)))
+            .route("/:verify_key", post(verify_email)))
         .nest("/reset", Router::new()
-            .route("/", post(request_reset))
-            .route("/:reset_key", post(reset_password))
-            .with_state(key_state))
+            .route("/", post(request_reset)
+                .layer(RateLimitLayer::by_target(state.config.clone(), email_target)))
+            .route("/:reset_key", post(reset_password)))
+}
+
+// username or email targeted by a LoginUser body
+fn login_target(_config: &Config, body: &Bytes) -> Option<String> {
+    serde_json::from_slice::<LoginUser>(body).ok().map(|user| user.username)
+}
+
+// account targeted by the pending token of an MfaLogin body, which stays the same across challenges from new logins
+fn mfa_target(config: &Config, body: &Bytes) -> Option<String> {
+    let payload = serde_json::from_slice::<MfaLogin>(body).ok()?;
+    MfaPendingClaims::from_string(config, &payload.mfa_token).ok().map(|claims| claims.sub)
 }
 
-async fn test_auth_route(request: Request) -> Result<(StatusCode, String), AuthError> {
+// email targeted by a plain text email body
+fn email_target(_config: &Config, body: &Bytes) -> Option<String> {
+    std::str::from_utf8(body).ok().map(str::to_string)
+}
+
+async fn test_auth_route(request: Request) -> Result<(StatusCode, String), ApiError> {
     // generate AuthClaims from encoded x-claim header
     let claims = AuthClaims::from_header(request.headers());
-    println!("Authentication claims: {:?}", claims);
+    tracing::debug!(?claims, "Authentication claims");
     // return with OK Status and body containing verified response
     Ok((StatusCode::OK, "Auth verified".to_string()))
 }
 
-async fn request_auth_token(request: Request) -> Result<(StatusCode, HeaderMap), AuthError> {
+async fn request_auth_token(State(state): State<AppState>, request: Request) -> Result<(StatusCode, HeaderMap), ApiError> {
     // generate AuthRequesterClaims from encoded x-claim header
     let claims = AuthRequesterClaims::from_header(request.headers());
     // generate new AuthClaims token from UUID in AuthRequesterClaims
-    if let Ok(auth_claims) = AuthClaims::new(claims.sub.clone()).await {
+    if let Ok(auth_claims) = AuthClaims::new(&state, claims.sub.clone()).await {
         let token_result = auth_claims.generate_token();
         let auth_token: AuthToken;
         match token_result {
             Ok(token) => auth_token = token,
             Err(error) => {
-                println!("Error creating token for UUID {}: {:?}", claims.sub, error);
+                tracing::error!(user_uuid = %claims.sub, ?error, "Error creating token");
                 return Err(error)
             }
         }
-        // insert newly generated token into Authorization header
+        // rotate requester token so the one used for this request cannot be used again
+        let requester_token = claims.rotate(&state).await?.generate_token()?;
+        // insert newly generated tokens into Authorization and requester token headers
         let mut header_map = HeaderMap::new();
         header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
-        // respond to request with token in header
+        header_map.insert(REQUESTER_TOKEN_HEADER, HeaderValue::from_str(&requester_token.to_string()).unwrap());
+        // respond to request with tokens in header
         Ok((StatusCode::CREATED, header_map.clone()))
     } else {
-        Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+        Err(ApiError::auth(AuthErrorType::AccessDenied))
     }
 }
 
+// route for revoking the session of the supplied requester token
+async fn logout_user(State(state): State<AppState>, request: Request) -> Result<StatusCode, ApiError> {
+    // generate AuthRequesterClaims from encoded x-claim header
+    let claims = AuthRequesterClaims::from_header(request.headers());
+    claims.revoke(&state).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// route for revoking every session of the requesting user
+async fn logout_all_user_sessions(State(state): State<AppState>, request: Request) -> Result<StatusCode, ApiError> {
+    // generate AuthRequesterClaims from encoded x-claim header
+    let claims = AuthRequesterClaims::from_header(request.headers());
+    match sessions::revoke_db_user_sessions(&state.pool, claims.sub.clone()).await {
+        Ok(_) => Ok(StatusCode::NO_CONTENT),
+        Err(error) => {
+            tracing::error!(user_uuid = %claims.sub, %error, "Error revoking sessions");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// route for publishing public keys used to verify issued tokens
+async fn get_jwks() -> (StatusCode, Json<JwkSet>) {
+    (StatusCode::OK, Json(keys::jwks()))
+}
+
 // route for logging in user with provided LoginUser json
 async fn login_user(
+    State(state): State<AppState>,
     Json(payload): Json<LoginUser>,
-) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), AuthError> {
-    // check if supplied credentials are not empty
-    if payload.username.is_empty() || payload.pass.is_empty() {
-        return Err(AuthError::from_error_type(AuthErrorType::WrongCredentials));
+) -> Result<Response, ApiError> {
+    // check that both credentials were supplied
+    payload.validate().map_err(ApiError::validation)?;
+    // get user by username from database, an unknown username counts as a failed login
+    let user = match state.users.find_by_username_or_email(&payload.username).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => {
+            telemetry::record_login("failure");
+            return Err(ApiError::auth(AuthErrorType::UserDoesNotExist));
+        },
+        Err(error) => return Err(error.into())
+    };
+    // reject locked accounts before checking the password
+    if let Some(seconds) = lockout::locked_for(&user) {
+        return Err(ApiError::auth(AuthErrorType::AccountLocked).with_retry_after(seconds));
     }
-    // get user by username from database
-    let result = users::get_db_user_by_username_or_email(payload.username).await;
-    // if can't get user by username, return 400
-    if let Err(_) = result {
-        return Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist));
-    }
-    // unwrap result from DB as user object
-    let user = result.unwrap();
-    // verify supplied password is validated
-    if verify(payload.pass, &user.pass).unwrap() {
-        // build response user
-        let user_info = UserInfo::from_user(user);
-        // generate token from UserInfo uuid
-        let token_result = AuthRequesterClaims::new(user_info.uuid.clone()).await.unwrap().generate_token();
-        let auth_token: AuthToken;
-        match token_result {
-            Ok(token) => auth_token = token,
-            Err(error) => return Err(error)
+    // verify supplied password against stored hash
+    let verified = match passwords::verify_password(&payload.pass, &user.pass) {
+        Ok(verified) => verified,
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error verifying password");
+            return Err(ApiError::internal());
         }
-        // insert newly generated token into Authorization header
-        let mut header_map = HeaderMap::new();
-        header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
-        // respond to request with UserInfo in body
-        Ok((StatusCode::CREATED, header_map.clone(), axum::Json(user_info)))
+    };
+    if verified {
+        // upgrade legacy or outdated hashes to the configured hasher
+        if passwords::needs_rehash(&user.pass) {
+            let mut rehashed_user = user.clone();
+            rehashed_user.pass = payload.pass;
+            match users::update_db_user(state.users.as_ref(), rehashed_user).await {
+                Ok(_) => tracing::info!(user_uuid = %user.uuid, "Rehashed password"),
+                Err(error) => tracing::error!(user_uuid = %user.uuid, %error, "Error rehashing password")
+            }
+        }
+        // block unverified accounts when the verification policy requires it
+        if !verification::login_allowed(&state.config, &user) {
+            return Err(ApiError::auth(AuthErrorType::EmailNotVerified));
+        }
+        // require a second factor before issuing a requester token when TOTP is enabled
+        match mfa::get_db_user_totp(&state.pool, user.uuid.clone()).await {
+            Ok(Some(user_totp)) if user_totp.enabled => {
+                let mfa_token = MfaPendingClaims::new(&state, user.uuid.clone()).await?.generate_token()?;
+                // respond to request with MFA pending token in body
+                telemetry::record_login("mfa_required");
+                return Ok((StatusCode::ACCEPTED, Json(MfaChallenge { mfa_token: mfa_token.to_string() })).into_response());
+            },
+            Ok(_) => {},
+            Err(error) => {
+                tracing::error!(user_uuid = %user.uuid, %error, "Error getting TOTP");
+                return Err(ApiError::database());
+            }
+        }
+        login_response(&state, user).await
     } else {
-        // respond with wrong credentials error
-        return Err(AuthError::from_error_type(AuthErrorType::WrongCredentials));
+        // respond with wrong credentials error, or locked error once past the threshold
+        Err(failed_login_error(&state, &user, AuthErrorType::WrongCredentials).await)
     }
 }
 
+// count failed login attempt and build error to respond with
+async fn failed_login_error(state: &AppState, user: &User, error_type: AuthErrorType) -> ApiError {
+    match lockout::record_db_failed_login(&state.config, state.users.as_ref(), user).await {
+        Ok(Some(seconds)) => {
+            telemetry::record_login("locked");
+            ApiError::auth(AuthErrorType::AccountLocked).with_retry_after(seconds)
+        },
+        Ok(None) => {
+            telemetry::record_login("failure");
+            ApiError::auth(error_type)
+        },
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error recording failed login");
+            telemetry::record_login("failure");
+            ApiError::auth(error_type)
+        }
+    }
+}
 
-// handler for creating a new user
-async fn register_user(
-    Json(payload): Json<RegisterUser>,
-) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), AuthError> {
-    if payload.username.is_empty() || payload.pass.is_empty() || payload.email.is_empty() {
-        return Err(AuthError::from_error_type(AuthErrorType::MissingFields));
+// route for completing a login with an MFA pending token and TOTP or recovery code
+async fn login_mfa(
+    State(state): State<AppState>,
+    Json(payload): Json<MfaLogin>,
+) -> Result<Response, ApiError> {
+    // verify MFA pending token
+    let claims = MfaPendingClaims::from_string(&state.config, &payload.mfa_token)?;
+    claims.validate(&state).await?;
+    let user = match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    if let Some(seconds) = lockout::locked_for(&user) {
+        return Err(ApiError::auth(AuthErrorType::AccountLocked).with_retry_after(seconds));
     }
-    // validate email address before inserting
-    if !EmailAddress::is_valid(&payload.email) {
-        return Err(AuthError::from_error_type(AuthErrorType::InvalidEmail));
+    let user_totp = match mfa::get_db_user_totp(&state.pool, user.uuid.clone()).await {
+        Ok(Some(user_totp)) if user_totp.enabled => user_totp,
+        Ok(_) => return Err(ApiError::auth(AuthErrorType::MfaNotEnrolled)),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error getting TOTP");
+            return Err(ApiError::database());
+        }
+    };
+    // verify supplied code against TOTP secret or unused recovery codes
+    match mfa::verify_db_mfa_code(&state.config, &state.pool, user.uuid.clone(), user.email.to_string(), &user_totp.secret, &payload.code).await {
+        Ok(true) => login_response(&state, user).await,
+        Ok(false) => Err(failed_login_error(&state, &user, AuthErrorType::InvalidMfaCode).await),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error verifying MFA code");
+            Err(ApiError::database())
+        }
     }
-    // insert user into table
-    let db_result = users::insert_db_user(payload).await;
-    // handle db errors
-    if let Err(error) = db_result {
-        println!("Error creating user: {}", error);
-        if error.to_string().contains("duplicate key") {
-            return Err(AuthError::from_error_type(AuthErrorType::UserAlreadyExists))
+}
+
+// respond to a completed login with a new requester token
+async fn login_response(state: &AppState, user: User) -> Result<Response, ApiError> {
+    // clear failed attempts only once every factor succeeded
+    if user.failed_logins > 0 {
+        if let Err(error) = lockout::reset_db_failed_logins(state.users.as_ref(), &user).await {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error resetting failed logins");
         }
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError));
     }
-    // unwrap returned User object
-    let user = db_result.unwrap();
-    // build UserInfo to return from User object
-    let user_info = UserInfo::from_user(user);
+    // build response user with roles and permissions
+    let user_info = roles::user_info_with_access(&state.pool, UserInfo::from_user(user)).await
+        .map_err(|_| ApiError::database())?;
     // generate token from UserInfo uuid
-    let token_result = AuthRequesterClaims::new(user_info.uuid.clone()).await.unwrap().generate_token();
-    let auth_token: AuthToken;
-    match token_result {
-        Ok(token) => auth_token = token,
+    let auth_token = AuthRequesterClaims::new(state, user_info.uuid.clone()).await?.generate_token()?;
+    // insert newly generated token into Authorization header
+    let mut header_map = HeaderMap::new();
+    header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
+    telemetry::record_login("success");
+    // respond to request with UserInfo in body
+    Ok((StatusCode::CREATED, header_map, Json(user_info)).into_response())
+}
+
+
+// handler for creating a new user
+async fn register_user(
+    State(state): State<AppState>,
+    Json(payload): Json<RegisterUser>,
+) -> Result<(StatusCode, HeaderMap, Json<UserInfo>), ApiError> {
+    // validate username, password policy and email address before inserting
+    payload.validate_with(passwords::breached_passwords()).map_err(ApiError::validation)?;
+    // insert user into table
+    let user = match users::insert_db_user(state.users.as_ref(), payload).await {
+        Ok(user) => user,
+        Err(error) if repositories::is_unique_violation(&error) => return Err(ApiError::auth(AuthErrorType::UserAlreadyExists)),
         Err(error) => {
-            println!("Error creating token for UUID {}: {:?}", user_info.uuid, error);
-            return Err(AuthError::from_error_type(AuthErrorType::TokenCreation))
+            tracing::error!(%error, "Error creating user");
+            return Err(ApiError::database());
         }
+    };
+    // email verification link, registration still succeeds if sending fails and can be resent
+    if let Err(error) = verification::queue_verification_email(&state, &user).await {
+        tracing::error!(user_uuid = %user.uuid, %error, "Error queueing verification email");
     }
-    // insert parsed token into headermap
+    // build UserInfo to return from User object
+    let user_info = UserInfo::from_user(user.clone());
+    // unverified accounts only get a requester token if they may log in
     let mut header_map = HeaderMap::new();
-    header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
+    if verification::login_allowed(&state.config, &user) {
+        // generate token from UserInfo uuid
+        // creating the claims stores the session, which can fail like signing the token
+        let token_result = match AuthRequesterClaims::new(&state, user_info.uuid.clone()).await {
+            Ok(claims) => claims.generate_token(),
+            Err(error) => Err(error)
+        };
+        let auth_token: AuthToken;
+        match token_result {
+            Ok(token) => auth_token = token,
+            Err(error) => {
+                tracing::error!(user_uuid = %user_info.uuid, ?error, "Error creating token");
+                return Err(ApiError::auth(AuthErrorType::TokenCreation))
+            }
+        }
+        // insert parsed token into headermap
+        header_map.insert(AUTHORIZATION, HeaderValue::from_str(&auth_token.to_string()).unwrap());
+    }
     // respond to request with UserInfo in body
     Ok((StatusCode::CREATED, header_map.clone(), axum::Json(user_info)))
 }
 
-async fn request_reset(
-    State(state): State<Arc<ResetKeysState>>,
+// route for verifying email address with key from emailed link
+async fn verify_email(
+    State(state): State<AppState>,
+    Path(verify_key): Path<String>
+) -> Result<StatusCode, ApiError> {
+    // verify signature and expiry of the emailed token
+    let claims = EmailVerificationClaims::from_string(&state.config, &verify_key)
+        .map_err(|_| ApiError::auth(AuthErrorType::VerificationLinkInvalid))?;
+    let user = match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    if user.email_verified_at.is_some() {
+        return Err(ApiError::auth(AuthErrorType::EmailAlreadyVerified));
+    }
+    // links sent to a previous address cannot verify the current one
+    match state.users.set_email_verified(&user.uuid, &claims.eml, jsonwebtoken::get_current_timestamp() as i64).await {
+        Ok(true) => Ok(StatusCode::ACCEPTED),
+        Ok(false) => Err(ApiError::auth(AuthErrorType::VerificationLinkInvalid)),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error verifying email");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// route for sending a new verification link to an unverified email address
+async fn resend_verification(
+    State(state): State<AppState>,
     email_address: String
-) -> Result<StatusCode, AuthError> {
+) -> Result<StatusCode, ApiError> {
     // parse email string
-    let email_address = EmailAddress::from_str(&email_address);
-    if let Err(_) = email_address {
-        return Err(AuthError::from_error_type(AuthErrorType::InvalidEmail));
-    }
-    let email_address = email_address.unwrap();
+    let email_address = match EmailAddress::from_str(&email_address) {
+        Ok(email_address) => email_address,
+        Err(_) => return Err(ApiError::auth(AuthErrorType::InvalidEmail))
+    };
     // ensure user exists in db
-    if let Err(_) = users::get_db_user_by_username_or_email(email_address.to_string()).await {
-        return Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist));
-    }
-    // generate reset key and insert into state
-    let reset_key = gen_reset_key();
-    let mut keys = state.keys.lock().await;
-    // parse env variables for generating email content
-    let company_name =  env::var("COMPANY_NAME").unwrap();
-    let company_domain =  env::var("COMPANY_DOMAIN").unwrap();
-    keys.insert(reset_key.clone(), TimeStampedEmail{email: email_address.to_owned(), time_stamp: SystemTime::now()});
-    // free mutex
-    drop(keys);
-    // read html template from static path
-    let html = fs::read_to_string("crates/server/resources/reset_template.html");
-    if let Err(_) = html {
-        println!("Could not read password reset template!");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
-    }
-    // replace placeholder text in html with proper information
-    let html = html.unwrap()
-        .replace("{COMPANY_NAME}", &company_name)
-        .replace("{RESET_PASSWORD_URL}", &format!("{company_domain}/reset?key={reset_key}&email={email_address}"));
-    // build email
-    let email = Message::builder()
-        .from(format!("{} <noreply@{}>", company_name, company_domain).parse().unwrap())
-        .to(email_address.to_string().parse().unwrap())
-        .subject(format!("Password Reset Requested for {}", company_name))
-        .header(ContentType::TEXT_HTML)
-        .body(html);
-    if let Err(_) = email {
-        println!("Could not parse email!");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+    let user = match state.users.find_by_username_or_email(email_address.as_str()).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    if user.email_verified_at.is_some() {
+        return Err(ApiError::auth(AuthErrorType::EmailAlreadyVerified));
     }
-    let email = email.unwrap();
-    // generate smtp credentials from env vars
-    let smtp_username = env::var("SMTP_USERNAME").to_owned();
-    if let Err(_) = smtp_username {
-        println!("SMTP_USERNAME environment variable not configured!");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+    match verification::queue_verification_email(&state, &user).await {
+        Ok(_) => Ok(StatusCode::CREATED),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error queueing verification email");
+            Err(ApiError::internal())
+        }
     }
-    let smtp_username = smtp_username.unwrap();
-    let smtp_password = env::var("SMTP_PASSWORD").to_owned();
-    if let Err(_) = smtp_password {
-        println!("SMTP_PASSWORD environment variable not configured!");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+}
+
+async fn request_reset(
+    State(state): State<AppState>,
+    email_address: String
+) -> Result<StatusCode, ApiError> {
+    // parse email string
+    let Ok(email_address) = EmailAddress::from_str(&email_address) else {
+        return Err(ApiError::auth(AuthErrorType::InvalidEmail));
+    };
+    // ensure user exists in db
+    match state.users.find_by_username_or_email(email_address.as_str()).await {
+        Ok(_) => {},
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
     }
-    let smtp_password = smtp_password.unwrap();
-    let smtp_host = env::var("SMTP_HOST").to_owned();
-    if let Err(_) = smtp_host {
-        println!("SMTP_HOST environment variable not configured!");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+    // generate reset key and store its hash in db
+    let reset_key = password_resets::gen_reset_key();
+    if let Err(error) = password_resets::insert_db_password_reset(&state.config, &state.pool, &reset_key, email_address.to_string()).await {
+        tracing::error!(%error, "Error storing password reset key");
+        return Err(ApiError::database())
     }
-    let smtp_host = smtp_host.unwrap();
-    let creds = Credentials::new(smtp_username, smtp_password);
-    // build mailer and send email to user email address
-    let mailer = SmtpTransport::relay(&smtp_host)
-        .unwrap()
-        .tls(Tls::None)
-        .credentials(creds)
-        .build();
-    match mailer.send(&email) {
-        Ok(_) => println!("Reset email sent successfully to {email_address}"),
-        Err(e) => {
-            println!("Failed to send email to {email_address}: {e:?}");
-            return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+    // replace placeholder text in html template with proper information
+    let reset_url = format!("{}/reset?key={reset_key}&email={email_address}", state.config.company.domain);
+    let html = match email::render_template("reset_template.html", &[
+        ("{COMPANY_NAME}", &state.config.company.name),
+        ("{RESET_PASSWORD_URL}", &reset_url)
+    ]) {
+        Ok(html) => html,
+        Err(error) => {
+            tracing::error!(%error, "Error rendering reset email");
+            return Err(ApiError::internal())
+        }
+    };
+    // queue email to user email address for the job worker to send
+    match jobs::enqueue_email(&state.config, &state.pool, email_address.to_string(), format!("Password Reset Requested for {}", state.config.company.name), html).await {
+        Ok(_) => tracing::info!(email = %email_address, "Reset email queued"),
+        Err(error) => {
+            tracing::error!(email = %email_address, %error, "Error queueing reset email");
+            return Err(ApiError::database())
         }
     }
     Ok(StatusCode::CREATED)
 }
 
-fn gen_reset_key() -> String {
-    rand::thread_rng()
-    .sample_iter(&Alphanumeric)
-    .take(64)
-    .map(char::from)
-    .collect()
-}
-
 async fn reset_password(
-    State(key_state):  State<Arc<ResetKeysState>>,
+    State(state): State<AppState>,
     Path(reset_key): Path<String>,
     Json(reset_user): Json<ResetUser>
-) -> Result<StatusCode, AuthError> {
+) -> Result<StatusCode, ApiError> {
+    // validate email address and password policy before touching the reset key
+    reset_user.validate_with(passwords::breached_passwords()).map_err(ApiError::validation)?;
     // retrieve user from db using reset_user email_address field
-    let db_result = users::get_db_user_by_username_or_email(reset_user.email_address.to_string()).await;
-    if let Err(_) = db_result {
-        return Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist));
-    }
-    let mut user = db_result.unwrap();
-    // lock state mutex
-    let mut keys = key_state.keys.lock().await;
-    // get key by passed reset_key param
-    let key = keys.get(&reset_key);
-    if let None = key {
-        return Err(AuthError::from_error_type(AuthErrorType::ResetLinkInvalid));
-    }
-    let timestamped_email = key.unwrap();
-    // ensure timestamped_email email field is same as reset_user body email_address field
-    if timestamped_email.email != reset_user.email_address {
-        return Err(AuthError::from_error_type(AuthErrorType::ResetLinkInvalid));
-    }
-    // ensure reset link is not over 24 hours old
-    let expiration_time = 3600*24;
-    if SystemTime::now().duration_since(timestamped_email.time_stamp).unwrap() > Duration::from_secs(expiration_time) {
-        return Err(AuthError::from_error_type(AuthErrorType::ResetLinkInvalid));
+    let mut user = match state.users.find_by_username_or_email(reset_user.email_address.as_str()).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    // the password must also differ from the username, known only now
+    Validator::new()
+        .password("pass", &reset_user.pass, Some(&user.username), passwords::breached_passwords())
+        .finish()
+        .map_err(ApiError::validation)?;
+    // consume unexpired reset key issued for reset_user body email_address field
+    match password_resets::consume_db_password_reset(&state.pool, &reset_key, reset_user.email_address.clone()).await {
+        Ok(true) => {},
+        Ok(false) => return Err(ApiError::auth(AuthErrorType::ResetLinkInvalid)),
+        Err(error) => {
+            tracing::error!(%error, "Error consuming password reset key");
+            return Err(ApiError::database())
+        }
     }
     // update user pass field
     user.pass = reset_user.pass;
     // update db user
-    let db_result = users::update_db_user(user).await;
+    let db_result = users::update_db_user(state.users.as_ref(), user).await;
     if let Err(e) = db_result {
-        println!("{e}");
-        return Err(AuthError::from_error_type(AuthErrorType::ServerError))
+        tracing::error!(error = %e, "Error updating password");
+        return Err(ApiError::database())
     }
-    // remove reset key from state and drop mutex
-    keys.remove(&reset_key);
-    drop(keys);
     Ok(StatusCode::ACCEPTED)
 }
\ No newline at end of file
@@ -0,0 +1,236 @@
+use axum::{
+    extract::{Json, Path, Query, State}, http::StatusCode, middleware, routing::{get, post}, Router
+};
+use http::HeaderMap;
+use serde::Deserialize;
+use types::{auth::AuthErrorType, chat::{AddMember, ChatMessage, Conversation, CreateRoom, DirectMessage, Presence, RoomInfo, RoomMember, StartConversation, CHAT_HISTORY_MAX_LIMIT, GENERAL_ROOM_ID}, user::User, validation::Validate};
+
+use crate::{error::ApiError, middleware::token_authentication, repositories, state::AppState, strategies::{authentication::{AuthClaims, Claims}, chat, direct_messages, rooms, verification}};
+
+#[derive(Debug, Deserialize)]
+struct MessageQuery {
+    room: Option<i64>,
+    before: Option<i64>,
+    limit: Option<i64>
+}
+
+#[derive(Debug, Deserialize)]
+struct PageQuery {
+    before: Option<i64>,
+    limit: Option<i64>
+}
+
+// route function to nest endpoints in router
+pub fn routes(state: AppState) -> Router<AppState> {
+    // create routes
+    Router::new()
+        .route("/messages", get(get_messages))
+        .route("/rooms", get(get_rooms).post(create_room))
+        .route("/rooms/:id/join", post(join_room))
+        .route("/rooms/:id/leave", post(leave_room))
+        .route("/rooms/:id/members", get(get_members).post(add_member))
+        .route("/direct", get(get_conversations).post(start_conversation))
+        .route("/direct/:uuid/messages", get(get_direct_messages))
+        .route("/direct/:uuid/read", post(mark_read))
+        .route("/presence", get(get_presence))
+        .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>))
+}
+
+// requesting user, held to the same verification policy as the websocket
+async fn chat_user(state: &AppState, headers: &HeaderMap) -> Result<User, ApiError> {
+    let claims = AuthClaims::from_header(headers);
+    match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) if verification::chat_allowed(&state.config, &user) => Ok(user),
+        Ok(_) => Err(ApiError::auth(AuthErrorType::EmailNotVerified)),
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => Err(error.into())
+    }
+}
+
+// page through the history of a room, general by default, older messages by passing the oldest id received as before
+async fn get_messages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MessageQuery>
+) -> Result<(StatusCode, Json<Vec<ChatMessage>>), ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    let room = rooms::readable_room(&state.pool, query.room.unwrap_or(GENERAL_ROOM_ID), &user.uuid).await?;
+    let limit = query.limit.unwrap_or(chat::JOIN_HISTORY_LIMIT).clamp(1, CHAT_HISTORY_MAX_LIMIT);
+    match chat::get_db_messages(&state.pool, room.id, query.before, limit).await {
+        Ok(messages) => Ok((StatusCode::OK, Json(messages))),
+        Err(error) => {
+            tracing::error!(%error, "Error getting chat messages");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// public rooms and the private rooms the user is a member of
+async fn get_rooms(State(state): State<AppState>, headers: HeaderMap) -> Result<(StatusCode, Json<Vec<RoomInfo>>), ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    match rooms::get_db_rooms(&state.pool, &user.uuid).await {
+        Ok(rooms) => Ok((StatusCode::OK, Json(rooms))),
+        Err(error) => Err(error.into())
+    }
+}
+
+async fn create_room(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<CreateRoom>
+) -> Result<(StatusCode, Json<RoomInfo>), ApiError> {
+    payload.validate().map_err(ApiError::validation)?;
+    let user = chat_user(&state, &headers).await?;
+    match rooms::insert_db_room(&state.pool, &payload.name, payload.private, &user.uuid).await {
+        Ok(room) => {
+            tracing::info!(room_id = room.id, private = room.is_private(), "Room created");
+            Ok((StatusCode::CREATED, Json(RoomInfo {
+                id: room.id,
+                private: room.is_private(),
+                name: room.name,
+                member: true,
+                created_at: room.created_at
+            })))
+        },
+        Err(error) if repositories::is_unique_violation(&error) => Err(ApiError::conflict("Room name taken")),
+        Err(error) => Err(error.into())
+    }
+}
+
+async fn join_room(State(state): State<AppState>, headers: HeaderMap, Path(room_id): Path<i64>) -> Result<StatusCode, ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    rooms::join_room(&state.pool, room_id, &user.uuid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn leave_room(State(state): State<AppState>, headers: HeaderMap, Path(room_id): Path<i64>) -> Result<StatusCode, ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    rooms::member_room(&state.pool, room_id, &user.uuid).await?;
+    rooms::delete_db_member(&state.pool, room_id, &user.uuid).await?;
+    // open sockets stop receiving the room and can no longer post to it
+    state.chat_hub.remove_from_room(room_id, &user);
+    Ok(StatusCode::NO_CONTENT)
+}
+
+async fn get_members(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<i64>
+) -> Result<(StatusCode, Json<Vec<RoomMember>>), ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    rooms::readable_room(&state.pool, room_id, &user.uuid).await?;
+    let memberships = rooms::get_db_members(&state.pool, room_id).await?;
+    // usernames live with the user repository, which may not share the pool
+    let mut members = Vec::new();
+    for membership in memberships {
+        match state.users.find_by_uuid(&membership.user_uuid).await {
+            Ok(member) => members.push(RoomMember {
+                user_uuid: membership.user_uuid,
+                username: member.username,
+                joined_at: membership.joined_at
+            }),
+            Err(sqlx::Error::RowNotFound) => continue,
+            Err(error) => return Err(error.into())
+        }
+    }
+    Ok((StatusCode::OK, Json(members)))
+}
+
+// members add users by username or email, the only way into a private room
+async fn add_member(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(room_id): Path<i64>,
+    Json(payload): Json<AddMember>
+) -> Result<StatusCode, ApiError> {
+    payload.validate().map_err(ApiError::validation)?;
+    let user = chat_user(&state, &headers).await?;
+    rooms::member_room(&state.pool, room_id, &user.uuid).await?;
+    let member = match state.users.find_by_username_or_email(&payload.username).await {
+        Ok(member) => member,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    rooms::insert_db_member(&state.pool, room_id, &member.uuid).await?;
+    tracing::info!(room_id, member_uuid = %member.uuid, "Room member added");
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// conversations of the user with their unread counts, most recently active first
+async fn get_conversations(State(state): State<AppState>, headers: HeaderMap) -> Result<(StatusCode, Json<Vec<Conversation>>), ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    let rows = direct_messages::get_db_conversations(&state.pool, &user.uuid).await?;
+    // usernames live with the user repository, conversations with deleted users are left out
+    let mut conversations = Vec::new();
+    for row in rows {
+        match state.users.find_by_uuid(&row.partner_uuid).await {
+            Ok(partner) => conversations.push(Conversation {
+                user_uuid: row.partner_uuid,
+                username: partner.username,
+                last_message_at: row.last_message_at,
+                unread: row.unread
+            }),
+            Err(sqlx::Error::RowNotFound) => continue,
+            Err(error) => return Err(error.into())
+        }
+    }
+    Ok((StatusCode::OK, Json(conversations)))
+}
+
+// look up a user by username or email to message them
+async fn start_conversation(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<StartConversation>
+) -> Result<(StatusCode, Json<Conversation>), ApiError> {
+    payload.validate().map_err(ApiError::validation)?;
+    let user = chat_user(&state, &headers).await?;
+    let partner = match state.users.find_by_username_or_email(&payload.username).await {
+        Ok(partner) => partner,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    if partner.uuid == user.uuid {
+        return Err(ApiError::bad_request("Cannot message yourself"));
+    }
+    // an existing conversation keeps its activity and unread count
+    let row = direct_messages::get_db_conversations(&state.pool, &user.uuid).await?
+        .into_iter()
+        .find(|row| row.partner_uuid == partner.uuid);
+    Ok((StatusCode::OK, Json(Conversation {
+        user_uuid: partner.uuid,
+        username: partner.username,
+        last_message_at: row.as_ref().map_or(0, |row| row.last_message_at),
+        unread: row.map_or(0, |row| row.unread)
+    })))
+}
+
+// page through a conversation like the room history
+async fn get_direct_messages(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(partner_uuid): Path<String>,
+    Query(query): Query<PageQuery>
+) -> Result<(StatusCode, Json<Vec<DirectMessage>>), ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    let limit = query.limit.unwrap_or(chat::JOIN_HISTORY_LIMIT).clamp(1, CHAT_HISTORY_MAX_LIMIT);
+    match direct_messages::get_db_direct_messages(&state.pool, &user.uuid, &partner_uuid, query.before, limit).await {
+        Ok(messages) => Ok((StatusCode::OK, Json(messages))),
+        Err(error) => {
+            tracing::error!(%error, "Error getting direct messages");
+            Err(ApiError::database())
+        }
+    }
+}
+
+async fn mark_read(State(state): State<AppState>, headers: HeaderMap, Path(partner_uuid): Path<String>) -> Result<StatusCode, ApiError> {
+    let user = chat_user(&state, &headers).await?;
+    direct_messages::mark_db_read(&state.pool, &user.uuid, &partner_uuid).await?;
+    Ok(StatusCode::NO_CONTENT)
+}
+
+// users connected to the chat right now, the websocket sends the same snapshot followed by changes
+async fn get_presence(State(state): State<AppState>, headers: HeaderMap) -> Result<(StatusCode, Json<Vec<Presence>>), ApiError> {
+    chat_user(&state, &headers).await?;
+    Ok((StatusCode::OK, Json(state.presence.snapshot())))
+}
@@ -0,0 +1,48 @@
+use axum::{
+    extract::{Json, State}, http::StatusCode, routing::get, Extension, Router
+};
+use metrics_exporter_prometheus::PrometheusHandle;
+use types::status::Readiness;
+
+use crate::{mailer, repositories::PoolStats, schema, state::AppState};
+
+// route function to merge probe endpoints into the top level of the router
+pub fn routes(metrics_handle: PrometheusHandle) -> Router<AppState> {
+    // create routes
+    Router::new()
+        .route("/health", get(get_health))
+        .route("/ready", get(get_readiness))
+        .route("/metrics", get(get_metrics))
+        .layer(Extension(metrics_handle))
+}
+
+// liveness probe, answering at all means the process is alive
+async fn get_health() -> StatusCode {
+    StatusCode::OK
+}
+
+// readiness probe, 503 until the database is reachable, migrations are current and the mailer exists
+async fn get_readiness(State(state): State<AppState>) -> (StatusCode, Json<Readiness>) {
+    let database = sqlx::query("SELECT 1;").execute(&state.pool).await.is_ok();
+    let latest_version = schema::latest_version(state.config.database.backend);
+    let migrations = database && matches!(schema::current_version(&state.pool).await, Ok(Some(version)) if version == latest_version);
+    let readiness = Readiness { database, migrations, mailer: mailer::mailer_created() };
+    let status = if readiness.is_ready() { StatusCode::OK } else { StatusCode::SERVICE_UNAVAILABLE };
+    (status, Json(readiness))
+}
+
+// Prometheus scrape endpoint, pool gauges are sampled when scraped
+async fn get_metrics(State(state): State<AppState>, Extension(metrics_handle): Extension<PrometheusHandle>) -> String {
+    let shared = PoolStats { size: state.pool.size(), idle: state.pool.num_idle(), max: state.pool.options().get_max_connections() };
+    record_pool("shared", shared);
+    if let Some(users) = state.users.pool_stats() {
+        record_pool("users", users);
+    }
+    metrics_handle.render()
+}
+
+fn record_pool(pool: &'static str, stats: PoolStats) {
+    metrics::gauge!("db_pool_connections", "pool" => pool).set(stats.size as f64);
+    metrics::gauge!("db_pool_idle_connections", "pool" => pool).set(stats.idle as f64);
+    metrics::gauge!("db_pool_max_connections", "pool" => pool).set(stats.max as f64);
+}
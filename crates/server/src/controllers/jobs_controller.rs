@@ -0,0 +1,32 @@
+use axum::{
+    extract::{Json, Query, State}, http::StatusCode, middleware, routing::get, Router
+};
+use serde::Deserialize;
+use types::{jobs::JobInfo, roles::JOBS_READ};
+
+use crate::{error::ApiError, middleware::{require_permission::{require_permission, RequirePermission}, token_authentication}, state::AppState, strategies::{authentication::AuthClaims, jobs}};
+
+#[derive(Debug, Deserialize)]
+struct JobQuery {
+    status: Option<String>
+}
+
+// route function to nest endpoints in router
+pub fn routes(state: AppState) -> Router<AppState> {
+    // create routes
+    Router::new()
+        .route("/", get(get_jobs))
+        .layer(middleware::from_fn_with_state(RequirePermission(JOBS_READ), require_permission))
+        .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>))
+}
+
+// list recent background jobs, optionally filtered by status, requires jobs:read
+async fn get_jobs(State(state): State<AppState>, Query(query): Query<JobQuery>) -> Result<(StatusCode, Json<Vec<JobInfo>>), ApiError> {
+    match jobs::get_db_jobs(&state.pool, query.status).await {
+        Ok(jobs) => Ok((StatusCode::OK, Json(jobs))),
+        Err(error) => {
+            tracing::error!(%error, "Error getting jobs");
+            Err(ApiError::database())
+        }
+    }
+}
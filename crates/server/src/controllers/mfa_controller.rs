@@ -0,0 +1,140 @@
+use axum::{
+    extract::{Json, Request, State}, http::StatusCode, middleware, routing::{get, post}, Router
+};
+use http::HeaderMap;
+use types::auth::{AuthErrorType, MfaCode, RecoveryCodes, TotpEnrollment, TotpStatus};
+
+use crate::{error::ApiError, middleware::token_authentication, state::AppState, strategies::{authentication::{AuthClaims, Claims}, mfa}};
+
+// route function to nest endpoints in router
+pub fn routes(state: AppState) -> Router<AppState> {
+    // create routes
+    Router::new()
+        .nest("/totp", Router::new()
+            .route("/", get(get_totp_status))
+            .route("/enroll", post(enroll_totp))
+            .route("/confirm", post(confirm_totp))
+            .route("/disable", post(disable_totp))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
+}
+
+// get whether TOTP is enabled for user of JWT claims
+async fn get_totp_status(State(state): State<AppState>, request: Request) -> Result<(StatusCode, Json<TotpStatus>), ApiError> {
+    // generate AuthClaims from encoded x-claim header
+    let claims = AuthClaims::from_header(request.headers());
+    match mfa::get_db_user_totp(&state.pool, claims.sub).await {
+        Ok(user_totp) => {
+            let enabled = user_totp.map(|user_totp| user_totp.enabled).unwrap_or(false);
+            Ok((StatusCode::OK, Json(TotpStatus { enabled })))
+        },
+        Err(error) => {
+            tracing::error!(%error, "Error getting TOTP status");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// generate unconfirmed TOTP secret for user of JWT claims
+async fn enroll_totp(State(state): State<AppState>, request: Request) -> Result<(StatusCode, Json<TotpEnrollment>), ApiError> {
+    // generate AuthClaims from encoded x-claim header
+    let claims = AuthClaims::from_header(request.headers());
+    let user = match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    match mfa::get_db_user_totp(&state.pool, user.uuid.clone()).await {
+        Ok(Some(user_totp)) if user_totp.enabled => return Err(ApiError::auth(AuthErrorType::MfaAlreadyEnabled)),
+        Ok(_) => {},
+        Err(error) => {
+            tracing::error!(%error, "Error getting TOTP");
+            return Err(ApiError::database())
+        }
+    }
+    // build otpauth uri from new secret for QR display
+    let secret = mfa::gen_totp_secret();
+    let totp = match mfa::build_totp(&state.config, &secret, user.email.to_string()) {
+        Some(totp) => totp,
+        None => {
+            tracing::error!(user_uuid = %user.uuid, "Could not build TOTP");
+            return Err(ApiError::internal())
+        }
+    };
+    if let Err(error) = mfa::insert_db_user_totp(&state.pool, user.uuid, secret.clone()).await {
+        tracing::error!(%error, "Error storing TOTP secret");
+        return Err(ApiError::database())
+    }
+    Ok((StatusCode::CREATED, Json(TotpEnrollment { secret, otpauth_uri: totp.get_url() })))
+}
+
+// enable TOTP after verifying a code from the enrolled secret
+async fn confirm_totp(State(state): State<AppState>, headers: HeaderMap, Json(payload): Json<MfaCode>) -> Result<(StatusCode, Json<RecoveryCodes>), ApiError> {
+    // generate AuthClaims from encoded x-claim header
+    let claims = AuthClaims::from_header(&headers);
+    let user = match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    let user_totp = match mfa::get_db_user_totp(&state.pool, user.uuid.clone()).await {
+        Ok(Some(user_totp)) if user_totp.enabled => return Err(ApiError::auth(AuthErrorType::MfaAlreadyEnabled)),
+        Ok(Some(user_totp)) => user_totp,
+        Ok(None) => return Err(ApiError::auth(AuthErrorType::MfaNotEnrolled)),
+        Err(error) => {
+            tracing::error!(%error, "Error getting TOTP");
+            return Err(ApiError::database())
+        }
+    };
+    // verify code was generated from the enrolled secret
+    let step = mfa::build_totp(&state.config, &user_totp.secret, user.email.to_string())
+        .and_then(|totp| mfa::matching_totp_step(&totp, &payload.code));
+    if step.is_none() {
+        return Err(ApiError::auth(AuthErrorType::InvalidMfaCode));
+    }
+    let db_result = async {
+        mfa::enable_db_user_totp(&state.pool, user.uuid.clone()).await?;
+        mfa::use_db_totp_step(&state.pool, user.uuid.clone(), step.unwrap()).await?;
+        mfa::replace_db_recovery_codes(&state.pool, user.uuid.clone()).await
+    }.await;
+    match db_result {
+        Ok(codes) => Ok((StatusCode::CREATED, Json(RecoveryCodes { codes }))),
+        Err(error) => {
+            tracing::error!(%error, "Error enabling TOTP");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// disable TOTP after verifying a TOTP or recovery code
+async fn disable_totp(State(state): State<AppState>, headers: HeaderMap, Json(payload): Json<MfaCode>) -> Result<StatusCode, ApiError> {
+    // generate AuthClaims from encoded x-claim header
+    let claims = AuthClaims::from_header(&headers);
+    let user = match state.users.find_by_uuid(&claims.sub).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    let user_totp = match mfa::get_db_user_totp(&state.pool, user.uuid.clone()).await {
+        Ok(Some(user_totp)) if user_totp.enabled => user_totp,
+        Ok(_) => return Err(ApiError::auth(AuthErrorType::MfaNotEnrolled)),
+        Err(error) => {
+            tracing::error!(%error, "Error getting TOTP");
+            return Err(ApiError::database())
+        }
+    };
+    match mfa::verify_db_mfa_code(&state.config, &state.pool, user.uuid.clone(), user.email.to_string(), &user_totp.secret, &payload.code).await {
+        Ok(true) => {},
+        Ok(false) => return Err(ApiError::auth(AuthErrorType::InvalidMfaCode)),
+        Err(error) => {
+            tracing::error!(%error, "Error verifying MFA code");
+            return Err(ApiError::database())
+        }
+    }
+    match mfa::delete_db_user_totp(&state.pool, user.uuid).await {
+        Ok(_) => Ok(StatusCode::OK),
+        Err(error) => {
+            tracing::error!(%error, "Error removing TOTP");
+            Err(ApiError::database())
+        }
+    }
+}
@@ -1,3 +1,9 @@
 pub mod users_controller;
 pub mod auth_controller;
-pub mod ws_controller;
\ No newline at end of file
+pub mod ws_controller;
+pub mod mfa_controller;
+pub mod roles_controller;
+pub mod jobs_controller;pub mod status_controller;
+pub mod health_controller;
+
+pub mod chat_controller;
\ No newline at end of file
@@ -0,0 +1,79 @@
+use axum::{
+    extract::{Json, State}, http::StatusCode, middleware, routing::{get, post}, Router
+};
+use types::{auth::AuthErrorType, roles::{Role, RoleAssignment, ROLES_ASSIGN, ROLES_READ}, user::UserInfo};
+
+use crate::{error::ApiError, middleware::{require_permission::{require_permission, RequirePermission}, token_authentication}, state::AppState, strategies::{authentication::AuthClaims, roles}};
+
+// route function to nest endpoints in router
+pub fn routes(state: AppState) -> Router<AppState> {
+    // create routes
+    Router::new()
+        .nest("/", Router::new()
+            .route("/", get(get_roles))
+            .layer(middleware::from_fn_with_state(RequirePermission(ROLES_READ), require_permission))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
+        .nest("/assign", Router::new()
+            .route("/", post(assign_role))
+            .layer(middleware::from_fn_with_state(RequirePermission(ROLES_ASSIGN), require_permission))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
+        .nest("/remove", Router::new()
+            .route("/", post(remove_role))
+            .layer(middleware::from_fn_with_state(RequirePermission(ROLES_ASSIGN), require_permission))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
+}
+
+// list all roles with the permissions they grant, requires roles:read
+async fn get_roles(State(state): State<AppState>) -> Result<(StatusCode, Json<Vec<Role>>), ApiError> {
+    match roles::get_db_roles(&state.pool).await {
+        Ok(roles) => Ok((StatusCode::OK, Json(roles))),
+        Err(error) => {
+            tracing::error!(%error, "Error getting roles");
+            Err(ApiError::database())
+        }
+    }
+}
+
+// grant role to user, requires roles:assign
+async fn assign_role(State(state): State<AppState>, Json(payload): Json<RoleAssignment>) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
+    let user = match state.users.find_by_uuid(&payload.user_uuid).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    match roles::assign_db_user_role(&state.pool, user.uuid.clone(), payload.role).await {
+        Ok(true) => {},
+        Ok(false) => return Err(ApiError::auth(AuthErrorType::RoleDoesNotExist)),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error assigning role");
+            return Err(ApiError::database());
+        }
+    }
+    user_info_response(&state, UserInfo::from_user(user)).await
+}
+
+// remove role from user, requires roles:assign
+async fn remove_role(State(state): State<AppState>, Json(payload): Json<RoleAssignment>) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
+    let user = match state.users.find_by_uuid(&payload.user_uuid).await {
+        Ok(user) => user,
+        Err(sqlx::Error::RowNotFound) => return Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => return Err(error.into())
+    };
+    match roles::remove_db_user_role(&state.pool, user.uuid.clone(), payload.role).await {
+        Ok(true) => {},
+        Ok(false) => return Err(ApiError::auth(AuthErrorType::RoleDoesNotExist)),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error removing role");
+            return Err(ApiError::database());
+        }
+    }
+    user_info_response(&state, UserInfo::from_user(user)).await
+}
+
+// respond with updated roles and permissions of user
+async fn user_info_response(state: &AppState, user_info: UserInfo) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
+    match roles::user_info_with_access(&state.pool, user_info).await {
+        Ok(user_info) => Ok((StatusCode::OK, Json(user_info))),
+        Err(_) => Err(ApiError::database())
+    }
+}
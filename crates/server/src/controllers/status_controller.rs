@@ -0,0 +1,30 @@
+use axum::{
+    extract::{Json, State}, http::StatusCode, routing::get, Router
+};
+use types::status::ServerStatus;
+
+use crate::{error::ApiError, schema, state::AppState};
+
+// route function to nest endpoints in router
+pub fn routes() -> Router<AppState> {
+    // create routes
+    Router::new()
+        .route("/", get(get_status))
+}
+
+// report server version and schema version of the connected database
+async fn get_status(State(state): State<AppState>) -> Result<(StatusCode, Json<ServerStatus>), ApiError> {
+    let backend = state.config.database.backend;
+    match schema::current_version(&state.pool).await {
+        Ok(schema_version) => Ok((StatusCode::OK, Json(ServerStatus {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            database: format!("{:?}", backend).to_lowercase(),
+            schema_version,
+            latest_schema_version: schema::latest_version(backend)
+        }))),
+        Err(error) => {
+            tracing::error!(%error, "Error getting schema version");
+            Err(ApiError::database())
+        }
+    }
+}
@@ -1,71 +1,185 @@
 use axum::{
-    extract::{Json, Request}, http::StatusCode, middleware, routing::{delete, get}, RequestExt, Router
+    extract::{Json, Request, State}, http::StatusCode, middleware, routing::{delete, get, post}, RequestExt, Router
 };
+use email_address::EmailAddress;
+use http::{header::AUTHORIZATION, HeaderMap, HeaderValue};
+use types::{auth::AuthErrorType, roles::{USERS_DELETE, USERS_READ}, user::{ChangePassword, DeleteUser, UpdateUser, User, UserInfo}, validation::{Validate, Validator}};
 
-use types::{auth::AuthErrorType, user::UserInfo};
-
-use crate::{middleware::token_authentication, strategies::{authentication::{AuthClaims, AuthError, AuthRequesterClaims, Claims}, users::{delete_user_by_uuid, get_all_users, get_db_user_by_uuid}}};
+use crate::{error::ApiError, middleware::{require_permission::{require_permission, RequirePermission}, token_authentication}, repositories, state::AppState, strategies::{authentication::{AuthClaims, AuthRequesterClaims, Claims}, passwords, roles::user_info_with_access, sessions, users::{self, get_all_users}, verification}};
 
 // route function to nest endpoints in router
-pub fn routes() -> Router {
+pub fn routes(state: AppState) -> Router<AppState> {
     // create routes
     Router::new()
         .nest("/info", Router::new()
             .route("/",get(get_user_info))
-            .layer(middleware::from_fn(token_authentication::authenticate_token::<AuthRequesterClaims>)))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
         .nest("/all", Router::new()
             .route("/",get(get_all_user_info))
-            .layer(middleware::from_fn(token_authentication::authenticate_token::<AuthClaims>)))
+            .layer(middleware::from_fn_with_state(RequirePermission(USERS_READ), require_permission))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
         .nest("/", Router::new()
-            .route("/", delete(delete_user))
-            .layer(middleware::from_fn(token_authentication::authenticate_token::<AuthClaims>)))
+            // deleting other users requires users:delete, the profile routes only act on the requesting user
+            .route("/", delete(delete_user)
+                .route_layer(middleware::from_fn_with_state(RequirePermission(USERS_DELETE), require_permission))
+                .patch(update_profile))
+            .route("/password", post(change_password))
+            .route("/me", delete(delete_self))
+            .layer(middleware::from_fn_with_state(state.clone(), token_authentication::authenticate_token::<AuthClaims>)))
 }
 
+// get user by uuid, telling missing users apart from database failures
+async fn find_user(state: &AppState, uuid: &str) -> Result<User, ApiError> {
+    match state.users.find_by_uuid(uuid).await {
+        Ok(user) => Ok(user),
+        Err(sqlx::Error::RowNotFound) => Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => Err(error.into())
+    }
+}
 
-// get user info by JWT claims
-async fn get_user_info(request: Request) -> Result<(StatusCode, Json<UserInfo>), AuthError> {
-    // generate AuthRequesterClaims from encoded x-claim header
-    let claims = AuthRequesterClaims::from_header(request.headers());
-    match get_db_user_by_uuid(claims.sub).await {
-        Ok(user) => {
-            Ok((StatusCode::OK, axum::Json(UserInfo::from_user(user))))
-        }, Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist))
+// check the current password before changes to the account
+fn confirm_password(user: &User, pass: &str) -> Result<(), ApiError> {
+    match passwords::verify_password(pass, &user.pass) {
+        Ok(true) => Ok(()),
+        Ok(false) => Err(ApiError::auth(AuthErrorType::WrongCredentials)),
+        Err(error) => {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error verifying password");
+            Err(ApiError::internal())
+        }
     }
 }
 
 // get user info by JWT claims
-async fn get_all_user_info(request: Request) -> Result<(StatusCode, Json<Vec<UserInfo>>), AuthError> {
-    // generate AuthRequesterClaims from encoded x-claim header
+async fn get_user_info(State(state): State<AppState>, request: Request) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
+    // generate AuthClaims from encoded x-claim header
     let claims = AuthClaims::from_header(request.headers());
-    if claims.acc {
-        match get_all_users().await {
-            Ok(users) => {
-                Ok((StatusCode::OK, axum::Json(users)))
-            }, Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist))
+    let user = find_user(&state, &claims.sub).await?;
+    match user_info_with_access(&state.pool, UserInfo::from_user(user)).await {
+        Ok(user_info) => Ok((StatusCode::OK, axum::Json(user_info))),
+        Err(error) => Err(error.into())
+    }
+}
+
+// get info of all users, requires users:read
+async fn get_all_user_info(State(state): State<AppState>) -> Result<(StatusCode, Json<Vec<UserInfo>>), ApiError> {
+    match get_all_users(state.users.as_ref()).await {
+        Ok(users) => {
+            let mut users_info = Vec::new();
+            for user_info in users {
+                match user_info_with_access(&state.pool, user_info).await {
+                    Ok(user_info) => users_info.push(user_info),
+                    Err(error) => return Err(error.into())
+                }
+            }
+            Ok((StatusCode::OK, axum::Json(users_info)))
+        }, Err(error) => Err(error.into())
+    }
+}
+
+// change username and email of the requesting user, a new email address has to be verified again
+async fn update_profile(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<UpdateUser>
+) -> Result<(StatusCode, Json<UserInfo>), ApiError> {
+    payload.validate().map_err(ApiError::validation)?;
+    let claims = AuthClaims::from_header(&headers);
+    let mut user = find_user(&state, &claims.sub).await?;
+    if let Some(username) = payload.username {
+        user.username = username;
+    }
+    let email_changed = match payload.email {
+        Some(email) if email != user.email.as_str() => {
+            user.email = EmailAddress::new_unchecked(email);
+            user.email_verified_at = None;
+            true
+        },
+        _ => false
+    };
+    // the stored password hash is kept as it is
+    if let Err(error) = state.users.update(&user).await {
+        if repositories::is_unique_violation(&error) {
+            return Err(ApiError::auth(AuthErrorType::UserAlreadyExists));
         }
-    } else {
-        Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+        tracing::error!(user_uuid = %user.uuid, %error, "Error updating profile");
+        return Err(ApiError::database());
+    }
+    // the change succeeded, a failed email can be resent from the verify page
+    if email_changed {
+        if let Err(error) = verification::queue_verification_email(&state, &user).await {
+            tracing::error!(user_uuid = %user.uuid, %error, "Error queueing verification email");
+        }
+    }
+    match user_info_with_access(&state.pool, UserInfo::from_user(user)).await {
+        Ok(user_info) => Ok((StatusCode::OK, Json(user_info))),
+        Err(error) => Err(error.into())
     }
 }
 
-async fn delete_user(request: Request) -> Result<StatusCode, AuthError> {
-    // generate AuthRequesterClaims from encoded x-claim header
-    let claims = AuthClaims::from_header(request.headers());
-    let uuid = request.extract().await;
+// replace the password of the requesting user after checking the current one
+async fn change_password(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<ChangePassword>
+) -> Result<(StatusCode, HeaderMap), ApiError> {
+    payload.validate_with(passwords::breached_passwords()).map_err(ApiError::validation)?;
+    let claims = AuthClaims::from_header(&headers);
+    let mut user = find_user(&state, &claims.sub).await?;
+    confirm_password(&user, &payload.current_pass)?;
+    // the password must also differ from the username
+    Validator::new()
+        .password("pass", &payload.pass, Some(&user.username), passwords::breached_passwords())
+        .finish()
+        .map_err(ApiError::validation)?;
+    user.pass = payload.pass;
+    if let Err(error) = users::update_db_user(state.users.as_ref(), user).await {
+        tracing::error!(user_uuid = %claims.sub, %error, "Error updating password");
+        return Err(ApiError::database());
+    }
+    // sign out every other device, the requesting one continues with a new session
+    if let Err(error) = sessions::revoke_db_user_sessions(&state.pool, claims.sub.clone()).await {
+        tracing::error!(user_uuid = %claims.sub, %error, "Error revoking sessions");
+        return Err(ApiError::database());
+    }
+    let requester_token = AuthRequesterClaims::new(&state, claims.sub.clone()).await?.generate_token()?;
+    let mut header_map = HeaderMap::new();
+    header_map.insert(AUTHORIZATION, HeaderValue::from_str(&requester_token.to_string()).unwrap());
+    Ok((StatusCode::NO_CONTENT, header_map))
+}
+
+// delete the requesting user after checking their password
+async fn delete_self(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(payload): Json<DeleteUser>
+) -> Result<StatusCode, ApiError> {
+    payload.validate().map_err(ApiError::validation)?;
+    let claims = AuthClaims::from_header(&headers);
+    let user = find_user(&state, &claims.sub).await?;
+    confirm_password(&user, &payload.pass)?;
+    match users::delete_db_user(state.users.as_ref(), &state.pool, user.uuid.clone()).await {
+        Ok(true) => {
+            tracing::info!(user_uuid = %user.uuid, "User deleted their account");
+            Ok(StatusCode::NO_CONTENT)
+        },
+        Ok(false) => Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+        Err(error) => Err(error.into())
+    }
+}
+
+// delete user by uuid in body, requires users:delete
+async fn delete_user(State(state): State<AppState>, request: Request) -> Result<StatusCode, ApiError> {
+    let uuid: Result<String, _> = request.extract().await;
     match uuid {
         Ok(uuid) => {
-            if claims.acc {
-                match delete_user_by_uuid(uuid).await {
-                    Ok(_) => {
-                        Ok(StatusCode::OK)
-                    }, Err(_) => Err(AuthError::from_error_type(AuthErrorType::UserDoesNotExist))
-                }
-            } else {
-                Err(AuthError::from_error_type(AuthErrorType::AccessDenied))
+            match users::delete_db_user(state.users.as_ref(), &state.pool, uuid).await {
+                Ok(true) => Ok(StatusCode::OK),
+                Ok(false) => Err(ApiError::auth(AuthErrorType::UserDoesNotExist)),
+                Err(error) => Err(error.into())
             }
         }, Err(error) => {
-            println!("{error}");
-            Err(AuthError::from_error_type(AuthErrorType::ServerError))
+            tracing::warn!(%error, "Could not read UUID to delete");
+            Err(ApiError::bad_request("Body must be the UUID of the user"))
         }
     }
-}
\ No newline at end of file
+}
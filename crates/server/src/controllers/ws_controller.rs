@@ -1,88 +1,300 @@
-use std::collections::HashSet;
-use std::sync::{Arc, Mutex};
+use std::borrow::Cow;
+use std::sync::Arc;
 use axum::{
     routing::get,
     Router
 };
 use axum::{
-    extract::{ws::{Message, WebSocket, WebSocketUpgrade}, State}, response::IntoResponse
+    extract::{ws::{close_code, CloseFrame, Message, WebSocket, WebSocketUpgrade}, State}, response::IntoResponse, Extension
 };
-use tokio::sync::broadcast;
-use futures::{sink::SinkExt, stream::StreamExt};
+use tokio::{sync::{broadcast, mpsc, Notify}, task::JoinHandle};
+use futures::{sink::SinkExt, stream::{SplitSink, StreamExt}};
+use tracing::{field::Empty, Instrument, Span};
+use types::{auth::AuthErrorType, chat::{ClientMessage, ServerMessage, CHAT_MESSAGE_MAX_LENGTH}, error::ApiErrorCode, user::User};
 
+use crate::middleware::request_id::current_request_id;
+use crate::shutdown::Shutdown;
+use crate::state::AppState;
 use crate::strategies::authentication::{AuthRequesterClaims, Claims};
-use crate::strategies::users::get_db_user_by_uuid;
-
-struct AppState {
-    user_set: Mutex<HashSet<String>>,
-    tx: broadcast::Sender<String>
-}
+use crate::strategies::{chat, chat_hub, direct_messages, rooms, verification};
 
 // route function to nest endpoints in router
-pub fn routes() -> Router {
-    let user_set = Mutex::new(HashSet::new());
-    let (tx, _rx) = broadcast::channel(100);
-    let app_state = Arc::new(AppState{user_set, tx});
+pub fn routes(shutdown: Shutdown) -> Router<AppState> {
     // create routes
     Router::new()
         .route("/", get(ws_handler))
-        .with_state(app_state)
+        .layer(Extension(shutdown))
 }
 
-async fn ws_handler(ws: WebSocketUpgrade, State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    ws.on_upgrade(|socket| {handle_socket(socket, state)})
+async fn ws_handler(ws: WebSocketUpgrade, State(state): State<AppState>, Extension(shutdown): Extension<Shutdown>) -> impl IntoResponse {
+    // the connection outlives the upgrade request, so it gets its own span carrying the request ID
+    let span = tracing::info_span!("ws_connection", request_id = current_request_id().unwrap_or_default(), user_uuid = Empty);
+    ws.on_upgrade(|socket| async move {
+        let connections = metrics::gauge!("websocket_connections");
+        connections.increment(1);
+        handle_socket(socket, state, shutdown).instrument(span).await;
+        connections.decrement(1);
+    })
 }
 
-async fn handle_socket(socket: WebSocket, state: Arc<AppState>) {
+// each socket holds its own shutdown handle so shutdown waits for the close frame to go out
+async fn handle_socket(socket: WebSocket, state: AppState, mut shutdown: Shutdown) {
     let (mut sender, mut receiver) = socket.split();
-    let mut username = String::new();
-    while let Some(Ok(auth)) = receiver.next().await {
-        if let Message::Text(text) = auth {
-            if let Ok(claims) = AuthRequesterClaims::from_string(&text) {
-                username = get_db_user_by_uuid(claims.sub.clone()).await.unwrap().username;
-                break;
-            } else {
-                sender.close().await.unwrap();
+    let user;
+    loop {
+        let auth = tokio::select! {
+            auth = receiver.next() => auth,
+            _ = shutdown.triggered() => {
+                close_for_shutdown(&mut sender).await;
                 return;
             }
+        };
+        let Some(Ok(auth)) = auth else {
+            return;
+        };
+        let Message::Text(text) = auth else {
+            continue;
+        };
+        let token = match serde_json::from_str::<ClientMessage>(&text) {
+            Ok(ClientMessage::Auth { token }) => token,
+            Ok(_) => {
+                let _ = send_message(&mut sender, &ServerMessage::error(ApiErrorCode::BadRequest, "Authenticate before sending messages")).await;
+                continue;
+            },
+            Err(error) => {
+                let _ = send_message(&mut sender, &ServerMessage::error(ApiErrorCode::BadRequest, format!("Invalid frame: {error}"))).await;
+                continue;
+            }
+        };
+        // reject tokens with invalid signatures or revoked sessions
+        let claims = match AuthRequesterClaims::from_string(&state.config, &token) {
+            Ok(claims) => claims.validate(&state).await.map(|_| claims),
+            Err(error) => Err(error)
+        };
+        // unverified accounts may be blocked from chat by the verification policy
+        let authenticated = match claims {
+            Ok(claims) => state.users.find_by_uuid(&claims.sub).await.ok()
+                .filter(|user| verification::chat_allowed(&state.config, user)),
+            Err(_) => None
+        };
+        if let Some(authenticated) = authenticated {
+            Span::current().record("user_uuid", &authenticated.uuid);
+            tracing::info!("Websocket authenticated");
+            user = authenticated;
+            break;
+        } else {
+            tracing::info!("Websocket authentication rejected");
+            let _ = send_message(&mut sender, &ServerMessage::error(ApiErrorCode::Auth(AuthErrorType::InvalidToken), AuthErrorType::InvalidToken.message())).await;
+            let _ = sender.close().await;
+            return;
         }
     }
 
-    let mut rx = state.tx.subscribe();
-
-    let msg = format!("{username} joined.");
-    let _ = state.tx.send(msg);
+    let _ = send_message(&mut sender, &ServerMessage::System { text: format!("Connected as {}", user.username), at: now() }).await;
+    // frames meant for this connection only, like acks, errors, direct messages and the messages of joined rooms
+    let (direct_tx, mut direct_rx) = mpsc::channel::<ServerMessage>(16);
 
     let mut send_task = tokio::spawn(async move {
-        while let Ok(msg) = rx.recv().await {
-            if msg == String::new() {
-                break;
-            }
-            if sender.send(Message::Text(msg)).await.is_err() {
+        loop {
+            let msg = tokio::select! {
+                Some(msg) = direct_rx.recv() => msg,
+                _ = shutdown.triggered() => {
+                    close_for_shutdown(&mut sender).await;
+                    break;
+                }
+            };
+            if send_message(&mut sender, &msg).await.is_err() {
                 break;
             }
         }
-    });
+    }.in_current_span());
 
-    let tx = state.tx.clone();
-    let name = username.clone();
+    let overflow = Arc::new(Notify::new());
+    let id = state.chat_hub.register(&user.uuid, direct_tx.clone(), overflow.clone());
+    // subscribed before coming online so no change falls between the snapshot and the presence frames
+    let presence_rx = state.presence.subscribe();
+    state.presence.connect(&user, id);
+    let _ = direct_tx.send(ServerMessage::PresenceSnapshot { users: state.presence.snapshot() }).await;
+    let presence = forward_presence(presence_rx, direct_tx.clone());
+    let mut connection = Connection { id, state, user, direct_tx, presence };
 
     let mut recv_task = tokio::spawn(async move {
-        while let Some(Ok(Message::Text(text))) = receiver.next().await {
-            if text == String::new() {
-                break;
+        while let Some(Ok(frame)) = receiver.next().await {
+            let text = match frame {
+                Message::Text(text) => text,
+                Message::Close(_) => break,
+                _ => continue
+            };
+            let replies = match serde_json::from_str::<ClientMessage>(&text) {
+                Ok(message) => connection.handle(message).await,
+                Err(error) => vec![ServerMessage::error(ApiErrorCode::BadRequest, format!("Invalid frame: {error}"))]
+            };
+            for reply in replies {
+                if connection.direct_tx.send(reply).await.is_err() {
+                    return;
+                }
             }
-            let _ = tx.send(format!("{name}: {text}"));
         }
-    });
+    }.in_current_span());
 
+    // dropping the connection with either task leaves its rooms, unregisters it and takes it offline
     tokio::select! {
         _ = (&mut send_task) => recv_task.abort(),
         _ = (&mut recv_task) => send_task.abort(),
+        // the send task may be stuck writing to the client, so both tasks are stopped
+        _ = overflow.notified() => {
+            send_task.abort();
+            recv_task.abort();
+        }
     };
 
-    let msg = format!("{username} left.");
-    let _ = state.tx.send(msg);
+    tracing::info!("Websocket closed");
+}
+
+// receiving side of an authenticated connection, the rooms it joined are kept by the chat hub
+struct Connection {
+    id: u64,
+    state: AppState,
+    user: User,
+    direct_tx: mpsc::Sender<ServerMessage>,
+    presence: JoinHandle<()>
+}
+
+impl Connection {
+    async fn handle(&mut self, message: ClientMessage) -> Vec<ServerMessage> {
+        match message {
+            ClientMessage::Join { room } => self.join(room).await,
+            ClientMessage::Leave { room } => self.leave(room).into_iter().collect(),
+            ClientMessage::Chat { room, text, client_id } => vec![self.chat(room, text, client_id).await],
+            ClientMessage::Direct { to, text, client_id } => vec![self.direct(to, text, client_id).await],
+            ClientMessage::Idle => {
+                self.state.presence.set_idle(&self.user.uuid, self.id, true);
+                vec![]
+            },
+            ClientMessage::Active => {
+                self.state.presence.set_idle(&self.user.uuid, self.id, false);
+                vec![]
+            },
+            ClientMessage::Auth { .. } => vec![ServerMessage::error(ApiErrorCode::BadRequest, "Already authenticated")]
+        }
+    }
+
+    async fn join(&mut self, room_id: i64) -> Vec<ServerMessage> {
+        if let Err(error) = rooms::join_room(&self.state.pool, room_id, &self.user.uuid).await {
+            let body = error.body();
+            return vec![ServerMessage::error(body.code, body.message)];
+        }
+        // subscribed before reading history so no message falls between the two, clients drop the duplicates by id
+        self.state.chat_hub.join_room(room_id, &self.user, self.id, self.direct_tx.clone());
+        match chat::get_db_messages(&self.state.pool, room_id, None, chat::JOIN_HISTORY_LIMIT).await {
+            Ok(messages) => vec![ServerMessage::History { room: room_id, messages }],
+            Err(error) => {
+                tracing::error!(%error, room_id, "Error getting chat history");
+                vec![ServerMessage::error(ApiErrorCode::Database, "History could not be loaded")]
+            }
+        }
+    }
+
+    fn leave(&mut self, room_id: i64) -> Option<ServerMessage> {
+        if !self.state.chat_hub.leave_room(room_id, &self.user, self.id) {
+            return Some(ServerMessage::error(ApiErrorCode::BadRequest, "Not in this room"));
+        }
+        None
+    }
+
+    async fn chat(&mut self, room_id: i64, text: String, client_id: Option<String>) -> ServerMessage {
+        // leaving over HTTP takes the connection out of the room as well
+        if !self.state.chat_hub.in_room(room_id, &self.user.uuid, self.id) {
+            return ServerMessage::error(ApiErrorCode::BadRequest, "Join the room before sending messages");
+        }
+        if let Err(message) = check_text(&text) {
+            return ServerMessage::error(ApiErrorCode::Validation, message);
+        }
+        // stored first so clients never see a message history does not have
+        match chat::insert_db_message(&self.state.pool, room_id, &self.user.uuid, &self.user.username, &text).await {
+            Ok(message) => {
+                let ack = ServerMessage::Ack { id: message.id, client_id, sent_at: message.sent_at };
+                self.state.chat_hub.broadcast(room_id, ServerMessage::Chat(message));
+                ack
+            },
+            Err(error) => {
+                tracing::error!(%error, room_id, "Error storing chat message");
+                ServerMessage::error(ApiErrorCode::Database, "Message could not be stored")
+            }
+        }
+    }
+
+    async fn direct(&mut self, to: String, text: String, client_id: Option<String>) -> ServerMessage {
+        if let Err(message) = check_text(&text) {
+            return ServerMessage::error(ApiErrorCode::Validation, message);
+        }
+        if to == self.user.uuid {
+            return ServerMessage::error(ApiErrorCode::BadRequest, "Cannot message yourself");
+        }
+        match self.state.users.find_by_uuid(&to).await {
+            Ok(_) => (),
+            Err(sqlx::Error::RowNotFound) => return ServerMessage::error(ApiErrorCode::Auth(AuthErrorType::UserDoesNotExist), AuthErrorType::UserDoesNotExist.message()),
+            Err(error) => {
+                tracing::error!(%error, "Error finding direct message recipient");
+                return ServerMessage::error(ApiErrorCode::Database, "Message could not be stored");
+            }
+        }
+        match direct_messages::insert_db_direct_message(&self.state.pool, &self.user.uuid, &to, &self.user.username, &text).await {
+            Ok(message) => {
+                let ack = ServerMessage::Ack { id: message.id, client_id, sent_at: message.sent_at };
+                // the sender's other tabs get their copy too
+                self.state.chat_hub.send_to_user(&to, ServerMessage::Direct(message.clone()));
+                self.state.chat_hub.send_to_user(&self.user.uuid, ServerMessage::Direct(message));
+                ack
+            },
+            Err(error) => {
+                tracing::error!(%error, "Error storing direct message");
+                ServerMessage::error(ApiErrorCode::Database, "Message could not be stored")
+            }
+        }
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        self.state.chat_hub.unregister(&self.user, self.id);
+        self.state.presence.disconnect(&self.user.uuid, self.id);
+        self.presence.abort();
+    }
+}
+
+// pass presence changes on to the connection until the task is aborted
+fn forward_presence(mut rx: broadcast::Receiver<ServerMessage>, direct_tx: mpsc::Sender<ServerMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        chat_hub::forward(&mut rx, &direct_tx).await;
+    }.in_current_span())
+}
 
-    state.user_set.lock().unwrap().remove(&username);
+fn check_text(text: &str) -> Result<(), String> {
+    if text.trim().is_empty() {
+        return Err("Message is empty".to_string());
+    }
+    if text.chars().count() > CHAT_MESSAGE_MAX_LENGTH {
+        return Err(format!("Message must be at most {CHAT_MESSAGE_MAX_LENGTH} characters long"));
+    }
+    Ok(())
+}
+
+fn now() -> i64 {
+    jsonwebtoken::get_current_timestamp() as i64
+}
+
+async fn send_message(sender: &mut SplitSink<WebSocket, Message>, message: &ServerMessage) -> Result<(), axum::Error> {
+    // ServerMessage only holds strings and integers, serializing it cannot fail
+    let text = serde_json::to_string(message).unwrap();
+    sender.send(Message::Text(text)).await
+}
+
+// tell the client the server is going away rather than dropping the connection, so it can reconnect
+async fn close_for_shutdown(sender: &mut SplitSink<WebSocket, Message>) {
+    let _ = send_message(sender, &ServerMessage::System { text: "Server shutting down".to_string(), at: now() }).await;
+    let frame = CloseFrame { code: close_code::AWAY, reason: Cow::from("Server shutting down") };
+    if sender.send(Message::Close(Some(frame))).await.is_ok() {
+        tracing::info!("Websocket closed for shutdown");
+    }
 }
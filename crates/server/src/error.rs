@@ -0,0 +1,75 @@
+use axum::{body::Body, response::{IntoResponse, Response}, Json};
+use http::{header::RETRY_AFTER, HeaderValue, StatusCode};
+use types::{auth::AuthErrorType, error::{ApiErrorBody, FieldError}};
+
+use crate::middleware::request_id::current_request_id;
+
+// API error with optional seconds until the request may be retried
+#[derive(Debug)]
+pub struct ApiError(types::error::ApiError, Option<u64>);
+
+impl ApiError {
+    pub fn auth(error_type: AuthErrorType) -> Self {
+        types::error::ApiError::auth(error_type).into()
+    }
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        types::error::ApiError::validation(fields).into()
+    }
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        types::error::ApiError::bad_request(message).into()
+    }
+    pub fn not_found(message: impl Into<String>) -> Self {
+        types::error::ApiError::not_found(message).into()
+    }
+    pub fn conflict(message: impl Into<String>) -> Self {
+        types::error::ApiError::conflict(message).into()
+    }
+    pub fn database() -> Self {
+        types::error::ApiError::database().into()
+    }
+    pub fn internal() -> Self {
+        types::error::ApiError::internal().into()
+    }
+    pub fn with_retry_after(mut self, seconds: u64) -> Self {
+        self.1 = Some(seconds);
+        self
+    }
+    pub fn status(&self) -> StatusCode {
+        self.0.status()
+    }
+    pub fn body(&self) -> ApiErrorBody {
+        self.0.body()
+    }
+}
+
+impl From<types::error::ApiError> for ApiError {
+    fn from(error: types::error::ApiError) -> Self {
+        Self(error, None)
+    }
+}
+
+impl From<AuthErrorType> for ApiError {
+    fn from(error_type: AuthErrorType) -> Self {
+        Self::auth(error_type)
+    }
+}
+
+// log the query failure, clients only learn that the database is at fault
+impl From<sqlx::Error> for ApiError {
+    fn from(error: sqlx::Error) -> Self {
+        tracing::error!(%error, "Database error");
+        Self::database()
+    }
+}
+
+impl IntoResponse for ApiError {
+    fn into_response(self) -> Response<Body> {
+        let mut body = self.body();
+        body.request_id = current_request_id();
+        let mut response = (self.status(), Json(body)).into_response();
+        if let Some(seconds) = self.1 {
+            response.headers_mut().insert(RETRY_AFTER, HeaderValue::from(seconds));
+        }
+        response
+    }
+}
@@ -0,0 +1,43 @@
+use std::{fs, path::PathBuf};
+
+use axum::async_trait;
+use lettre::{AsyncFileTransport, AsyncTransport, Message, Tokio1Executor};
+
+use super::{MailError, Mailer};
+
+// Writes each email as an .eml file for local development
+pub struct FileMailer {
+    transport: AsyncFileTransport<Tokio1Executor>,
+    directory: PathBuf
+}
+
+impl FileMailer {
+    pub fn from_config(directory: &str) -> Self {
+        fs::create_dir_all(directory)
+            .unwrap_or_else(|error| panic!("Cannot create mail directory {}: {}", directory, error));
+        Self { transport: AsyncFileTransport::new(directory), directory: PathBuf::from(directory) }
+    }
+}
+
+#[async_trait]
+impl Mailer for FileMailer {
+    fn name(&self) -> &'static str {
+        "file"
+    }
+    async fn send(&self, message: Message) -> Result<(), MailError> {
+        match self.transport.send(message).await {
+            Ok(email_id) => {
+                tracing::info!(%email_id, "Wrote email file");
+                Ok(())
+            },
+            Err(error) => Err(MailError(format!("File transport error: {}", error)))
+        }
+    }
+    // write and remove a probe file, the directory may have been removed or made read only
+    async fn check(&self) -> Result<(), MailError> {
+        let probe = self.directory.join(".ready");
+        tokio::fs::write(&probe, b"").await
+            .and(tokio::fs::remove_file(&probe).await)
+            .map_err(|error| MailError(format!("Cannot write to mail directory {}: {}", self.directory.display(), error)))
+    }
+}
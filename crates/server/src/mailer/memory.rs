@@ -0,0 +1,46 @@
+use std::sync::{Arc, Mutex};
+
+use axum::async_trait;
+use lettre::Message;
+
+use super::{MailError, Mailer};
+
+// Email captured by the memory mailer
+#[derive(Debug, Clone)]
+#[cfg_attr(not(test), allow(dead_code))]
+pub struct SentMail {
+    pub to: Vec<String>,
+    pub subject: String,
+    pub raw: String
+}
+
+// Keeps sent email in memory so tests can inspect it, clones share the same outbox
+#[derive(Clone, Default)]
+pub struct MemoryMailer {
+    outbox: Arc<Mutex<Vec<SentMail>>>
+}
+
+impl MemoryMailer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // email sent so far, oldest first
+    #[cfg_attr(not(test), allow(dead_code))]
+    pub fn sent(&self) -> Vec<SentMail> {
+        self.outbox.lock().unwrap().clone()
+    }
+}
+
+#[async_trait]
+impl Mailer for MemoryMailer {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+    async fn send(&self, message: Message) -> Result<(), MailError> {
+        let to = message.envelope().to().iter().map(|address| address.to_string()).collect();
+        let subject = message.headers().get_raw("Subject").unwrap_or_default().to_string();
+        let raw = String::from_utf8_lossy(&message.formatted()).to_string();
+        self.outbox.lock().unwrap().push(SentMail { to, subject, raw });
+        Ok(())
+    }
+}
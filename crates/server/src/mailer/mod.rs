@@ -0,0 +1,101 @@
+use std::{fmt, str::FromStr, time::Duration};
+
+use axum::async_trait;
+use lettre::{message::Mailbox, Message};
+use once_cell::sync::OnceCell;
+
+use crate::config::MailConfig;
+
+pub mod smtp;
+pub mod file;
+pub mod memory;
+
+// longest the transport check may delay startup
+const CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+// global MAILER singleton
+static MAILER: OnceCell<Box<dyn Mailer>> = OnceCell::new();
+// sender mailbox for outgoing email
+static SENDER: OnceCell<Mailbox> = OnceCell::new();
+
+#[derive(Debug)]
+pub struct MailError(pub String);
+
+impl fmt::Display for MailError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl std::error::Error for MailError {}
+
+// Transport outgoing email is delivered with
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MailTransport {
+    Smtp,
+    File,
+    Memory
+}
+
+impl FromStr for MailTransport {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "smtp" => Ok(MailTransport::Smtp),
+            "file" => Ok(MailTransport::File),
+            "memory" => Ok(MailTransport::Memory),
+            _ => Err("expected smtp, file or memory".to_string())
+        }
+    }
+}
+
+// trait for transports delivering outgoing email
+#[async_trait]
+pub trait Mailer: Send + Sync {
+    // name of transport for logging
+    fn name(&self) -> &'static str;
+    async fn send(&self, message: Message) -> Result<(), MailError>;
+    // check the transport can currently deliver
+    async fn check(&self) -> Result<(), MailError> {
+        Ok(())
+    }
+}
+
+// function for initializing the MAILER singleton from the mail config, checking the transport once
+pub async fn create_mailer(mail: &MailConfig) {
+    let mailer: Box<dyn Mailer> = match mail.transport {
+        MailTransport::Smtp => Box::new(smtp::SmtpMailer::from_config(&mail.smtp)),
+        MailTransport::File => Box::new(file::FileMailer::from_config(&mail.file_dir)),
+        MailTransport::Memory => Box::new(memory::MemoryMailer::new())
+    };
+    tracing::info!(transport = mailer.name(), "Created mailer");
+    // queued email is retried by the job worker, so an unreachable transport does not stop the server
+    match tokio::time::timeout(CHECK_TIMEOUT, mailer.check()).await {
+        Ok(Ok(())) => {},
+        Ok(Err(error)) => tracing::warn!(transport = mailer.name(), %error, "Mail transport cannot deliver yet"),
+        Err(_) => tracing::warn!(transport = mailer.name(), "Mail transport did not answer in time")
+    }
+    set_mailer(mail.from.clone(), mailer);
+}
+
+// install mailer singleton, lets tests use a memory mailer they keep a handle to
+pub fn set_mailer(sender: Mailbox, mailer: Box<dyn Mailer>) {
+    if SENDER.set(sender).is_err() || MAILER.set(mailer).is_err() {
+        panic!("Mailer already created");
+    }
+}
+
+// check if the mailer was created, used by the readiness probe
+pub fn mailer_created() -> bool {
+    MAILER.get().is_some()
+}
+
+// getter for accessing global MAILER singleton in other modules
+pub fn get_mailer() -> &'static dyn Mailer {
+    MAILER.get().expect("Mailer not created").as_ref()
+}
+
+// getter for sender mailbox of outgoing email
+pub fn sender() -> Mailbox {
+    SENDER.get().expect("Mailer not created").to_owned()
+}
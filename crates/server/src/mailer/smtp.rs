@@ -0,0 +1,91 @@
+use std::str::FromStr;
+
+use axum::async_trait;
+use lettre::{
+    transport::smtp::{authentication::Credentials, client::{Tls, TlsParameters}},
+    AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor
+};
+
+use crate::config::SmtpConfig;
+
+use super::{MailError, Mailer};
+
+// How the connection to the SMTP server is secured
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SmtpSecurity {
+    // plaintext, only for trusted local relays
+    None,
+    // upgrade plaintext connection with STARTTLS
+    StartTls,
+    // TLS from the start of the connection
+    Tls
+}
+
+impl FromStr for SmtpSecurity {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(SmtpSecurity::None),
+            "starttls" => Ok(SmtpSecurity::StartTls),
+            "tls" => Ok(SmtpSecurity::Tls),
+            _ => Err("expected none, starttls or tls".to_string())
+        }
+    }
+}
+
+impl SmtpSecurity {
+    pub fn default_port(&self) -> u16 {
+        match self {
+            SmtpSecurity::None => 25,
+            SmtpSecurity::StartTls => 587,
+            SmtpSecurity::Tls => 465
+        }
+    }
+}
+
+pub struct SmtpMailer {
+    transport: AsyncSmtpTransport<Tokio1Executor>
+}
+
+impl SmtpMailer {
+    pub fn from_config(smtp: &SmtpConfig) -> Self {
+        let tls = match smtp.tls {
+            SmtpSecurity::None => Tls::None,
+            SmtpSecurity::StartTls => Tls::Required(tls_parameters(&smtp.host)),
+            SmtpSecurity::Tls => Tls::Wrapper(tls_parameters(&smtp.host))
+        };
+        let mut builder = AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&smtp.host)
+            .port(smtp.port)
+            .tls(tls);
+        // credentials are optional for relays that accept mail without auth
+        if let Some((username, password)) = &smtp.credentials {
+            builder = builder.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+        Self { transport: builder.build() }
+    }
+}
+
+fn tls_parameters(host: &str) -> TlsParameters {
+    TlsParameters::new(host.to_string())
+        .unwrap_or_else(|error| panic!("Cannot create TLS parameters for {}: {}", host, error))
+}
+
+#[async_trait]
+impl Mailer for SmtpMailer {
+    fn name(&self) -> &'static str {
+        "smtp"
+    }
+    async fn send(&self, message: Message) -> Result<(), MailError> {
+        match self.transport.send(message).await {
+            Ok(_) => Ok(()),
+            Err(error) => Err(MailError(format!("SMTP error: {}", error)))
+        }
+    }
+    async fn check(&self) -> Result<(), MailError> {
+        match self.transport.test_connection().await {
+            Ok(true) => Ok(()),
+            Ok(false) => Err(MailError("SMTP server did not answer".to_string())),
+            Err(error) => Err(MailError(format!("SMTP error: {}", error)))
+        }
+    }
+}
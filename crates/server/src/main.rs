@@ -1,50 +1,153 @@
-use std::{net::SocketAddr, path::PathBuf};
+use std::{future::IntoFuture, net::SocketAddr, path::PathBuf, sync::Arc, time::Duration};
 
-use axum::Router;
-use http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::{middleware::from_fn, Router};
+use http::{header::{AUTHORIZATION, CONTENT_TYPE}, HeaderName};
 use tower::ServiceBuilder;
-use tower_http::cors::{Any, CorsLayer};
+use tower_http::{
+    cors::{Any, CorsLayer},
+    request_id::{MakeRequestUuid, PropagateRequestIdLayer, SetRequestIdLayer},
+    trace::TraceLayer
+};
 
+mod cli;
+mod config;
+mod error;
 mod pool;
+mod repositories;
+mod schema;
+mod state;
+mod mailer;
 mod strategies;
 mod controllers;
 mod middleware;
+mod telemetry;
+mod shutdown;
+
+// header carrying the request ID, generated unless the client or a proxy sent one
+const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
 
 #[tokio::main]
 async fn main() {
 
+    // parse subcommand before anything else so --help works without configuration
+    let cli: cli::Cli = argh::from_env();
+
     // load environment variables with dotenv if debug
     if cfg!(debug_assertions) {
         match dotenv::dotenv() {
             Ok(path) => path,
             Err(error) => {
-                println!("Cannot access .env file: {}", error);
+                eprintln!("Cannot access .env file: {}", error);
                 PathBuf::from("")
             }
         };
     }
 
-    //create pg pool
-    pool::create_pool().await;
+    // load and validate configuration, exiting with a report of every problem
+    let config = config::load_config();
+
+    // structured logging configured by the log section
+    telemetry::init_tracing(&config.log);
+
+    match cli.command {
+        None | Some(cli::Command::Serve(_)) => serve(config).await,
+        Some(command) => cli::run(command, config).await
+    }
+}
+
+async fn serve(config: Arc<config::Config>) {
+    tracing::info!("Loaded configuration");
+
+    // collect metrics for the Prometheus scrape endpoint
+    let metrics_handle = telemetry::init_metrics();
+
+    // load signing keys and the breached password list before accepting requests
+    strategies::keys::load_keys(&config.keys);
+    strategies::passwords::load_passwords(&config.passwords);
+
+    // create pool and user repository for the configured database
+    let (pool, users) = pool::create_pool(&config.database).await;
+    tracing::info!(repository = users.name(), "Created SQL pool and user repository");
+
+    // check schema against embedded migrations, the memory database always starts empty so it is always migrated
+    let migrate = config.database.migrate || config.database.backend == pool::Backend::Memory;
+    match schema::prepare_schema(&pool, config.database.backend, migrate).await {
+        Ok(version) => tracing::info!(version, "Schema is current"),
+        Err(error) => {
+            tracing::error!("{}", error);
+            std::process::exit(1);
+        }
+    }
+    let state = state::AppState { config: config.clone(), pool, users, presence: Arc::default(), chat_hub: Arc::default() };
+
+    // validate mail configuration before accepting requests
+    mailer::create_mailer(&config.mail).await;
+
+    // background tasks and websockets hold a handle to stop on shutdown and to be waited for
+    let coordinator = shutdown::ShutdownCoordinator::new();
+
+    // purge expired password reset keys in the background
+    tokio::spawn(strategies::password_resets::sweep_expired_password_resets(config.clone(), state.pool.clone(), coordinator.subscribe()));
+
+    // send queued email and other background jobs
+    tokio::spawn(strategies::jobs::run_job_worker(config.clone(), state.pool.clone(), coordinator.subscribe()));
+
+    // purge finished jobs once their retention is over
+    tokio::spawn(strategies::jobs::sweep_finished_jobs(config.clone(), state.pool.clone(), coordinator.subscribe()));
 
     let cors = CorsLayer::permissive()
         .allow_origin(Any)
-        .allow_headers([AUTHORIZATION, CONTENT_TYPE])
+        .allow_headers([AUTHORIZATION, CONTENT_TYPE, REQUEST_ID_HEADER])
         .expose_headers(Any);
 
     let app = Router::new()
-        .nest("/ws", controllers::ws_controller::routes())
-        .nest("/auth", controllers::auth_controller::routes())
-        .nest("/user", controllers::users_controller::routes())
+        .nest("/ws", controllers::ws_controller::routes(coordinator.subscribe()))
+        .nest("/auth", controllers::auth_controller::routes(state.clone()))
+        .nest("/user", controllers::users_controller::routes(state.clone()))
+        .nest("/mfa", controllers::mfa_controller::routes(state.clone()))
+        .nest("/roles", controllers::roles_controller::routes(state.clone()))
+        .nest("/jobs", controllers::jobs_controller::routes(state.clone()))
+        .nest("/chat", controllers::chat_controller::routes(state.clone()))
+        .nest("/status", controllers::status_controller::routes())
+        .merge(controllers::health_controller::routes(metrics_handle))
         .layer(
             ServiceBuilder::new()
-            .layer(cors));
+            .layer(SetRequestIdLayer::new(REQUEST_ID_HEADER, MakeRequestUuid))
+            .layer(TraceLayer::new_for_http()
+                .make_span_with(telemetry::make_request_span)
+                .on_response(telemetry::on_response)
+                .on_failure(()))
+            .layer(PropagateRequestIdLayer::new(REQUEST_ID_HEADER))
+            .layer(from_fn(middleware::request_id::scope_request_id))
+            .layer(from_fn(middleware::track_metrics::track_metrics))
+            .layer(cors))
+        .with_state(state);
 
-    let addr = SocketAddr::from(([127, 0, 0, 1], 3001));
-    println!("Server listening on http://{}", addr);
+    let addr = config.server.bind_address;
+    tracing::info!("Server listening on http://{}", addr);
     let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    match axum::serve(listener, app).await {
-        Ok(_) => {},
-        Err(error) => panic!("Could not bind to {}: {}", addr,error)
+    // stop accepting connections once shutdown fires and let in-flight requests finish
+    let mut server_shutdown = coordinator.subscribe();
+    let mut server = tokio::spawn(axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move { server_shutdown.triggered().await })
+        .into_future());
+
+    tokio::select! {
+        result = &mut server => match result {
+            Ok(Ok(_)) => {},
+            Ok(Err(error)) => panic!("Could not bind to {}: {}", addr, error),
+            Err(error) => panic!("Server task failed: {}", error)
+        },
+        _ = shutdown::signal() => {}
+    }
+
+    // the server, websockets and workers all hold a handle, so draining waits for every one of them
+    let timeout = Duration::from_secs(config.server.shutdown_timeout);
+    tracing::info!(timeout_secs = config.server.shutdown_timeout, "Shutting down");
+    coordinator.trigger();
+    if coordinator.drain(timeout).await {
+        tracing::info!("Shutdown complete");
+    } else {
+        tracing::warn!("Shutdown timed out, exiting with work still in progress");
     }
 }
@@ -1 +1,5 @@
-pub mod token_authentication;
\ No newline at end of file
+pub mod token_authentication;
+pub mod require_permission;
+pub mod rate_limit;
+pub mod request_id;
+pub mod track_metrics;
@@ -0,0 +1,138 @@
+use std::{
+    collections::HashMap, convert::Infallible, net::SocketAddr, sync::{Arc, Mutex}, task::{Context, Poll}
+};
+
+use axum::{
+    body::{to_bytes, Body, Bytes},
+    extract::{ConnectInfo, Request},
+    response::{IntoResponse, Response}
+};
+use futures::future::BoxFuture;
+use tower::{Layer, Service};
+use types::auth::AuthErrorType;
+
+use crate::{config::Config, error::ApiError};
+
+// largest request body read to find the target
+const MAX_BODY_SIZE: usize = 64 * 1024;
+// number of tracked keys before expired windows are pruned
+const PRUNE_THRESHOLD: usize = 1024;
+
+// fixed window request counters by key
+struct WindowCounter {
+    // length of a window in seconds
+    window: u64,
+    max: u32,
+    windows: Mutex<HashMap<String, (u64, u32)>>
+}
+
+impl WindowCounter {
+    fn new(window: u64, max: u32) -> Self {
+        Self { window, max, windows: Mutex::new(HashMap::new()) }
+    }
+    // count request for key, returns seconds until the window resets if the limit is exceeded
+    fn hit(&self, key: String) -> Result<(), u64> {
+        let now = jsonwebtoken::get_current_timestamp();
+        let window = self.window;
+        let mut windows = self.windows.lock().unwrap();
+        if windows.len() > PRUNE_THRESHOLD {
+            windows.retain(|_, (started_at, _)| now < *started_at + window);
+        }
+        let (started_at, count) = windows.entry(key).or_insert((now, 0));
+        if now >= *started_at + window {
+            *started_at = now;
+            *count = 0;
+        }
+        if *count >= self.max {
+            return Err(*started_at + window - now);
+        }
+        *count += 1;
+        Ok(())
+    }
+}
+
+struct RateLimitState {
+    config: Arc<Config>,
+    // find username or email targeted by the request body
+    target: fn(&Config, &Bytes) -> Option<String>,
+    by_ip: WindowCounter,
+    by_target: WindowCounter
+}
+
+// layer for rate limiting requests by client IP and by targeted account
+#[derive(Clone)]
+pub struct RateLimitLayer {
+    state: Arc<RateLimitState>
+}
+
+impl RateLimitLayer {
+    // limit by client IP and by the account the body targets
+    pub fn by_target(config: Arc<Config>, target: fn(&Config, &Bytes) -> Option<String>) -> Self {
+        let rate_limit = &config.rate_limit;
+        let by_ip = WindowCounter::new(rate_limit.window, rate_limit.ip_max);
+        let by_target = WindowCounter::new(rate_limit.window, rate_limit.target_max);
+        Self {
+            state: Arc::new(RateLimitState { config, target, by_ip, by_target })
+        }
+    }
+}
+
+impl<S> Layer<S> for RateLimitLayer {
+    type Service = RateLimit<S>;
+    fn layer(&self, inner: S) -> Self::Service {
+        RateLimit { inner, state: self.state.clone() }
+    }
+}
+
+#[derive(Clone)]
+pub struct RateLimit<S> {
+    inner: S,
+    state: Arc<RateLimitState>
+}
+
+impl<S> Service<Request> for RateLimit<S>
+where
+    S: Service<Request, Response = Response, Error = Infallible> + Clone + Send + 'static,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = Infallible;
+    type Future = BoxFuture<'static, Result<Response, Infallible>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        // take the service that was driven to readiness
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+        let state = self.state.clone();
+        Box::pin(async move {
+            let client_ip = request.extensions().get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(address)| address.ip().to_string())
+                .unwrap_or("unknown".to_string());
+            if let Err(seconds) = state.by_ip.hit(client_ip) {
+                return Ok(too_many_requests(seconds));
+            }
+            // buffer body to find the target, then rebuild the request from it
+            let (parts, body) = request.into_parts();
+            let bytes = match to_bytes(body, MAX_BODY_SIZE).await {
+                Ok(bytes) => bytes,
+                Err(_) => return Ok(ApiError::bad_request("Request body is too large").into_response())
+            };
+            if let Some(target) = (state.target)(&state.config, &bytes) {
+                if let Err(seconds) = state.by_target.hit(target.trim().to_lowercase()) {
+                    return Ok(too_many_requests(seconds));
+                }
+            }
+            inner.call(Request::from_parts(parts, Body::from(bytes))).await
+        })
+    }
+}
+
+fn too_many_requests(seconds: u64) -> Response {
+    ApiError::auth(AuthErrorType::TooManyRequests)
+        .with_retry_after(seconds)
+        .into_response()
+}
@@ -0,0 +1,20 @@
+use axum::{extract::Request, middleware::Next, response::Response};
+
+use crate::telemetry;
+
+tokio::task_local! {
+    // request ID of the request being handled, lets error responses echo it back
+    static REQUEST_ID: String;
+}
+
+// request ID of the request the current task is handling, None outside a request
+pub fn current_request_id() -> Option<String> {
+    REQUEST_ID.try_with(|request_id| request_id.clone()).ok()
+        .filter(|request_id| !request_id.is_empty())
+}
+
+// middleware function making the request ID available for the rest of the request
+pub async fn scope_request_id(request: Request, next: Next) -> Response {
+    let request_id = telemetry::request_id(&request);
+    REQUEST_ID.scope(request_id, next.run(request)).await
+}
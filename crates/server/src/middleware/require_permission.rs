@@ -0,0 +1,29 @@
+use axum::{
+    extract::{Request, State},
+    middleware::Next,
+    response::Response
+};
+use types::auth::AuthErrorType;
+
+use crate::{error::ApiError, strategies::authentication::{AuthClaims, Claims}};
+
+// permission required to reach the routes behind the layer
+#[derive(Debug, Clone, Copy)]
+pub struct RequirePermission(pub &'static str);
+
+// middleware function for rejecting claims without the required permission,
+// must be layered inside authenticate_token::<AuthClaims> so X-Claims is set
+pub async fn require_permission(
+    State(RequirePermission(permission)): State<RequirePermission>,
+    request: Request,
+    next: Next,
+) -> Result<Response, ApiError> {
+    if !request.headers().contains_key("X-Claims") {
+        return Err(ApiError::auth(AuthErrorType::InvalidToken));
+    }
+    let claims = AuthClaims::from_header(request.headers());
+    if !claims.has_permission(permission) {
+        return Err(ApiError::auth(AuthErrorType::AccessDenied));
+    }
+    Ok(next.run(request).await)
+}
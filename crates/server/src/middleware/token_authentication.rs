@@ -9,6 +9,7 @@ use http::HeaderValue;
 use serde::Serialize;
 use serde_json::json;
 use base64::prelude::*;
+use tracing::Span;
 use crate::strategies::authentication::Claims;
 
 // middleware function for authenticating token
@@ -23,6 +24,10 @@ where T: Claims,T: Serialize, T: Debug {
         header_map.remove("X-Claims");
     }
     let json = json!(claims);
+    // attribute the rest of the request to the authenticated user
+    if let Some(user_uuid) = json.get("sub").and_then(|sub| sub.as_str()) {
+        Span::current().record("user_uuid", user_uuid);
+    }
     let encoded_text = BASE64_STANDARD.encode(json.to_string());
     request.headers_mut().insert("X-Claims", HeaderValue::from_str(&encoded_text).unwrap());
     next.run(request).await
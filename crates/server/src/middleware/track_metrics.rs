@@ -0,0 +1,22 @@
+use std::time::Instant;
+
+use axum::{
+    extract::{MatchedPath, Request},
+    middleware::Next,
+    response::Response
+};
+
+// middleware function counting requests and recording latency per route
+pub async fn track_metrics(request: Request, next: Next) -> Response {
+    let start = Instant::now();
+    // label by route template rather than raw path to keep the number of series bounded
+    let route = request.extensions().get::<MatchedPath>()
+        .map(|matched_path| matched_path.as_str().to_string())
+        .unwrap_or("unmatched".to_string());
+    let method = request.method().to_string();
+    let response = next.run(request).await;
+    let status = response.status().as_u16().to_string();
+    metrics::counter!("http_requests_total", "method" => method.clone(), "route" => route.clone(), "status" => status).increment(1);
+    metrics::histogram!("http_request_duration_seconds", "method" => method, "route" => route).record(start.elapsed().as_secs_f64());
+    response
+}
@@ -1,137 +1,82 @@
-use std::env;
-
-
-#[cfg(not(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres")),
-)))]
-use std::time::Duration;
-
-#[cfg(not(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres")),
-)))]
-use once_cell::sync::OnceCell;
-
-#[cfg(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres")),
-))]
-use sqlx::{any::Any, Pool};
-
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-use sqlx::{any::{Any, AnyPoolOptions}, Pool};
-
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-use sqlx::{PgPool, postgres::PgPoolOptions};
-
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-use sqlx::{SqlitePool, sqlite::SqlitePoolOptions};
-
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-// global POOL singleton
-static POOL: OnceCell<Pool<Any>> = OnceCell::new();
-
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-// global POOL singleton
-static POOL: OnceCell<PgPool> = OnceCell::new();
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-// global POOL singleton
-static POOL: OnceCell<SqlitePool> = OnceCell::new();
-
-// function for initializing the POOL singleton
-pub async fn create_pool() {
-
-    let database_url = match env::var("DATABASE_URL") {
-        Ok(url) => url,
-        Err(error) => {
-            panic!("Error getting DATABASE_URL: {}", error);
-        }
-    };
-    init_pool(database_url).await;
+use std::{str::FromStr, sync::Arc, time::Duration};
+
+use sqlx::{any::AnyPoolOptions, postgres::PgPoolOptions, sqlite::SqlitePoolOptions, AnyPool};
+
+use crate::{
+    config::DatabaseConfig,
+    repositories::{memory::MemoryUserRepository, sql::{PostgresUserRepository, SqliteUserRepository}, UserRepository}
+};
+
+// connections to the database across both pools
+const MAX_CONNECTIONS: u32 = 100;
+// share of MAX_CONNECTIONS kept by the user repository pool
+const USER_CONNECTIONS: u32 = 20;
+const IDLE_TIMEOUT: Duration = Duration::from_millis(1000);
+const MEMORY_DATABASE_URL: &str = "sqlite::memory:";
+
+// Database engine, chosen at runtime from the DATABASE_URL scheme
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backend {
+    Postgres,
+    Sqlite,
+    // users kept in process memory, for tests and demos
+    Memory
 }
 
-#[cfg(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres")),
-))]
-async fn init_pool(_database_url: String) {
-    panic!("Cannot have multiple database features enabled!")
+impl FromStr for Backend {
+    type Err = String;
+    fn from_str(url: &str) -> Result<Self, Self::Err> {
+        let scheme = url.split(':').next().unwrap_or_default();
+        match scheme.to_lowercase().as_str() {
+            "postgres" | "postgresql" => Ok(Backend::Postgres),
+            "sqlite" => Ok(Backend::Sqlite),
+            "memory" => Ok(Backend::Memory),
+            _ => Err("expected a postgres://, sqlite: or memory: URL".to_string())
+        }
+    }
 }
 
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-async fn init_pool(database_url: String) {
+// connect to the configured database, returning the pool for tables without a repository and the user repository
+pub async fn create_pool(database: &DatabaseConfig) -> (AnyPool, Arc<dyn UserRepository>) {
     sqlx::any::install_default_drivers();
-    let pool = match AnyPoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url).await {
-            Ok(pool) => {
-                println!("Created SQL pool from DB url");
-                pool
-            },
-            Err(error) => {
-                panic!("Could not create pool: {}", error);
-            }
-        };
-    POOL.set(pool).unwrap();
-}
-
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-async fn init_pool(database_url: String) {
-    let pool = match PgPoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url).await {
-            Ok(pool) => {
-                println!("Created postgresql pool");
-                pool
-            },
-            Err(error) => {
-                panic!("Could not create pool: {}", error);
-            }
-        };
-    POOL.set(pool).unwrap();
-}
-
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-async fn init_pool(database_url: String) {
-    let pool = match SqlitePoolOptions::new()
-        .max_connections(100)
-        .idle_timeout(Some(Duration::from_millis(1000)))
-        .connect(&database_url).await {
-            Ok(pool) => {
-                println!("Created sqlite pool");
-                pool
-            },
-            Err(error) => {
-                panic!("Could not create pool: {}", error);
-            }
-        };
-    POOL.set(pool).unwrap();
-}
-
-#[cfg(any(
-    all(feature = "postgres", any(feature = "sqlite")),
-    all(feature = "sqlite", any(feature = "postgres")),
-))]
-pub fn get_pool() -> Pool<Any> {
-    panic!("Cannot have multiple database features enabled!")
+    let result = match database.backend {
+        Backend::Memory => connect_memory().await,
+        _ => connect(database).await
+    };
+    match result {
+        Ok(connection) => connection,
+        Err(error) => {
+            panic!("Could not create pool: {}", error);
+        }
+    }
 }
 
-#[cfg(not(any(feature = "postgres", feature = "sqlite")))]
-pub fn get_pool() -> Pool<Any> {
-    POOL.get().unwrap().to_owned()
+async fn connect(database: &DatabaseConfig) -> Result<(AnyPool, Arc<dyn UserRepository>), sqlx::Error> {
+    let pool = AnyPoolOptions::new()
+        .max_connections(MAX_CONNECTIONS - USER_CONNECTIONS)
+        .idle_timeout(Some(IDLE_TIMEOUT))
+        .connect(&database.url).await?;
+    // the user repository gets its own pool of native connections for the backend, from the same budget
+    let users: Arc<dyn UserRepository> = match database.backend {
+        Backend::Postgres => Arc::new(PostgresUserRepository::new("postgres", PgPoolOptions::new()
+            .max_connections(USER_CONNECTIONS)
+            .idle_timeout(Some(IDLE_TIMEOUT))
+            .connect(&database.url).await?)),
+        _ => Arc::new(SqliteUserRepository::new("sqlite", SqlitePoolOptions::new()
+            .max_connections(USER_CONNECTIONS)
+            .idle_timeout(Some(IDLE_TIMEOUT))
+            .connect(&database.url).await?))
+    };
+    Ok((pool, users))
 }
 
-#[cfg(all(feature = "postgres", not(feature = "sqlite")))]
-// getter for accessing global POOL singleton in other modules
-pub fn get_pool() -> PgPool {
-    POOL.get().unwrap().to_owned()
+// users in memory, other tables in an in-memory SQLite database kept alive by a single connection that is never closed
+async fn connect_memory() -> Result<(AnyPool, Arc<dyn UserRepository>), sqlx::Error> {
+    let pool = AnyPoolOptions::new()
+        .max_connections(1)
+        .min_connections(1)
+        .idle_timeout(None)
+        .max_lifetime(None)
+        .connect(MEMORY_DATABASE_URL).await?;
+    Ok((pool, Arc::new(MemoryUserRepository::new())))
 }
-
-#[cfg(all(feature = "sqlite", not(feature = "postgres")))]
-// getter for accessing global POOL singleton in other modules
-pub fn get_pool() -> SqlitePool {
-    POOL.get().unwrap().to_owned()
-}
\ No newline at end of file
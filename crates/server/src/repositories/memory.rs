@@ -0,0 +1,158 @@
+use std::{error::Error, fmt, sync::Mutex};
+
+use axum::async_trait;
+use email_address::EmailAddress;
+use sqlx::error::{DatabaseError, ErrorKind};
+use types::user::User;
+
+use super::{NewUser, PoolStats, UserRepository};
+
+// Keeps users in memory for tests and demos, nothing survives a restart
+#[derive(Default)]
+pub struct MemoryUserRepository {
+    users: Mutex<Vec<User>>
+}
+
+impl MemoryUserRepository {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // apply change to the user with the uuid, returns RowNotFound if there is none
+    fn modify<T>(&self, uuid: &str, change: impl FnOnce(&mut User) -> T) -> Result<T, sqlx::Error> {
+        let mut users = self.users.lock().unwrap();
+        users.iter_mut()
+            .find(|user| user.uuid == uuid)
+            .map(change)
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+}
+
+// unique constraint failure matching what the SQL backends report for taken usernames and emails
+#[derive(Debug)]
+struct UniqueViolation(String);
+
+impl fmt::Display for UniqueViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "duplicate key value violates unique constraint on {}", self.0)
+    }
+}
+
+impl Error for UniqueViolation {}
+
+impl DatabaseError for UniqueViolation {
+    fn message(&self) -> &str {
+        "duplicate key value violates unique constraint"
+    }
+    fn as_error(&self) -> &(dyn Error + Send + Sync + 'static) {
+        self
+    }
+    fn as_error_mut(&mut self) -> &mut (dyn Error + Send + Sync + 'static) {
+        self
+    }
+    fn into_error(self: Box<Self>) -> Box<dyn Error + Send + Sync + 'static> {
+        self
+    }
+    fn kind(&self) -> ErrorKind {
+        ErrorKind::UniqueViolation
+    }
+}
+
+// check uuid, username and email are not taken by a user other than the one with the given id
+fn check_unique(users: &[User], id: i32, uuid: &str, username: &str, email: &str) -> Result<(), sqlx::Error> {
+    let taken = |column: &str| Err(sqlx::Error::Database(Box::new(UniqueViolation(column.to_string()))));
+    for user in users.iter().filter(|user| user.id != id) {
+        if user.uuid == uuid {
+            return taken("uuid");
+        }
+        if user.username == username {
+            return taken("username");
+        }
+        if user.email.as_str() == email {
+            return taken("email");
+        }
+    }
+    Ok(())
+}
+
+#[async_trait]
+impl UserRepository for MemoryUserRepository {
+    fn name(&self) -> &'static str {
+        "memory"
+    }
+    fn pool_stats(&self) -> Option<PoolStats> {
+        None
+    }
+    async fn find_by_username_or_email(&self, username_or_email: &str) -> Result<User, sqlx::Error> {
+        self.users.lock().unwrap().iter()
+            .find(|user| user.username == username_or_email || user.email.as_str() == username_or_email)
+            .cloned()
+            .ok_or(sqlx::Error::RowNotFound)
+    }
+    async fn find_by_uuid(&self, uuid: &str) -> Result<User, sqlx::Error> {
+        self.modify(uuid, |user| user.clone())
+    }
+    async fn all(&self) -> Result<Vec<User>, sqlx::Error> {
+        Ok(self.users.lock().unwrap().clone())
+    }
+    async fn insert(&self, new_user: NewUser) -> Result<User, sqlx::Error> {
+        let mut users = self.users.lock().unwrap();
+        check_unique(&users, 0, &new_user.uuid, &new_user.username, &new_user.email)?;
+        let user = User {
+            id: users.last().map(|user| user.id + 1).unwrap_or(1),
+            uuid: new_user.uuid,
+            username: new_user.username,
+            pass: new_user.pass,
+            email: EmailAddress::new_unchecked(new_user.email),
+            email_verified_at: None,
+            failed_logins: 0,
+            locked_until: None
+        };
+        users.push(user.clone());
+        Ok(user)
+    }
+    async fn update(&self, updated: &User) -> Result<(), sqlx::Error> {
+        let mut users = self.users.lock().unwrap();
+        check_unique(&users, updated.id, &updated.uuid, &updated.username, updated.email.as_str())?;
+        // like the SQL backends an update of a missing id changes nothing
+        if let Some(user) = users.iter_mut().find(|user| user.id == updated.id) {
+            user.uuid = updated.uuid.clone();
+            user.username = updated.username.clone();
+            user.pass = updated.pass.clone();
+            user.email = updated.email.clone();
+            user.email_verified_at = updated.email_verified_at;
+        }
+        Ok(())
+    }
+    async fn delete(&self, uuid: &str) -> Result<bool, sqlx::Error> {
+        let mut users = self.users.lock().unwrap();
+        let count = users.len();
+        users.retain(|user| user.uuid != uuid);
+        Ok(users.len() < count)
+    }
+    async fn set_email_verified(&self, uuid: &str, email: &str, verified_at: i64) -> Result<bool, sqlx::Error> {
+        let verified = self.modify(uuid, |user| {
+            if user.email.as_str() != email || user.email_verified_at.is_some() {
+                return false;
+            }
+            user.email_verified_at = Some(verified_at);
+            true
+        });
+        // the SQL backends report a missing user as nothing updated
+        Ok(verified.unwrap_or(false))
+    }
+    async fn increment_failed_logins(&self, uuid: &str) -> Result<i32, sqlx::Error> {
+        self.modify(uuid, |user| {
+            user.failed_logins += 1;
+            user.failed_logins
+        })
+    }
+    async fn lock(&self, uuid: &str, locked_until: i64) -> Result<(), sqlx::Error> {
+        self.modify(uuid, |user| user.locked_until = Some(locked_until)).or(Ok(()))
+    }
+    async fn reset_failed_logins(&self, uuid: &str) -> Result<(), sqlx::Error> {
+        self.modify(uuid, |user| {
+            user.failed_logins = 0;
+            user.locked_until = None;
+        }).or(Ok(()))
+    }
+}
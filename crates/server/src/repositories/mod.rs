@@ -0,0 +1,148 @@
+use axum::async_trait;
+use types::user::User;
+
+pub mod sql;
+pub mod memory;
+
+// Fields of a new user, with the password already hashed
+#[derive(Debug, Clone)]
+pub struct NewUser {
+    pub uuid: String,
+    pub username: String,
+    pub pass: String,
+    pub email: String
+}
+
+// Connection counts of the pool behind a repository
+pub struct PoolStats {
+    pub size: u32,
+    pub idle: usize,
+    pub max: u32
+}
+
+// trait for storage of user accounts, lookups of missing users fail with RowNotFound
+#[async_trait]
+pub trait UserRepository: Send + Sync {
+    // name of backend for logging
+    fn name(&self) -> &'static str;
+    // None for backends without a connection pool
+    fn pool_stats(&self) -> Option<PoolStats>;
+    async fn find_by_username_or_email(&self, username_or_email: &str) -> Result<User, sqlx::Error>;
+    async fn find_by_uuid(&self, uuid: &str) -> Result<User, sqlx::Error>;
+    async fn all(&self) -> Result<Vec<User>, sqlx::Error>;
+    async fn insert(&self, user: NewUser) -> Result<User, sqlx::Error>;
+    // store uuid, username, password hash, email and email verification of the user with the same id
+    async fn update(&self, user: &User) -> Result<(), sqlx::Error>;
+    // returns false if no user has the uuid
+    async fn delete(&self, uuid: &str) -> Result<bool, sqlx::Error>;
+    // mark email as verified if it is still the user's unverified address
+    async fn set_email_verified(&self, uuid: &str, email: &str, verified_at: i64) -> Result<bool, sqlx::Error>;
+    // count a failed login, returns the consecutive failures
+    async fn increment_failed_logins(&self, uuid: &str) -> Result<i32, sqlx::Error>;
+    async fn lock(&self, uuid: &str, locked_until: i64) -> Result<(), sqlx::Error>;
+    // clear failed logins and any lock
+    async fn reset_failed_logins(&self, uuid: &str) -> Result<(), sqlx::Error>;
+}
+
+// check if an insert or update failed because the username or email is taken
+pub fn is_unique_violation(error: &sqlx::Error) -> bool {
+    error.as_database_error().is_some_and(|error| error.is_unique_violation())
+}
+
+#[cfg(test)]
+mod tests {
+    use sqlx::sqlite::SqlitePoolOptions;
+
+    use crate::{pool::Backend, schema};
+
+    use super::{is_unique_violation, memory::MemoryUserRepository, sql::SqliteUserRepository, NewUser, UserRepository};
+
+    fn new_user(name: &str) -> NewUser {
+        NewUser {
+            uuid: format!("{name}-uuid"),
+            username: name.to_string(),
+            pass: "hash".to_string(),
+            email: format!("{name}@example.com")
+        }
+    }
+
+    // behaviour every backend must share, so controllers tested against the memory backend act the same on a database
+    async fn check_contract(users: &dyn UserRepository) {
+        let alice = users.insert(new_user("alice")).await.unwrap();
+        let bob = users.insert(new_user("bob")).await.unwrap();
+        assert_eq!(alice.username, "alice");
+        assert_eq!(alice.email.as_str(), "alice@example.com");
+        assert_eq!((alice.email_verified_at, alice.failed_logins, alice.locked_until), (None, 0, None));
+        assert!(bob.id > alice.id);
+
+        // lookups by username, email and uuid, missing users fail with RowNotFound
+        assert_eq!(users.find_by_username_or_email("alice").await.unwrap().uuid, alice.uuid);
+        assert_eq!(users.find_by_username_or_email("bob@example.com").await.unwrap().uuid, bob.uuid);
+        assert_eq!(users.find_by_uuid(&bob.uuid).await.unwrap().username, "bob");
+        assert!(matches!(users.find_by_uuid("missing").await, Err(sqlx::Error::RowNotFound)));
+        assert!(matches!(users.find_by_username_or_email("missing").await, Err(sqlx::Error::RowNotFound)));
+        let all: Vec<String> = users.all().await.unwrap().into_iter().map(|user| user.username).collect();
+        assert_eq!(all, ["alice", "bob"]);
+
+        // taken usernames and emails are unique violations on insert and update
+        let mut taken_username = new_user("carol");
+        taken_username.username = "alice".to_string();
+        assert!(is_unique_violation(&users.insert(taken_username).await.unwrap_err()));
+        let mut taken_email = new_user("carol");
+        taken_email.email = "bob@example.com".to_string();
+        assert!(is_unique_violation(&users.insert(taken_email).await.unwrap_err()));
+        let mut renamed = bob.clone();
+        renamed.username = "alice".to_string();
+        assert!(is_unique_violation(&users.update(&renamed).await.unwrap_err()));
+
+        // update stores the changed fields of the user with the same id
+        let mut updated = alice.clone();
+        updated.username = "alicia".to_string();
+        updated.pass = "new hash".to_string();
+        users.update(&updated).await.unwrap();
+        let found = users.find_by_uuid(&alice.uuid).await.unwrap();
+        assert_eq!((found.username.as_str(), found.pass.as_str()), ("alicia", "new hash"));
+
+        // email is verified once and only while it is still the user's address
+        assert!(!users.set_email_verified(&alice.uuid, "other@example.com", 10).await.unwrap());
+        assert!(users.set_email_verified(&alice.uuid, "alice@example.com", 10).await.unwrap());
+        assert!(!users.set_email_verified(&alice.uuid, "alice@example.com", 20).await.unwrap());
+        assert!(!users.set_email_verified("missing", "alice@example.com", 20).await.unwrap());
+        assert_eq!(users.find_by_uuid(&alice.uuid).await.unwrap().email_verified_at, Some(10));
+
+        // failed logins count up until reset, which also clears the lock
+        assert_eq!(users.increment_failed_logins(&bob.uuid).await.unwrap(), 1);
+        assert_eq!(users.increment_failed_logins(&bob.uuid).await.unwrap(), 2);
+        users.lock(&bob.uuid, 1000).await.unwrap();
+        let locked = users.find_by_uuid(&bob.uuid).await.unwrap();
+        assert_eq!((locked.failed_logins, locked.locked_until), (2, Some(1000)));
+        users.reset_failed_logins(&bob.uuid).await.unwrap();
+        let reset = users.find_by_uuid(&bob.uuid).await.unwrap();
+        assert_eq!((reset.failed_logins, reset.locked_until), (0, None));
+        assert!(matches!(users.increment_failed_logins("missing").await, Err(sqlx::Error::RowNotFound)));
+        users.lock("missing", 1000).await.unwrap();
+        users.reset_failed_logins("missing").await.unwrap();
+
+        // delete reports whether a user was removed
+        assert!(users.delete(&bob.uuid).await.unwrap());
+        assert!(!users.delete(&bob.uuid).await.unwrap());
+        assert!(matches!(users.find_by_uuid(&bob.uuid).await, Err(sqlx::Error::RowNotFound)));
+        assert_eq!(users.all().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn memory_repository_meets_contract() {
+        check_contract(&MemoryUserRepository::new()).await;
+    }
+
+    #[tokio::test]
+    async fn sqlite_repository_meets_contract() {
+        // a single connection keeps the in-memory database alive and shared
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .connect("sqlite::memory:").await
+            .unwrap();
+        schema::migrator(Backend::Sqlite).run(&pool).await.unwrap();
+        check_contract(&SqliteUserRepository::new("sqlite", pool)).await;
+    }
+}
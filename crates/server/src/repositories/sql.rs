@@ -0,0 +1,114 @@
+use axum::async_trait;
+use sqlx::{Database, Decode, Encode, Executor, FromRow, IntoArguments, Pool, Postgres, Sqlite, Type};
+use types::user::User;
+
+use super::{NewUser, PoolStats, UserRepository};
+
+pub type PostgresUserRepository = SqlUserRepository<Postgres>;
+pub type SqliteUserRepository = SqlUserRepository<Sqlite>;
+
+// Users in a SQL database, the queries run unchanged on Postgres and SQLite which both bind $1 style parameters
+pub struct SqlUserRepository<DB: Database> {
+    name: &'static str,
+    pool: Pool<DB>
+}
+
+impl<DB: Database> SqlUserRepository<DB> {
+    pub fn new(name: &'static str, pool: Pool<DB>) -> Self {
+        Self { name, pool }
+    }
+}
+
+#[async_trait]
+impl<DB: Database> UserRepository for SqlUserRepository<DB>
+where
+    for<'c> &'c mut DB::Connection: Executor<'c, Database = DB>,
+    for<'q> DB::Arguments<'q>: IntoArguments<'q, DB>,
+    for<'r> User: FromRow<'r, DB::Row>,
+    for<'r> (i32,): FromRow<'r, DB::Row>,
+    for<'q> &'q str: Encode<'q, DB> + Type<DB>,
+    for<'q> String: Encode<'q, DB> + Type<DB>,
+    for<'q> i32: Encode<'q, DB> + Decode<'q, DB> + Type<DB>,
+    for<'q> i64: Encode<'q, DB> + Type<DB>,
+    for<'q> Option<i64>: Encode<'q, DB>,
+{
+    fn name(&self) -> &'static str {
+        self.name
+    }
+    fn pool_stats(&self) -> Option<PoolStats> {
+        Some(PoolStats { size: self.pool.size(), idle: self.pool.num_idle(), max: self.pool.options().get_max_connections() })
+    }
+    async fn find_by_username_or_email(&self, username_or_email: &str) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM \"users\" WHERE username = $1 OR email = $1;")
+            .bind(username_or_email)
+            .fetch_one(&self.pool).await
+    }
+    async fn find_by_uuid(&self, uuid: &str) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM \"users\" WHERE uuid = $1;")
+            .bind(uuid)
+            .fetch_one(&self.pool).await
+    }
+    async fn all(&self) -> Result<Vec<User>, sqlx::Error> {
+        sqlx::query_as::<_, User>("SELECT * FROM \"users\" ORDER BY id;")
+            .fetch_all(&self.pool).await
+    }
+    async fn insert(&self, user: NewUser) -> Result<User, sqlx::Error> {
+        sqlx::query_as::<_, User>(
+            "INSERT INTO \"users\" (uuid, username, pass, email)
+            VALUES ($1, $2, $3, $4)
+            RETURNING *;")
+            .bind(user.uuid)
+            .bind(user.username)
+            .bind(user.pass)
+            .bind(user.email)
+            .fetch_one(&self.pool).await
+    }
+    async fn update(&self, user: &User) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE \"users\" SET uuid = $2, username = $3, pass = $4, email = $5, email_verified_at = $6 WHERE id = $1;")
+            .bind(user.id)
+            .bind(user.uuid.as_str())
+            .bind(user.username.as_str())
+            .bind(user.pass.as_str())
+            .bind(user.email.to_string())
+            .bind(user.email_verified_at)
+            .execute(&self.pool).await
+            .map(|_| ())
+    }
+    // the affected row count is not exposed across databases, so changed rows are returned instead
+    async fn delete(&self, uuid: &str) -> Result<bool, sqlx::Error> {
+        sqlx::query_as::<_, (i32,)>("DELETE FROM \"users\" WHERE uuid = $1 RETURNING id;")
+            .bind(uuid)
+            .fetch_optional(&self.pool).await
+            .map(|deleted| deleted.is_some())
+    }
+    async fn set_email_verified(&self, uuid: &str, email: &str, verified_at: i64) -> Result<bool, sqlx::Error> {
+        sqlx::query_as::<_, (i32,)>(
+            "UPDATE \"users\" SET email_verified_at = $3
+            WHERE uuid = $1 AND email = $2 AND email_verified_at IS NULL
+            RETURNING id;")
+            .bind(uuid)
+            .bind(email)
+            .bind(verified_at)
+            .fetch_optional(&self.pool).await
+            .map(|verified| verified.is_some())
+    }
+    async fn increment_failed_logins(&self, uuid: &str) -> Result<i32, sqlx::Error> {
+        sqlx::query_scalar::<_, i32>(
+            "UPDATE \"users\" SET failed_logins = failed_logins + 1 WHERE uuid = $1 RETURNING failed_logins;")
+            .bind(uuid)
+            .fetch_one(&self.pool).await
+    }
+    async fn lock(&self, uuid: &str, locked_until: i64) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE \"users\" SET locked_until = $2 WHERE uuid = $1;")
+            .bind(uuid)
+            .bind(locked_until)
+            .execute(&self.pool).await
+            .map(|_| ())
+    }
+    async fn reset_failed_logins(&self, uuid: &str) -> Result<(), sqlx::Error> {
+        sqlx::query("UPDATE \"users\" SET failed_logins = 0, locked_until = NULL WHERE uuid = $1;")
+            .bind(uuid)
+            .execute(&self.pool).await
+            .map(|_| ())
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashSet;
+
+use sqlx::{
+    migrate::{Migrate, Migrator},
+    AnyPool
+};
+
+use crate::pool::Backend;
+
+// migration sets embedded at compile time, build.rs reruns the embedding when they change
+static POSTGRES_MIGRATOR: Migrator = sqlx::migrate!("../../migrations/postgres");
+static SQLITE_MIGRATOR: Migrator = sqlx::migrate!("../../migrations/sqlite");
+
+// migration set for the connected database, the memory backend keeps its other tables in SQLite
+pub fn migrator(backend: Backend) -> &'static Migrator {
+    match backend {
+        Backend::Postgres => &POSTGRES_MIGRATOR,
+        Backend::Sqlite | Backend::Memory => &SQLITE_MIGRATOR
+    }
+}
+
+// version of the newest embedded migration
+pub fn latest_version(backend: Backend) -> i64 {
+    migrator(backend).iter()
+        .filter(|migration| migration.migration_type.is_up_migration())
+        .map(|migration| migration.version)
+        .max()
+        .unwrap_or(0)
+}
+
+// version of the newest successfully applied migration, None before any migration ran
+pub async fn current_version(pool: &AnyPool) -> Result<Option<i64>, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    conn.ensure_migrations_table().await?;
+    let applied = conn.list_applied_migrations().await?;
+    Ok(applied.iter().map(|migration| migration.version).max())
+}
+
+// check the schema against the embedded migrations, applying pending ones when allowed, returns the schema version
+pub async fn prepare_schema(pool: &AnyPool, backend: Backend, migrate: bool) -> Result<i64, String> {
+    let migrator = migrator(backend);
+    let mut conn = pool.acquire().await
+        .map_err(|error| format!("Could not connect to check schema: {error}"))?;
+    conn.ensure_migrations_table().await
+        .map_err(|error| format!("Could not create migrations table: {error}"))?;
+    // a migration that failed halfway needs fixing by hand before anything else touches the schema
+    if let Some(version) = conn.dirty_version().await.map_err(|error| format!("Could not read schema state: {error}"))? {
+        return Err(format!("Schema is dirty, migration {version} partially applied and must be repaired by hand"));
+    }
+    let applied = conn.list_applied_migrations().await
+        .map_err(|error| format!("Could not list applied migrations: {error}"))?;
+    let embedded: HashSet<i64> = migrator.iter().map(|migration| migration.version).collect();
+    // migrations unknown to this build were applied by a newer server
+    if let Some(unknown) = applied.iter().map(|migration| migration.version).filter(|version| !embedded.contains(version)).max() {
+        return Err(format!("Schema version {unknown} is newer than this server supports ({})", latest_version(backend)));
+    }
+    // applied migrations edited after the fact would leave the schema out of step with the code
+    for migration in migrator.iter().filter(|migration| migration.migration_type.is_up_migration()) {
+        if applied.iter().any(|applied| applied.version == migration.version && applied.checksum != migration.checksum) {
+            return Err(format!("Migration {} was changed after it was applied", migration.version));
+        }
+    }
+    let applied_versions: HashSet<i64> = applied.iter().map(|migration| migration.version).collect();
+    let pending: Vec<i64> = migrator.iter()
+        .filter(|migration| migration.migration_type.is_up_migration() && !applied_versions.contains(&migration.version))
+        .map(|migration| migration.version)
+        .collect();
+    drop(conn);
+    if !pending.is_empty() {
+        if !migrate {
+            return Err(format!("Schema has {} pending migrations, apply them with `server migrate` or set database.migrate", pending.len()));
+        }
+        // run also verifies checksums of applied migrations
+        migrator.run(pool).await
+            .map_err(|error| format!("Could not apply migrations: {error}"))?;
+        tracing::info!(count = pending.len(), "Applied migrations");
+    }
+    current_version(pool).await
+        .map(|version| version.unwrap_or(0))
+        .map_err(|error| format!("Could not read schema version: {error}"))
+}
@@ -0,0 +1,68 @@
+use std::time::Duration;
+
+use tokio::sync::{mpsc, watch};
+
+// handed to long running tasks, which stop taking new work once it fires
+#[derive(Clone)]
+pub struct Shutdown {
+    signal: watch::Receiver<bool>,
+    // only dropped when the task holding it finishes, so the coordinator can wait for every task
+    _guard: mpsc::Sender<()>
+}
+
+impl Shutdown {
+    // resolves once the server starts shutting down, immediately if it already is
+    pub async fn triggered(&mut self) {
+        let _ = self.signal.wait_for(|shutting_down| *shutting_down).await;
+    }
+}
+
+// owned by main, fires the shutdown and waits for the tasks holding a Shutdown
+pub struct ShutdownCoordinator {
+    signal: watch::Sender<bool>,
+    guard: mpsc::Sender<()>,
+    finished: mpsc::Receiver<()>
+}
+
+impl ShutdownCoordinator {
+    pub fn new() -> Self {
+        let (signal, _) = watch::channel(false);
+        let (guard, finished) = mpsc::channel(1);
+        ShutdownCoordinator { signal, guard, finished }
+    }
+
+    pub fn subscribe(&self) -> Shutdown {
+        Shutdown { signal: self.signal.subscribe(), _guard: self.guard.clone() }
+    }
+
+    pub fn trigger(&self) {
+        self.signal.send_replace(true);
+    }
+
+    // wait until every Shutdown was dropped, false if the timeout ran out first
+    pub async fn drain(self, timeout: Duration) -> bool {
+        let ShutdownCoordinator { guard, mut finished, .. } = self;
+        drop(guard);
+        // nothing is ever sent, recv returns None once the last sender is gone
+        tokio::time::timeout(timeout, finished.recv()).await.is_ok()
+    }
+}
+
+// resolves on SIGINT, or SIGTERM where the platform has it
+pub async fn signal() {
+    let interrupt = async {
+        tokio::signal::ctrl_c().await.expect("Could not listen for SIGINT");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Could not listen for SIGTERM")
+            .recv().await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = interrupt => tracing::info!("Received SIGINT"),
+        _ = terminate => tracing::info!("Received SIGTERM")
+    }
+}
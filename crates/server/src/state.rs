@@ -0,0 +1,18 @@
+use std::sync::Arc;
+
+use sqlx::AnyPool;
+
+use crate::{config::Config, repositories::UserRepository, strategies::{chat_hub::ChatHub, presence::PresenceTracker}};
+
+// State shared with every handler through axum
+#[derive(Clone)]
+pub struct AppState {
+    pub config: Arc<Config>,
+    // pool for tables without a repository
+    pub pool: AnyPool,
+    pub users: Arc<dyn UserRepository>,
+    // users connected to the chat, shared by the websocket and the presence endpoint
+    pub presence: Arc<PresenceTracker>,
+    // live websocket connections and their rooms
+    pub chat_hub: Arc<ChatHub>
+}
@@ -1,67 +1,36 @@
-use std::env;
-use axum::{async_trait, body::Body, extract::FromRequestParts, http::request::Parts, response::{IntoResponse, Response}, Json, RequestPartsExt};
+use axum::{async_trait, extract::{FromRef, FromRequestParts}, http::request::Parts, RequestPartsExt};
 use axum_extra::{headers::{Authorization, authorization::Bearer}, TypedHeader};
-use http::{HeaderMap, StatusCode};
-use jsonwebtoken::{decode, encode, Algorithm, DecodingKey, EncodingKey, Header, Validation};
-use once_cell::sync::Lazy;
+use http::HeaderMap;
+use jsonwebtoken::{decode, decode_header, encode, Validation};
 use serde::{Deserialize, Serialize};
-use serde_json::json;
-use types::auth::{AuthErrorBody, AuthErrorType, AuthToken};
+use types::auth::{AuthErrorType, AuthToken};
 use struct_iterable::Iterable;
 use base64::prelude::*;
 
-use super::users::get_db_user_by_uuid;
-
-// Keys for encoding/decoding authorization tokens with JWT_SECRET
-static KEYS: Lazy<Keys> = Lazy::new(|| {
-    let secret = std::env::var("AUTH_TOKEN_SECRET").expect("AUTH_TOKEN_SECRET must be configured.");
-    Keys::new(secret.as_bytes())
-});
-
-// Auth token lifetime
-static TOKEN_LIFETIME: Lazy<u64> = Lazy::new(|| {
-    u64::from_str_radix(env::var("AUTH_TOKEN_EXPIRE")
-        .expect("AUTH_TOKEN_EXPIRE must be configured").as_str(), 10)
-        .expect("Cannot parse AUTH_TOKEN_EXPIRE as u64")
-});
-
-// Auth request token lifetime
-static TOKEN_REQUESTER_LIFETIME: Lazy<u64> = Lazy::new(|| {
-    u64::from_str_radix(env::var("AUTH_REQUEST_TOKEN_EXPIRE")
-        .expect("AUTH_REQUEST_TOKEN_EXPIRE must be configured").as_str(), 10)
-        .expect("Cannot parse AUTH_REQUEST_TOKEN_EXPIRE as u64")
-});
-
-struct Keys {
-    encoding: EncodingKey,
-    decoding: DecodingKey
-}
+use uuid::Uuid;
 
-impl Keys {
-    fn new(secret: &[u8]) -> Self {
-        Self {
-            encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret)
-        }
-    }
-}
+use crate::{config::Config, error::ApiError, state::AppState};
+
+use super::{keys, roles::get_db_user_permissions, sessions};
 
 // trait for JWT claims
 pub trait Claims {
-    // create empty claim
-    fn default() -> Self;
     // create claim from UUID
-    async fn new(uuid: String) -> Result<Self, AuthError> where Self: Sized;
+    async fn new(state: &AppState, uuid: String) -> Result<Self, ApiError> where Self: Sized;
+    // check claim against server side state after signature validation
+    async fn validate(&self, _state: &AppState) -> Result<(), ApiError> {
+        Ok(())
+    }
     // generate AuthToken from Claims
-    fn generate_token(&self) -> Result<AuthToken, AuthError>
+    fn generate_token(&self) -> Result<AuthToken, ApiError>
     where Self: Serialize {
-        match encode(&Header::default(), &self, &KEYS.encoding) {
+        match encode(&keys::signing_header(), &self, &keys::signing_key().encoding) {
             Ok(encoded_string) => {
                 Ok(AuthToken::new(encoded_string))
             },
             Err(error) => {
-                println!("Error creating token: {}", error);
-                Err(AuthError::from_error_type(AuthErrorType::TokenCreation))
+                tracing::error!(%error, "Error creating token");
+                Err(ApiError::auth(AuthErrorType::TokenCreation))
             }
         }
     }
@@ -71,85 +40,86 @@ pub trait Claims {
         let value = headers.get("X-Claims").unwrap();
         return serde_json::from_str(&String::from_utf8(BASE64_STANDARD.decode(value).unwrap()).unwrap()).unwrap();
     }
-    fn from_string(encoded_str: &str) -> Result<Self, AuthError>
+    fn from_string(config: &Config, encoded_str: &str) -> Result<Self, ApiError>
     where Self: Sized,Self: for<'de> Deserialize<'de> {
-        let mut validation = Validation::new(Algorithm::HS256);
-        validation.leeway = 5;
-        validation.set_audience(&[env::var("COMPANY_DOMAIN").unwrap()]);
-        validation.set_issuer(&[env::var("COMPANY_NAME").unwrap()]);
-        match decode::<Self>(encoded_str, &KEYS.decoding, &validation) {
-            Ok(claims) => {
-                Ok(claims.claims)
-            },
-            Err(_) => {
-                Err(AuthError::from_error_type(AuthErrorType::InvalidToken))
-            }
-        }
+        decode_claims::<Self>(config, encoded_str)
     }
 }
 
-// build claims from request Authorization header
-async fn claims_from_request<T>(parts: &mut Parts) -> Result<T, AuthError>
+// decode and verify token with the verification key matching its kid header
+fn decode_claims<T>(config: &Config, token: &str) -> Result<T, ApiError>
 where T: for<'de> Deserialize<'de> {
+    let header = decode_header(token)
+        .map_err(|_| ApiError::auth(AuthErrorType::InvalidToken))?;
+    let key = keys::verification_key(header.kid.as_deref())
+        .ok_or(ApiError::auth(AuthErrorType::InvalidToken))?;
+    // Build validation strategy, only accepting the algorithm of the matched key
+    let mut validation = Validation::new(key.algorithm);
+    validation.leeway = 5;
+    validation.set_audience(&[&config.company.domain]);
+    validation.set_issuer(&[&config.company.name]);
+    match decode::<T>(token, &key.decoding, &validation) {
+        Ok(token_data) => Ok(token_data.claims),
+        Err(_) => Err(ApiError::auth(AuthErrorType::InvalidToken))
+    }
+}
+
+// build claims from request Authorization header
+async fn claims_from_request<T>(parts: &mut Parts, state: &AppState) -> Result<T, ApiError>
+where T: Claims, T: for<'de> Deserialize<'de> {
     // Extract the token from the authorization header
     let TypedHeader(Authorization(bearer)) = parts
         .extract::<TypedHeader<Authorization<Bearer>>>()
         .await
-        .map_err(|_| AuthError::from_error_type(AuthErrorType::InvalidToken))?;
-    // Build validation strategy
-    let mut validation = Validation::new(Algorithm::HS256);
-    validation.leeway = 5;
-    validation.set_audience(&[env::var("COMPANY_DOMAIN").unwrap()]);
-    validation.set_issuer(&[env::var("COMPANY_NAME").unwrap()]);
+        .map_err(|_| ApiError::auth(AuthErrorType::InvalidToken))?;
     // Decode the user data
-    let token_data = decode::<T>(bearer.token(), &KEYS.decoding, &validation)
-    .map_err(|_| AuthError::from_error_type(AuthErrorType::InvalidToken))?;
-    Ok(token_data.claims)
+    let claims = decode_claims::<T>(&state.config, bearer.token())?;
+    // Reject claims revoked server side
+    claims.validate(state).await?;
+    Ok(claims)
 }
 
-// Struct for JWT with access level
+// Struct for JWT with granted permissions
 #[derive(Debug, Serialize, Deserialize, Iterable)]
 pub struct AuthClaims {
     pub aud: String,
     pub com: String,
     pub sub: String,
     pub exp: u64,
-    pub acc: bool
+    pub permissions: Vec<String>
+}
+
+impl AuthClaims {
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == permission)
+    }
 }
 
 impl Claims for AuthClaims {
-    fn default() -> AuthClaims {
-        Self {
+    async fn new(state: &AppState, uuid: String) -> Result<AuthClaims, ApiError> {
+        let user = match state.users.find_by_uuid(&uuid).await {
+            Ok(user) => user,
+            Err(_) => return Err(ApiError::auth(AuthErrorType::TokenCreation))
+        };
+        let permissions = match get_db_user_permissions(&state.pool, user.uuid.clone()).await {
+            Ok(permissions) => permissions,
+            Err(error) => {
+                tracing::error!(user_uuid = %user.uuid, %error, "Error loading permissions");
+                return Err(ApiError::auth(AuthErrorType::TokenCreation));
+            }
+        };
+        Ok(Self {
             // user uuid
-            sub: String::new(),
+            sub: user.uuid,
             // issuer domain
-            aud: env::var("COMPANY_DOMAIN").unwrap(),
+            aud: state.config.company.domain.clone(),
             // issuer company
-            com: env::var("COMPANY_NAME").unwrap(),
+            com: state.config.company.name.clone(),
             // expiration timestamp from unix epoch
-                exp: jsonwebtoken::get_current_timestamp() + *TOKEN_LIFETIME,
-            // access level
-            acc: false
-        }
-    }
-    async fn new(uuid: String) -> Result<AuthClaims, AuthError> {
-        match get_db_user_by_uuid(uuid).await {
-            Ok(user) => Ok(Self {
-                // user uuid
-                sub: user.uuid,
-                // issuer domain
-                aud: env::var("COMPANY_DOMAIN").unwrap(),
-                // issuer company
-                com: env::var("COMPANY_NAME").unwrap(),
-                // expiration timestamp from unix epoch
-                exp: jsonwebtoken::get_current_timestamp() + *TOKEN_LIFETIME,
-                // access level
-                acc: user.is_admin
-            }),
-            Err(_) => {
-                Err(AuthError::from_error_type(AuthErrorType::TokenCreation))
-            }
-        }
+            exp: jsonwebtoken::get_current_timestamp() + state.config.tokens.auth_expire,
+            // permissions granted by the user's roles
+            permissions
+        })
     }
 }
 
@@ -159,11 +129,12 @@ impl Claims for AuthClaims {
 #[async_trait]
 impl<S> FromRequestParts<S> for AuthClaims
 where
-    S: Sync,
+    AppState: FromRef<S>,
+    S: Send + Sync,
 {
-    type Rejection = AuthError;
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        claims_from_request::<AuthClaims>(parts).await
+    type Rejection = ApiError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        claims_from_request::<AuthClaims>(parts, &AppState::from_ref(state)).await
     }
 }
 
@@ -173,34 +144,83 @@ pub struct AuthRequesterClaims {
     pub aud: String,
     pub com: String,
     pub sub: String,
-    pub exp: u64
+    pub exp: u64,
+    pub jti: String
 }
 
-impl Claims for AuthRequesterClaims {
-    fn default() -> AuthRequesterClaims {
-        Self {
+impl AuthRequesterClaims {
+    // create claim for UUID tracked by a session in the given family
+    async fn with_family(state: &AppState, uuid: String, family_id: String) -> Result<AuthRequesterClaims, ApiError> {
+        let claims = Self {
             // user uuid
-            sub: String::new(),
+            sub: uuid,
             // issuer domain
-            aud: env::var("COMPANY_DOMAIN").unwrap(),
+            aud: state.config.company.domain.clone(),
             // issuer company
-            com: env::var("COMPANY_NAME").unwrap(),
+            com: state.config.company.name.clone(),
             // expiration timestamp from unix epoch
-            exp: jsonwebtoken::get_current_timestamp() + *TOKEN_REQUESTER_LIFETIME,
-            // access level
+            exp: jsonwebtoken::get_current_timestamp() + state.config.tokens.request_expire,
+            // session token id
+            jti: Uuid::new_v4().to_string()
+        };
+        match sessions::insert_db_session(&state.pool, claims.jti.clone(), family_id, claims.sub.clone(), claims.exp).await {
+            Ok(_) => Ok(claims),
+            Err(error) => {
+                tracing::error!(user_uuid = %claims.sub, %error, "Error creating session");
+                Err(ApiError::auth(AuthErrorType::TokenCreation))
+            }
         }
     }
-    async fn new(uuid: String) -> Result<AuthRequesterClaims, AuthError> {
-        Ok(Self {
-            // user uuid
-            sub: uuid,
-            // issuer domain
-            aud: env::var("COMPANY_DOMAIN").unwrap(),
-            // issuer company
-            com: env::var("COMPANY_NAME").unwrap(),
-            // expiration timestamp from unix epoch
-            exp: jsonwebtoken::get_current_timestamp() + *TOKEN_REQUESTER_LIFETIME,
-        })
+    // replace claim with a new one in the same session family
+    pub async fn rotate(&self, state: &AppState) -> Result<AuthRequesterClaims, ApiError> {
+        let session = match sessions::get_db_session_by_jti(&state.pool, self.jti.clone()).await {
+            Ok(session) => session,
+            Err(_) => return Err(ApiError::auth(AuthErrorType::InvalidToken))
+        };
+        if let Err(error) = sessions::rotate_db_session(&state.pool, self.jti.clone()).await {
+            tracing::error!(user_uuid = %self.sub, %error, "Error rotating session");
+            return Err(ApiError::database());
+        }
+        Self::with_family(state, self.sub.clone(), session.family_id).await
+    }
+    // revoke session family this claim belongs to
+    pub async fn revoke(&self, state: &AppState) -> Result<(), ApiError> {
+        let session = match sessions::get_db_session_by_jti(&state.pool, self.jti.clone()).await {
+            Ok(session) => session,
+            Err(_) => return Err(ApiError::auth(AuthErrorType::InvalidToken))
+        };
+        match sessions::revoke_db_session_family(&state.pool, session.family_id).await {
+            Ok(_) => Ok(()),
+            Err(error) => {
+                tracing::error!(user_uuid = %self.sub, %error, "Error revoking session");
+                Err(ApiError::database())
+            }
+        }
+    }
+}
+
+impl Claims for AuthRequesterClaims {
+    async fn new(state: &AppState, uuid: String) -> Result<AuthRequesterClaims, ApiError> {
+        // start a new session family
+        Self::with_family(state, uuid, Uuid::new_v4().to_string()).await
+    }
+    async fn validate(&self, state: &AppState) -> Result<(), ApiError> {
+        let session = match sessions::get_db_session_by_jti(&state.pool, self.jti.clone()).await {
+            Ok(session) => session,
+            Err(_) => return Err(ApiError::auth(AuthErrorType::InvalidToken))
+        };
+        if session.revoked || session.user_uuid != self.sub {
+            return Err(ApiError::auth(AuthErrorType::InvalidToken));
+        }
+        // reuse of a rotated token means it leaked, revoke the whole family
+        if session.is_reused(state.config.tokens.session_rotation_grace) {
+            tracing::warn!(user_uuid = %self.sub, "Rotated token reused, revoking session family");
+            if let Err(error) = sessions::revoke_db_session_family(&state.pool, session.family_id).await {
+                tracing::error!(user_uuid = %self.sub, %error, "Error revoking session family");
+            }
+            return Err(ApiError::auth(AuthErrorType::InvalidToken));
+        }
+        Ok(())
     }
 }
 
@@ -210,33 +230,76 @@ impl Claims for AuthRequesterClaims {
 #[async_trait]
 impl<S> FromRequestParts<S> for AuthRequesterClaims
 where
-    S: Sync,
+    AppState: FromRef<S>,
+    S: Send + Sync,
 {
-    type Rejection = AuthError;
-    async fn from_request_parts(parts: &mut Parts, _state: &S) -> Result<Self, Self::Rejection> {
-        claims_from_request::<AuthRequesterClaims>(parts).await
+    type Rejection = ApiError;
+    async fn from_request_parts(parts: &mut Parts, state: &S) -> Result<Self, Self::Rejection> {
+        claims_from_request::<AuthRequesterClaims>(parts, &AppState::from_ref(state)).await
     }
 }
 
-#[derive(Debug)]
-pub struct AuthError(types::auth::AuthError);
+// Struct for JWT claims of a password login waiting for a second factor
+#[derive(Debug, Serialize, Deserialize)]
+pub struct MfaPendingClaims {
+    pub aud: String,
+    pub com: String,
+    pub sub: String,
+    pub exp: u64,
+    pub mfa: bool
+}
 
-impl AuthError {
-    pub fn from_error_type(error_type: AuthErrorType) -> Self {
-        Self {
-            0: types::auth::AuthError::from_error_type(error_type)
-        }
-    }
-    pub fn status(&self) -> StatusCode {
-        self.0.status.to_owned()
+impl Claims for MfaPendingClaims {
+    async fn new(state: &AppState, uuid: String) -> Result<MfaPendingClaims, ApiError> {
+        Ok(Self {
+            // user uuid
+            sub: uuid,
+            // issuer domain
+            aud: state.config.company.domain.clone(),
+            // issuer company
+            com: state.config.company.name.clone(),
+            // expiration timestamp from unix epoch
+            exp: jsonwebtoken::get_current_timestamp() + state.config.tokens.mfa_expire,
+            // second factor pending
+            mfa: true
+        })
     }
-    pub fn body(&self) -> AuthErrorBody {
-        self.0.body.to_owned()
+    async fn validate(&self, _state: &AppState) -> Result<(), ApiError> {
+        if !self.mfa {
+            return Err(ApiError::auth(AuthErrorType::InvalidToken));
+        }
+        Ok(())
     }
 }
 
-impl IntoResponse for AuthError {
-    fn into_response(self) -> Response<Body> {
-        (self.status(), Json(json!(self.body()))).into_response()
+// Struct for JWT claims of an emailed verification link
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EmailVerificationClaims {
+    pub aud: String,
+    pub com: String,
+    pub sub: String,
+    pub exp: u64,
+    pub eml: String
+}
+
+impl Claims for EmailVerificationClaims {
+    async fn new(state: &AppState, uuid: String) -> Result<EmailVerificationClaims, ApiError> {
+        match state.users.find_by_uuid(&uuid).await {
+            Ok(user) => Ok(Self {
+                // user uuid
+                sub: user.uuid,
+                // issuer domain
+                aud: state.config.company.domain.clone(),
+                // issuer company
+                com: state.config.company.name.clone(),
+                // expiration timestamp from unix epoch
+                exp: jsonwebtoken::get_current_timestamp() + state.config.tokens.email_verify_expire,
+                // email address being verified
+                eml: user.email.to_string()
+            }),
+            Err(_) => {
+                Err(ApiError::auth(AuthErrorType::TokenCreation))
+            }
+        }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,40 @@
+use sqlx::AnyPool;
+use types::chat::ChatMessage;
+
+// number of messages sent to a client after it joins
+pub const JOIN_HISTORY_LIMIT: i64 = 50;
+
+// store accepted message, the returned message carries the assigned id
+pub async fn insert_db_message(pool: &AnyPool, room_id: i64, sender_uuid: &str, username: &str, text: &str) -> Result<ChatMessage, sqlx::Error> {
+    let sent_at = jsonwebtoken::get_current_timestamp() as i64;
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO \"messages\" (room_id, sender_uuid, username, text, sent_at)
+        VALUES ($1, $2, $3, $4, $5) RETURNING id;")
+        .bind(room_id)
+        .bind(sender_uuid)
+        .bind(username)
+        .bind(text)
+        .bind(sent_at)
+        .fetch_one(pool).await?;
+    Ok(ChatMessage {
+        id,
+        room_id,
+        sender_uuid: sender_uuid.to_string(),
+        username: username.to_string(),
+        text: text.to_string(),
+        sent_at
+    })
+}
+
+// page of room messages older than before, or the latest ones without it, oldest first
+pub async fn get_db_messages(pool: &AnyPool, room_id: i64, before: Option<i64>, limit: i64) -> Result<Vec<ChatMessage>, sqlx::Error> {
+    let mut messages = sqlx::query_as::<_, ChatMessage>(
+        "SELECT id, room_id, sender_uuid, username, text, sent_at FROM \"messages\"
+        WHERE room_id = $1 AND id < $2 ORDER BY id DESC LIMIT $3;")
+        .bind(room_id)
+        .bind(before.unwrap_or(i64::MAX))
+        .bind(limit)
+        .fetch_all(pool).await?;
+    messages.reverse();
+    Ok(messages)
+}
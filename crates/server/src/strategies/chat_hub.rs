@@ -0,0 +1,195 @@
+use std::collections::HashMap;
+use std::sync::{atomic::{AtomicU64, Ordering}, Arc, Mutex};
+
+use tokio::{sync::{broadcast, mpsc::{self, error::TrySendError}, Notify}, task::JoinHandle};
+use tracing::Instrument;
+use types::{chat::ServerMessage, user::User};
+
+// queue of a connection, and the signal to drop it once it stops reading
+struct ConnectionHandle {
+    tx: mpsc::Sender<ServerMessage>,
+    overflow: Arc<Notify>
+}
+
+// room forwarding task per connection id
+type RoomForwards = HashMap<u64, JoinHandle<()>>;
+
+// live websocket connections and the rooms they listen to, shared with the HTTP endpoints that change memberships
+pub struct ChatHub {
+    // broadcast channel per room, created on the first join and dropped once nobody listens
+    rooms: Mutex<HashMap<i64, broadcast::Sender<ServerMessage>>>,
+    // open connections per user uuid, a user may be connected from several tabs or devices
+    connections: Mutex<HashMap<String, HashMap<u64, ConnectionHandle>>>,
+    // room forwarding task of each connection per room and user uuid, a user is in a room while any of their connections is
+    room_users: Mutex<HashMap<(i64, String), RoomForwards>>,
+    next_connection: AtomicU64
+}
+
+impl Default for ChatHub {
+    fn default() -> Self {
+        ChatHub {
+            rooms: Mutex::new(HashMap::new()),
+            connections: Mutex::new(HashMap::new()),
+            room_users: Mutex::new(HashMap::new()),
+            next_connection: AtomicU64::new(0)
+        }
+    }
+}
+
+impl ChatHub {
+    pub fn register(&self, user_uuid: &str, tx: mpsc::Sender<ServerMessage>, overflow: Arc<Notify>) -> u64 {
+        let id = self.next_connection.fetch_add(1, Ordering::Relaxed);
+        self.connections.lock().unwrap()
+            .entry(user_uuid.to_string())
+            .or_default()
+            .insert(id, ConnectionHandle { tx, overflow });
+        id
+    }
+
+    // forget a closed connection and take it out of its rooms
+    pub fn unregister(&self, user: &User, id: u64) {
+        {
+            let mut connections = self.connections.lock().unwrap();
+            if let Some(user_connections) = connections.get_mut(&user.uuid) {
+                user_connections.remove(&id);
+                if user_connections.is_empty() {
+                    connections.remove(&user.uuid);
+                }
+            }
+        }
+        let room_ids: Vec<i64> = self.room_users.lock().unwrap().iter()
+            .filter(|((_, user_uuid), forwards)| *user_uuid == user.uuid && forwards.contains_key(&id))
+            .map(|((room_id, _), _)| *room_id)
+            .collect();
+        for room_id in room_ids {
+            self.leave_room(room_id, user, id);
+        }
+    }
+
+    // deliver to every connection of a user without waiting, a connection whose queue is full stopped reading and is dropped
+    pub fn send_to_user(&self, user_uuid: &str, message: ServerMessage) {
+        let connections = self.connections.lock().unwrap();
+        let Some(user_connections) = connections.get(user_uuid) else {
+            return;
+        };
+        for (id, handle) in user_connections {
+            if let Err(TrySendError::Full(_)) = handle.tx.try_send(message.clone()) {
+                tracing::warn!(user_uuid, connection_id = id, "Dropping websocket that stopped reading");
+                handle.overflow.notify_one();
+            }
+        }
+    }
+
+    pub fn broadcast(&self, room_id: i64, message: ServerMessage) {
+        if let Some(tx) = self.rooms.lock().unwrap().get(&room_id) {
+            let _ = tx.send(message);
+        }
+    }
+
+    pub fn in_room(&self, room_id: i64, user_uuid: &str, id: u64) -> bool {
+        self.room_users.lock().unwrap()
+            .get(&(room_id, user_uuid.to_string()))
+            .is_some_and(|forwards| forwards.contains_key(&id))
+    }
+
+    // start forwarding a room to the connection, the room hears of the user joining with their first connection only
+    pub fn join_room(self: &Arc<Self>, room_id: i64, user: &User, id: u64, direct_tx: mpsc::Sender<ServerMessage>) {
+        let mut room_users = self.room_users.lock().unwrap();
+        let forwards = room_users.entry((room_id, user.uuid.clone())).or_default();
+        if forwards.contains_key(&id) {
+            return;
+        }
+        let first = forwards.is_empty();
+        forwards.insert(id, self.forward_room(room_id, direct_tx));
+        if first {
+            self.broadcast(room_id, ServerMessage::Join { room: room_id, user_uuid: user.uuid.clone(), username: user.username.clone(), at: now() });
+        }
+    }
+
+    // stop forwarding a room to the connection, returns false if it was not in the room
+    pub fn leave_room(&self, room_id: i64, user: &User, id: u64) -> bool {
+        let mut room_users = self.room_users.lock().unwrap();
+        let key = (room_id, user.uuid.clone());
+        let Some(forward) = room_users.get_mut(&key).and_then(|forwards| forwards.remove(&id)) else {
+            return false;
+        };
+        forward.abort();
+        if room_users.get(&key).is_some_and(|forwards| forwards.is_empty()) {
+            room_users.remove(&key);
+            self.broadcast(room_id, ServerMessage::Leave { room: room_id, user_uuid: user.uuid.clone(), username: user.username.clone(), at: now() });
+        }
+        true
+    }
+
+    // the user is no longer a member, so every connection of theirs stops listening to the room
+    pub fn remove_from_room(&self, room_id: i64, user: &User) {
+        let Some(forwards) = self.room_users.lock().unwrap().remove(&(room_id, user.uuid.clone())) else {
+            return;
+        };
+        for forward in forwards.into_values() {
+            forward.abort();
+        }
+        let leave = ServerMessage::Leave { room: room_id, user_uuid: user.uuid.clone(), username: user.username.clone(), at: now() };
+        self.broadcast(room_id, leave.clone());
+        // their connections no longer receive the room, so they are told directly
+        self.send_to_user(&user.uuid, leave);
+    }
+
+    fn subscribe(&self, room_id: i64) -> broadcast::Receiver<ServerMessage> {
+        self.rooms.lock().unwrap()
+            .entry(room_id)
+            .or_insert_with(|| broadcast::channel(100).0)
+            .subscribe()
+    }
+
+    fn release_room(&self, room_id: i64) {
+        let mut rooms = self.rooms.lock().unwrap();
+        if rooms.get(&room_id).is_some_and(|tx| tx.receiver_count() == 0) {
+            rooms.remove(&room_id);
+        }
+    }
+
+    // pass the messages of a room on to the connection until the task is aborted
+    fn forward_room(self: &Arc<Self>, room_id: i64, direct_tx: mpsc::Sender<ServerMessage>) -> JoinHandle<()> {
+        let rx = self.subscribe(room_id);
+        let mut subscription = RoomSubscription { hub: self.clone(), room_id, rx: Some(rx) };
+        tokio::spawn(async move {
+            if let Some(rx) = subscription.rx.as_mut() {
+                forward(rx, &direct_tx).await;
+            }
+        }.in_current_span())
+    }
+}
+
+// receiver of a room channel, which is given up once its last receiver is gone
+struct RoomSubscription {
+    hub: Arc<ChatHub>,
+    room_id: i64,
+    rx: Option<broadcast::Receiver<ServerMessage>>
+}
+
+impl Drop for RoomSubscription {
+    fn drop(&mut self) {
+        self.rx.take();
+        self.hub.release_room(self.room_id);
+    }
+}
+
+// pass broadcast frames on to a connection until either side closes
+pub async fn forward(rx: &mut broadcast::Receiver<ServerMessage>, direct_tx: &mpsc::Sender<ServerMessage>) {
+    loop {
+        let msg = match rx.recv().await {
+            Ok(msg) => msg,
+            // a slow client missed messages, tell it instead of dropping the connection
+            Err(broadcast::error::RecvError::Lagged(skipped)) => ServerMessage::System { text: format!("Missed {skipped} messages"), at: now() },
+            Err(broadcast::error::RecvError::Closed) => break
+        };
+        if direct_tx.send(msg).await.is_err() {
+            break;
+        }
+    }
+}
+
+fn now() -> i64 {
+    jsonwebtoken::get_current_timestamp() as i64
+}
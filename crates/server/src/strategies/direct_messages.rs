@@ -0,0 +1,71 @@
+use sqlx::{any::AnyQueryResult, AnyPool};
+use types::chat::DirectMessage;
+
+// conversation of a user with the last activity and unread count, usernames come from the user repository
+#[derive(Debug, sqlx::FromRow)]
+pub struct ConversationRow {
+    pub partner_uuid: String,
+    pub last_message_at: i64,
+    pub unread: i64
+}
+
+pub async fn insert_db_direct_message(pool: &AnyPool, sender_uuid: &str, recipient_uuid: &str, username: &str, text: &str) -> Result<DirectMessage, sqlx::Error> {
+    let sent_at = jsonwebtoken::get_current_timestamp() as i64;
+    let id = sqlx::query_scalar::<_, i64>(
+        "INSERT INTO \"direct_messages\" (sender_uuid, recipient_uuid, username, text, sent_at)
+        VALUES ($1, $2, $3, $4, $5) RETURNING id;")
+        .bind(sender_uuid)
+        .bind(recipient_uuid)
+        .bind(username)
+        .bind(text)
+        .bind(sent_at)
+        .fetch_one(pool).await?;
+    Ok(DirectMessage {
+        id,
+        sender_uuid: sender_uuid.to_string(),
+        recipient_uuid: recipient_uuid.to_string(),
+        username: username.to_string(),
+        text: text.to_string(),
+        sent_at
+    })
+}
+
+// page of the conversation between two users older than before, oldest first
+pub async fn get_db_direct_messages(pool: &AnyPool, user_uuid: &str, partner_uuid: &str, before: Option<i64>, limit: i64) -> Result<Vec<DirectMessage>, sqlx::Error> {
+    let mut messages = sqlx::query_as::<_, DirectMessage>(
+        "SELECT id, sender_uuid, recipient_uuid, username, text, sent_at FROM \"direct_messages\"
+        WHERE ((sender_uuid = $1 AND recipient_uuid = $2) OR (sender_uuid = $2 AND recipient_uuid = $1)) AND id < $3
+        ORDER BY id DESC LIMIT $4;")
+        .bind(user_uuid)
+        .bind(partner_uuid)
+        .bind(before.unwrap_or(i64::MAX))
+        .bind(limit)
+        .fetch_all(pool).await?;
+    messages.reverse();
+    Ok(messages)
+}
+
+// conversations of the user, most recently active first
+pub async fn get_db_conversations(pool: &AnyPool, user_uuid: &str) -> Result<Vec<ConversationRow>, sqlx::Error> {
+    sqlx::query_as::<_, ConversationRow>(
+        "SELECT CASE WHEN sender_uuid = $1 THEN recipient_uuid ELSE sender_uuid END AS partner_uuid,
+            MAX(sent_at) AS last_message_at,
+            SUM(CASE WHEN recipient_uuid = $1 AND read_at IS NULL THEN 1 ELSE 0 END) AS unread
+        FROM \"direct_messages\"
+        WHERE sender_uuid = $1 OR recipient_uuid = $1
+        GROUP BY partner_uuid
+        ORDER BY MAX(id) DESC;")
+        .bind(user_uuid)
+        .fetch_all(pool).await
+}
+
+// mark every message the partner sent to the user as read
+pub async fn mark_db_read(pool: &AnyPool, user_uuid: &str, partner_uuid: &str) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"direct_messages\" SET read_at = $3
+        WHERE recipient_uuid = $1 AND sender_uuid = $2 AND read_at IS NULL;")
+        .bind(user_uuid)
+        .bind(partner_uuid)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+}
@@ -0,0 +1,51 @@
+use std::fs;
+
+use lettre::{message::header::ContentType, Message};
+
+use crate::mailer;
+
+// read html template from resources and replace its placeholders
+pub fn render_template(template: &str, replacements: &[(&str, &str)]) -> Result<String, String> {
+    let html = fs::read_to_string(format!("crates/server/resources/{template}"))
+        .map_err(|error| format!("Could not read email template {template}: {error}"))?;
+    Ok(replacements.iter().fold(html, |html, (placeholder, value)| html.replace(placeholder, value)))
+}
+
+// send html email from the configured sender with the configured mailer
+pub async fn send_html_email(to: &str, subject: String, html: String) -> Result<(), String> {
+    // build email
+    let email = Message::builder()
+        .from(mailer::sender())
+        .to(to.parse().map_err(|error| format!("Could not parse recipient {to}: {error}"))?)
+        .subject(subject)
+        .header(ContentType::TEXT_HTML)
+        .body(html)
+        .map_err(|error| format!("Could not build email: {error}"))?;
+    mailer::get_mailer().send(email).await
+        .map_err(|error| format!("Failed to send email to {to}: {error}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mailer::{self, memory::MemoryMailer};
+
+    use super::send_html_email;
+
+    #[tokio::test]
+    async fn sends_through_installed_mailer() {
+        // the installed clone shares its outbox with the one kept here
+        let outbox = MemoryMailer::new();
+        mailer::set_mailer("Acme <noreply@acme.test>".parse().unwrap(), Box::new(outbox.clone()));
+        let reset_url = "acme.test/reset?key=abc123&email=alice@example.com";
+        send_html_email("alice@example.com", "Password Reset Requested for Acme".to_string(), format!("<a href=\"{reset_url}\">Reset</a>")).await.unwrap();
+        let sent = outbox.sent();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].to, ["alice@example.com"]);
+        assert_eq!(sent[0].subject, "Password Reset Requested for Acme");
+        assert!(sent[0].raw.contains("From: Acme <noreply@acme.test>"));
+        assert!(sent[0].raw.contains(reset_url));
+        // a recipient that is not an address is rejected before reaching the transport
+        assert!(send_html_email("not an address", "Subject".to_string(), String::new()).await.is_err());
+        assert_eq!(outbox.sent().len(), 1);
+    }
+}
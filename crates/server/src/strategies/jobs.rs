@@ -0,0 +1,235 @@
+use std::{sync::Arc, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use sqlx::{any::AnyQueryResult, AnyPool};
+use types::jobs::JobInfo;
+
+use crate::{config::Config, shutdown::Shutdown};
+
+use super::email;
+
+// longest delay between retries
+const MAX_RETRY_DELAY: i64 = 3600 * 6;
+// number of jobs listed for admins
+const JOB_LIST_LIMIT: i64 = 100;
+// seconds between purges of finished jobs
+const JOB_SWEEP_INTERVAL: u64 = 3600;
+
+const STATUS_PENDING: &str = "pending";
+const STATUS_RUNNING: &str = "running";
+const STATUS_COMPLETED: &str = "completed";
+const STATUS_FAILED: &str = "failed";
+
+const KIND_EMAIL: &str = "email";
+
+#[derive(Debug, sqlx::FromRow)]
+struct Job {
+    id: i32,
+    kind: String,
+    payload: String,
+    attempts: i32,
+    max_attempts: i32
+}
+
+// payload of a queued email
+#[derive(Debug, Serialize, Deserialize)]
+struct EmailJob {
+    to: String,
+    subject: String,
+    html: String
+}
+
+async fn insert_db_job(config: &Config, pool: &AnyPool, kind: &str, payload: String) -> Result<AnyQueryResult, sqlx::Error> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    sqlx::query(
+        "INSERT INTO \"jobs\" (kind, payload, status, attempts, max_attempts, run_at, created_at, updated_at)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8);")
+        .bind(kind)
+        .bind(payload)
+        .bind(STATUS_PENDING)
+        .bind(0)
+        .bind(config.jobs.max_attempts)
+        .bind(now)
+        .bind(now)
+        .bind(now)
+        .execute(pool).await
+}
+
+// queue html email for the worker to send
+pub async fn enqueue_email(config: &Config, pool: &AnyPool, to: String, subject: String, html: String) -> Result<AnyQueryResult, sqlx::Error> {
+    let payload = serde_json::to_string(&EmailJob { to, subject, html })
+        .map_err(|error| sqlx::Error::Encode(Box::new(error)))?;
+    insert_db_job(config, pool, KIND_EMAIL, payload).await
+}
+
+// claim the oldest due pending job, returns None if there is none or another worker claimed it first
+async fn claim_db_job(pool: &AnyPool) -> Result<Option<Job>, sqlx::Error> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    let job = sqlx::query_as::<_, Job>(
+        "SELECT id, kind, payload, attempts, max_attempts FROM \"jobs\"
+        WHERE status = $1 AND run_at <= $2
+        ORDER BY run_at LIMIT 1;")
+        .bind(STATUS_PENDING)
+        .bind(now)
+        .fetch_optional(pool).await?;
+    let Some(mut job) = job else {
+        return Ok(None);
+    };
+    let claimed = sqlx::query(
+        "UPDATE \"jobs\" SET status = $2, attempts = attempts + 1, claimed_at = $3, updated_at = $3
+        WHERE id = $1 AND status = $4;")
+        .bind(job.id)
+        .bind(STATUS_RUNNING)
+        .bind(now)
+        .bind(STATUS_PENDING)
+        .execute(pool).await?
+        .rows_affected() > 0;
+    job.attempts += 1;
+    Ok(claimed.then_some(job))
+}
+
+// payloads can hold reset keys and verification links, so they are cleared once a job is finished
+// updates only apply to the attempt that was claimed, in case the lease ran out and another worker took the job back
+async fn complete_db_job(pool: &AnyPool, job: &Job) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"jobs\" SET status = $2, payload = '', last_error = NULL, claimed_at = NULL, updated_at = $3
+        WHERE id = $1 AND status = $4 AND attempts = $5;")
+        .bind(job.id)
+        .bind(STATUS_COMPLETED)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .bind(STATUS_RUNNING)
+        .bind(job.attempts)
+        .execute(pool).await
+}
+
+// schedule retry with the delay doubled per attempt, or mark failed once out of attempts
+async fn fail_db_job(config: &Config, pool: &AnyPool, job: &Job, error: String) -> Result<AnyQueryResult, sqlx::Error> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    let (status, run_at, payload) = if job.attempts >= job.max_attempts {
+        (STATUS_FAILED, now, "")
+    } else {
+        let delay = config.jobs.retry_base.saturating_mul(1 << (job.attempts - 1).min(20)).min(MAX_RETRY_DELAY);
+        (STATUS_PENDING, now + delay, job.payload.as_str())
+    };
+    sqlx::query(
+        "UPDATE \"jobs\" SET status = $2, run_at = $3, last_error = $4, updated_at = $5, payload = $6, claimed_at = NULL
+        WHERE id = $1 AND status = $7 AND attempts = $8;")
+        .bind(job.id)
+        .bind(status)
+        .bind(run_at)
+        .bind(error)
+        .bind(now)
+        .bind(payload)
+        .bind(STATUS_RUNNING)
+        .bind(job.attempts)
+        .execute(pool).await
+}
+
+// return jobs whose lease ran out to the queue, their worker stopped or is stuck
+// jobs running from before leases were tracked have no claim time and count as expired
+async fn requeue_db_expired_jobs(config: &Config, pool: &AnyPool) -> Result<AnyQueryResult, sqlx::Error> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    sqlx::query(
+        "UPDATE \"jobs\" SET status = $2, claimed_at = NULL, updated_at = $3
+        WHERE status = $1 AND (claimed_at IS NULL OR claimed_at <= $4);")
+        .bind(STATUS_RUNNING)
+        .bind(STATUS_PENDING)
+        .bind(now)
+        .bind(now - config.jobs.lease)
+        .execute(pool).await
+}
+
+pub async fn delete_db_finished_jobs(config: &Config, pool: &AnyPool) -> Result<AnyQueryResult, sqlx::Error> {
+    let cutoff = jsonwebtoken::get_current_timestamp() as i64 - config.jobs.retention;
+    sqlx::query("DELETE FROM \"jobs\" WHERE status IN ($1, $2) AND updated_at <= $3;")
+        .bind(STATUS_COMPLETED)
+        .bind(STATUS_FAILED)
+        .bind(cutoff)
+        .execute(pool).await
+}
+
+// periodically purge finished jobs past their retention until the server shuts down
+pub async fn sweep_finished_jobs(config: Arc<Config>, pool: AnyPool, mut shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(Duration::from_secs(JOB_SWEEP_INTERVAL));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = shutdown.triggered() => break
+        }
+        match delete_db_finished_jobs(&config, &pool).await {
+            Ok(result) => if result.rows_affected() > 0 {
+                tracing::info!(count = result.rows_affected(), "Removed finished jobs");
+            },
+            Err(error) => tracing::error!(%error, "Error removing finished jobs")
+        }
+    }
+}
+
+// most recent jobs, optionally only those with the given status
+pub async fn get_db_jobs(pool: &AnyPool, status: Option<String>) -> Result<Vec<JobInfo>, sqlx::Error> {
+    sqlx::query_as::<_, JobInfo>(
+        "SELECT id, kind, status, attempts, max_attempts, run_at, last_error, created_at, updated_at FROM \"jobs\"
+        WHERE $1 IS NULL OR status = $1
+        ORDER BY id DESC LIMIT $2;")
+        .bind(status)
+        .bind(JOB_LIST_LIMIT)
+        .fetch_all(pool).await
+}
+
+async fn run_job(job: &Job) -> Result<(), String> {
+    match job.kind.as_str() {
+        KIND_EMAIL => {
+            let email_job: EmailJob = serde_json::from_str(&job.payload)
+                .map_err(|error| format!("Invalid email job payload: {error}"))?;
+            email::send_html_email(&email_job.to, email_job.subject, email_job.html).await
+        },
+        kind => Err(format!("Unknown job kind: {kind}"))
+    }
+}
+
+// process queued jobs until the server shuts down, a job already running is allowed to finish
+pub async fn run_job_worker(config: Arc<Config>, pool: AnyPool, mut shutdown: Shutdown) {
+    let poll_interval = Duration::from_secs(config.jobs.poll_interval);
+    loop {
+        // other server instances may be running jobs too, only expired leases are taken back
+        match requeue_db_expired_jobs(&config, &pool).await {
+            Ok(result) => if result.rows_affected() > 0 {
+                tracing::info!(count = result.rows_affected(), "Requeued jobs with expired leases");
+            },
+            Err(error) => tracing::error!(%error, "Error requeueing jobs with expired leases")
+        }
+        let claimed = tokio::select! {
+            claimed = claim_db_job(&pool) => claimed,
+            _ = shutdown.triggered() => break
+        };
+        let job = match claimed {
+            Ok(Some(job)) => job,
+            Ok(None) => {
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => continue,
+                    _ = shutdown.triggered() => break
+                }
+            },
+            Err(error) => {
+                tracing::error!(%error, "Error claiming job");
+                tokio::select! {
+                    _ = tokio::time::sleep(poll_interval) => continue,
+                    _ = shutdown.triggered() => break
+                }
+            }
+        };
+        let result = match run_job(&job).await {
+            Ok(_) => complete_db_job(&pool, &job).await,
+            Err(error) => {
+                tracing::warn!(job_id = job.id, attempt = job.attempts, max_attempts = job.max_attempts, %error, "Job attempt failed");
+                fail_db_job(&config, &pool, &job, error).await
+            }
+        };
+        match result {
+            Ok(result) if result.rows_affected() == 0 => tracing::warn!(job_id = job.id, "Job lease expired before it finished"),
+            Ok(_) => {},
+            Err(error) => tracing::error!(job_id = job.id, %error, "Error updating job")
+        }
+    }
+    tracing::info!("Job worker stopped");
+}
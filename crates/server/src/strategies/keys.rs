@@ -0,0 +1,174 @@
+use std::fs;
+
+use base64::prelude::*;
+use jsonwebtoken::{
+    jwk::{AlgorithmParameters, CommonParameters, EllipticCurve, Jwk, JwkSet, KeyAlgorithm, OctetKeyPairParameters, OctetKeyPairType, PublicKeyUse, RSAKeyParameters, RSAKeyType},
+    Algorithm, DecodingKey, EncodingKey, Header
+};
+use once_cell::sync::OnceCell;
+use rsa::{pkcs1::DecodeRsaPublicKey, pkcs8::DecodePublicKey, traits::PublicKeyParts, RsaPublicKey};
+
+use crate::config::KeyConfig;
+
+// DER prefix of an Ed25519 SubjectPublicKeyInfo, followed by the 32 byte public key
+const ED25519_SPKI_PREFIX: [u8; 12] = [0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00];
+
+// Keys for signing and verifying tokens, loaded once from config at boot
+static KEY_STORE: OnceCell<KeyStore> = OnceCell::new();
+
+pub struct SigningKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub encoding: EncodingKey
+}
+
+pub struct VerificationKey {
+    pub kid: String,
+    pub algorithm: Algorithm,
+    pub decoding: DecodingKey,
+    // public JWK, absent for symmetric keys which must never be published
+    pub jwk: Option<Jwk>
+}
+
+pub struct KeyStore {
+    signing: SigningKey,
+    verification: Vec<VerificationKey>
+}
+
+impl KeyStore {
+    fn from_config(keys: &KeyConfig) -> Self {
+        let algorithm = keys.algorithm;
+        let kid = keys.key_id.clone();
+        match algorithm {
+            Algorithm::HS256 => {
+                let secret = keys.secret.as_ref().expect("keys.secret must be configured.");
+                Self {
+                    signing: SigningKey {
+                        kid: kid.clone(),
+                        algorithm,
+                        encoding: EncodingKey::from_secret(secret.as_bytes())
+                    },
+                    verification: vec![VerificationKey {
+                        kid,
+                        algorithm,
+                        decoding: DecodingKey::from_secret(secret.as_bytes()),
+                        jwk: None
+                    }]
+                }
+            },
+            Algorithm::RS256 | Algorithm::EdDSA => {
+                let private_path = keys.private_key.as_ref().expect("keys.private_key must be configured.");
+                let private_pem = fs::read(private_path)
+                    .unwrap_or_else(|error| panic!("Cannot read private key {}: {}", private_path, error));
+                let encoding = match algorithm {
+                    Algorithm::RS256 => EncodingKey::from_rsa_pem(&private_pem),
+                    _ => EncodingKey::from_ed_pem(&private_pem)
+                }.unwrap_or_else(|error| panic!("Cannot parse private key {}: {}", private_path, error));
+                let public_path = keys.public_key.as_ref().expect("keys.public_key must be configured.");
+                let current_key = load_public_key(kid.clone(), public_path);
+                if current_key.algorithm != algorithm {
+                    panic!("Public key {} does not match keys.algorithm", public_path);
+                }
+                // previously used public keys stay valid until tokens signed with them expire
+                let mut verification = vec![current_key];
+                for (previous_kid, previous_path) in &keys.previous_keys {
+                    verification.push(load_public_key(previous_kid.clone(), previous_path));
+                }
+                Self {
+                    signing: SigningKey { kid, algorithm, encoding },
+                    verification
+                }
+            },
+            _ => panic!("Unsupported keys.algorithm: {:?}", algorithm)
+        }
+    }
+}
+
+// load public key PEM, inferring EdDSA or RS256 from its contents
+fn load_public_key(kid: String, path: &str) -> VerificationKey {
+    let pem_bytes = fs::read(path).unwrap_or_else(|error| panic!("Cannot read public key {}: {}", path, error));
+    let parsed = pem::parse(&pem_bytes).unwrap_or_else(|error| panic!("Cannot parse public key {}: {}", path, error));
+    let der = parsed.contents();
+    if parsed.tag() == "PUBLIC KEY" && der.len() == 44 && der.starts_with(&ED25519_SPKI_PREFIX) {
+        let jwk = Jwk {
+            common: jwk_common_parameters(&kid, KeyAlgorithm::EdDSA),
+            algorithm: AlgorithmParameters::OctetKeyPair(OctetKeyPairParameters {
+                key_type: OctetKeyPairType::OctetKeyPair,
+                curve: EllipticCurve::Ed25519,
+                x: BASE64_URL_SAFE_NO_PAD.encode(&der[12..])
+            })
+        };
+        return VerificationKey {
+            kid,
+            algorithm: Algorithm::EdDSA,
+            decoding: DecodingKey::from_ed_pem(&pem_bytes).expect("Cannot parse Ed25519 public key"),
+            jwk: Some(jwk)
+        };
+    }
+    let rsa_key = match parsed.tag() {
+        "RSA PUBLIC KEY" => RsaPublicKey::from_pkcs1_der(der).ok(),
+        "PUBLIC KEY" => RsaPublicKey::from_public_key_der(der).ok(),
+        _ => None
+    }.unwrap_or_else(|| panic!("Public key {} is neither Ed25519 nor RSA", path));
+    let jwk = Jwk {
+        common: jwk_common_parameters(&kid, KeyAlgorithm::RS256),
+        algorithm: AlgorithmParameters::RSA(RSAKeyParameters {
+            key_type: RSAKeyType::RSA,
+            n: BASE64_URL_SAFE_NO_PAD.encode(rsa_key.n().to_bytes_be()),
+            e: BASE64_URL_SAFE_NO_PAD.encode(rsa_key.e().to_bytes_be())
+        })
+    };
+    VerificationKey {
+        kid,
+        algorithm: Algorithm::RS256,
+        decoding: DecodingKey::from_rsa_pem(&pem_bytes).expect("Cannot parse RSA public key"),
+        jwk: Some(jwk)
+    }
+}
+
+fn jwk_common_parameters(kid: &str, key_algorithm: KeyAlgorithm) -> CommonParameters {
+    CommonParameters {
+        public_key_use: Some(PublicKeyUse::Signature),
+        key_algorithm: Some(key_algorithm),
+        key_id: Some(kid.to_string()),
+        ..Default::default()
+    }
+}
+
+fn key_store() -> &'static KeyStore {
+    KEY_STORE.get().expect("Keys not loaded")
+}
+
+// getter for key used to sign new tokens
+pub fn signing_key() -> &'static SigningKey {
+    &key_store().signing
+}
+
+// build token header with algorithm and kid of the signing key
+pub fn signing_header() -> Header {
+    let signing = signing_key();
+    let mut header = Header::new(signing.algorithm);
+    header.kid = Some(signing.kid.clone());
+    header
+}
+
+// find verification key by token kid, tokens without a kid predate key IDs and use the signing key
+pub fn verification_key(kid: Option<&str>) -> Option<&'static VerificationKey> {
+    let key_store = key_store();
+    let kid = kid.unwrap_or(&key_store.signing.kid);
+    key_store.verification.iter().find(|key| key.kid == kid)
+}
+
+// public keys for other services to verify our tokens
+pub fn jwks() -> JwkSet {
+    JwkSet {
+        keys: key_store().verification.iter().filter_map(|key| key.jwk.clone()).collect()
+    }
+}
+
+// load keys at boot so unreadable or mismatched keys stop the server before it serves requests
+pub fn load_keys(keys: &KeyConfig) {
+    if KEY_STORE.set(KeyStore::from_config(keys)).is_err() {
+        panic!("Keys already loaded");
+    }
+}
@@ -0,0 +1,36 @@
+use types::user::User;
+
+use crate::{config::Config, repositories::UserRepository};
+
+// seconds until a locked account may log in again, None if it is not locked
+pub fn locked_for(user: &User) -> Option<u64> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    user.locked_until
+        .filter(|locked_until| *locked_until > now)
+        .map(|locked_until| (locked_until - now) as u64)
+}
+
+// lock duration after the given number of consecutive failures, the first lock doubles for every further failure
+fn lock_duration(config: &Config, failed_logins: i32) -> Option<i64> {
+    let lockout = &config.lockout;
+    if failed_logins < lockout.threshold {
+        return None;
+    }
+    let doublings = (failed_logins - lockout.threshold).min(30) as u32;
+    Some(lockout.base.saturating_mul(2_i64.saturating_pow(doublings)).min(lockout.max))
+}
+
+// count failed login, locking the account once past the threshold, returns seconds locked for
+pub async fn record_db_failed_login(config: &Config, users: &dyn UserRepository, user: &User) -> Result<Option<u64>, sqlx::Error> {
+    let failed_logins = users.increment_failed_logins(&user.uuid).await?;
+    let Some(duration) = lock_duration(config, failed_logins) else {
+        return Ok(None);
+    };
+    users.lock(&user.uuid, jsonwebtoken::get_current_timestamp() as i64 + duration).await?;
+    tracing::warn!(user_uuid = %user.uuid, duration, failed_logins, "Locked account after failed logins");
+    Ok(Some(duration as u64))
+}
+
+pub async fn reset_db_failed_logins(users: &dyn UserRepository, user: &User) -> Result<(), sqlx::Error> {
+    users.reset_failed_logins(&user.uuid).await
+}
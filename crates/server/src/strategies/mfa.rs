@@ -0,0 +1,149 @@
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::{any::{AnyQueryResult, AnyRow}, AnyPool, FromRow, Row};
+use totp_rs::{Algorithm, Secret, TOTP};
+
+use crate::config::Config;
+
+// number of recovery codes generated when two-factor authentication is confirmed
+const RECOVERY_CODE_COUNT: usize = 10;
+// TOTP time step in seconds
+const TOTP_STEP: u64 = 30;
+// TOTP time steps accepted either side of the current one to allow for clock drift
+const TOTP_SKEW: u64 = 1;
+
+#[derive(Debug)]
+pub struct UserTotp {
+    pub secret: String,
+    pub enabled: bool
+}
+
+// the Any driver cannot decode SQLite booleans, so enabled is selected as an integer
+impl FromRow<'_, AnyRow> for UserTotp {
+    fn from_row(row: &AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            secret: row.try_get("secret")?,
+            enabled: row.try_get::<i32, _>("enabled")? != 0
+        })
+    }
+}
+
+// build TOTP generator for base32 secret labelled with the user account
+pub fn build_totp(config: &Config, secret: &str, account_name: String) -> Option<TOTP> {
+    let secret_bytes = Secret::Encoded(secret.to_string()).to_bytes().ok()?;
+    let issuer = Some(config.company.name.clone());
+    TOTP::new(Algorithm::SHA1, 6, TOTP_SKEW as u8, TOTP_STEP, secret_bytes, issuer, account_name).ok()
+}
+
+// generate new base32 encoded TOTP secret
+pub fn gen_totp_secret() -> String {
+    Secret::generate_secret().to_encoded().to_string()
+}
+
+// find time step a code was generated for, if it is within the accepted skew
+pub fn matching_totp_step(totp: &TOTP, code: &str) -> Option<i64> {
+    let current_step = jsonwebtoken::get_current_timestamp() / TOTP_STEP;
+    (current_step.saturating_sub(TOTP_SKEW)..=current_step + TOTP_SKEW)
+        .find(|step| totp.generate(step * TOTP_STEP) == code.trim())
+        .map(|step| step as i64)
+}
+
+// generate random recovery code formatted as two groups of five characters
+fn gen_recovery_code() -> String {
+    let code: String = rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(10)
+        .map(|byte| char::from(byte).to_ascii_lowercase())
+        .collect();
+    format!("{}-{}", &code[0..5], &code[5..10])
+}
+
+fn hash_recovery_code(code: &str) -> String {
+    hex::encode(Sha256::digest(code.trim().to_lowercase().as_bytes()))
+}
+
+pub async fn get_db_user_totp(pool: &AnyPool, user_uuid: String) -> Result<Option<UserTotp>, sqlx::Error> {
+    sqlx::query_as::<_, UserTotp>(
+        "SELECT secret, CAST(enabled AS INTEGER) AS enabled FROM \"user_totp\" WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .fetch_optional(pool).await
+}
+
+// store unconfirmed secret, replacing any previous unconfirmed enrollment
+pub async fn insert_db_user_totp(pool: &AnyPool, user_uuid: String, secret: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"user_totp\" WHERE user_uuid = $1 AND enabled = $2;")
+        .bind(user_uuid.clone())
+        .bind(false)
+        .execute(pool).await?;
+    sqlx::query(
+        "INSERT INTO \"user_totp\" (user_uuid, secret, enabled, created_at)
+        VALUES ($1, $2, $3, $4);")
+        .bind(user_uuid)
+        .bind(secret)
+        .bind(false)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+}
+
+pub async fn enable_db_user_totp(pool: &AnyPool, user_uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("UPDATE \"user_totp\" SET enabled = $2 WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .bind(true)
+        .execute(pool).await
+}
+
+// record time step as used, returns false if it or a later step was already used
+pub async fn use_db_totp_step(pool: &AnyPool, user_uuid: String, step: i64) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"user_totp\" SET last_used_step = $2
+        WHERE user_uuid = $1 AND (last_used_step IS NULL OR last_used_step < $2);")
+        .bind(user_uuid)
+        .bind(step)
+        .execute(pool).await
+        .map(|result| result.rows_affected() > 0)
+}
+
+pub async fn delete_db_user_totp(pool: &AnyPool, user_uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"recovery_codes\" WHERE user_uuid = $1;")
+        .bind(user_uuid.clone())
+        .execute(pool).await?;
+    sqlx::query("DELETE FROM \"user_totp\" WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .execute(pool).await
+}
+
+// replace recovery codes of user, returning the plaintext codes to show once
+pub async fn replace_db_recovery_codes(pool: &AnyPool, user_uuid: String) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query("DELETE FROM \"recovery_codes\" WHERE user_uuid = $1;")
+        .bind(user_uuid.clone())
+        .execute(pool).await?;
+    let codes: Vec<String> = (0..RECOVERY_CODE_COUNT).map(|_| gen_recovery_code()).collect();
+    for code in &codes {
+        sqlx::query(
+            "INSERT INTO \"recovery_codes\" (user_uuid, code_hash, created_at)
+            VALUES ($1, $2, $3);")
+            .bind(user_uuid.clone())
+            .bind(hash_recovery_code(code))
+            .bind(jsonwebtoken::get_current_timestamp() as i64)
+            .execute(pool).await?;
+    }
+    Ok(codes)
+}
+
+// remove matching recovery code so it can only be used once, returns false if none matched
+pub async fn consume_db_recovery_code(pool: &AnyPool, user_uuid: String, code: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query("DELETE FROM \"recovery_codes\" WHERE user_uuid = $1 AND code_hash = $2;")
+        .bind(user_uuid)
+        .bind(hash_recovery_code(code))
+        .execute(pool).await
+        .map(|result| result.rows_affected() > 0)
+}
+
+// verify TOTP or recovery code for user with enabled two-factor authentication
+pub async fn verify_db_mfa_code(config: &Config, pool: &AnyPool, user_uuid: String, account_name: String, secret: &str, code: &str) -> Result<bool, sqlx::Error> {
+    if let Some(step) = build_totp(config, secret, account_name).and_then(|totp| matching_totp_step(&totp, code)) {
+        // reject replays of an already used code
+        return use_db_totp_step(pool, user_uuid, step).await;
+    }
+    consume_db_recovery_code(pool, user_uuid, code).await
+}
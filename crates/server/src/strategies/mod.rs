@@ -1,2 +1,17 @@
 pub mod users;
-pub mod authentication;
\ No newline at end of file
+pub mod authentication;
+pub mod passwords;
+pub mod password_resets;
+pub mod sessions;
+pub mod keys;
+pub mod mfa;
+pub mod roles;
+pub mod email;
+pub mod verification;
+pub mod jobs;
+pub mod lockout;
+pub mod chat;
+pub mod rooms;
+pub mod direct_messages;
+pub mod presence;
+pub mod chat_hub;
\ No newline at end of file
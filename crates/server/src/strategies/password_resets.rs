@@ -0,0 +1,67 @@
+use std::{sync::Arc, time::Duration};
+
+use rand::{distributions::Alphanumeric, Rng};
+use sha2::{Digest, Sha256};
+use sqlx::{any::AnyQueryResult, AnyPool};
+
+use crate::{config::Config, shutdown::Shutdown};
+
+// generate random reset key to send to the user
+pub fn gen_reset_key() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(64)
+        .map(char::from)
+        .collect()
+}
+
+// hash reset key so only the emailed copy can be used to reset
+fn hash_reset_key(reset_key: &str) -> String {
+    hex::encode(Sha256::digest(reset_key.as_bytes()))
+}
+
+pub async fn insert_db_password_reset(config: &Config, pool: &AnyPool, reset_key: &str, email: String) -> Result<AnyQueryResult, sqlx::Error> {
+    let created_at = jsonwebtoken::get_current_timestamp() as i64;
+    sqlx::query(
+        "INSERT INTO \"password_resets\" (key_hash, email, created_at, expires_at)
+        VALUES ($1, $2, $3, $4);")
+        .bind(hash_reset_key(reset_key))
+        .bind(email)
+        .bind(created_at)
+        .bind(created_at + config.passwords.reset_expire as i64)
+        .execute(pool).await
+}
+
+// remove an unexpired reset key for email so it can only be used once, returns false if none matched
+pub async fn consume_db_password_reset(pool: &AnyPool, reset_key: &str, email: String) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM \"password_resets\" WHERE key_hash = $1 AND email = $2 AND expires_at > $3;")
+        .bind(hash_reset_key(reset_key))
+        .bind(email)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+        .map(|result| result.rows_affected() > 0)
+}
+
+pub async fn delete_expired_password_resets(pool: &AnyPool) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"password_resets\" WHERE expires_at <= $1;")
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+}
+
+// periodically purge expired reset keys until the server shuts down
+pub async fn sweep_expired_password_resets(config: Arc<Config>, pool: AnyPool, mut shutdown: Shutdown) {
+    let mut interval = tokio::time::interval(Duration::from_secs(config.passwords.reset_sweep_interval));
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {},
+            _ = shutdown.triggered() => break
+        }
+        match delete_expired_password_resets(&pool).await {
+            Ok(result) => if result.rows_affected() > 0 {
+                tracing::info!(count = result.rows_affected(), "Removed expired password reset keys");
+            },
+            Err(error) => tracing::error!(%error, "Error removing expired password reset keys")
+        }
+    }
+}
@@ -0,0 +1,212 @@
+use std::{fmt, fs, str::FromStr};
+
+use argon2::{
+    password_hash::{rand_core::OsRng, PasswordHash, PasswordHasher as _, PasswordVerifier, SaltString},
+    Argon2
+};
+use bcrypt::{hash_with_salt, DEFAULT_COST};
+use once_cell::sync::OnceCell;
+use types::validation::BreachedPasswords;
+
+use crate::config::PasswordConfig;
+
+// hasher used for all newly stored passwords, selected by passwords.hasher
+static DEFAULT_HASHER: OnceCell<Box<dyn PasswordHasher>> = OnceCell::new();
+// encoded bcrypt salt derived from the legacy global passwords.legacy_salt, if configured
+static LEGACY_SALT: OnceCell<Option<String>> = OnceCell::new();
+// breached passwords rejected by validation, read once from passwords.breached_list
+static BREACHED_PASSWORDS: OnceCell<BreachedPasswords> = OnceCell::new();
+
+// set up the default hasher and read the breached password list before accepting requests
+pub fn load_passwords(passwords: &PasswordConfig) {
+    let hasher: Box<dyn PasswordHasher> = match passwords.hasher {
+        HashAlgorithm::Bcrypt => Box::new(BcryptHasher::new(DEFAULT_COST)),
+        HashAlgorithm::Argon2id => Box::new(Argon2Hasher::new())
+    };
+    if DEFAULT_HASHER.set(hasher).is_err()
+        || LEGACY_SALT.set(legacy_salt(passwords.legacy_salt.as_deref())).is_err()
+        || BREACHED_PASSWORDS.set(read_breached_passwords(passwords.breached_list.as_deref())).is_err() {
+        panic!("Passwords already loaded");
+    }
+}
+
+fn legacy_salt(password_salt: Option<&str>) -> Option<String> {
+    let password_salt = password_salt?;
+    if password_salt.len() < 16 {
+        return None;
+    }
+    let mut salt: [u8; 16] = [0; 16];
+    salt.copy_from_slice(&password_salt.as_bytes()[0..16]);
+    hash_with_salt("", DEFAULT_COST, salt).ok().map(|parts| parts.get_salt())
+}
+
+fn read_breached_passwords(path: Option<&str>) -> BreachedPasswords {
+    let Some(path) = path else {
+        return BreachedPasswords::default();
+    };
+    match fs::read_to_string(path) {
+        Ok(list) => {
+            let breached = BreachedPasswords::from_list(&list);
+            tracing::info!(count = breached.len(), "Loaded breached password list");
+            breached
+        },
+        Err(error) => panic!("Could not read breached password list {path}: {error}")
+    }
+}
+
+pub fn breached_passwords() -> &'static BreachedPasswords {
+    BREACHED_PASSWORDS.get().expect("Passwords not loaded")
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    Bcrypt,
+    Argon2id
+}
+
+impl HashAlgorithm {
+    // infer algorithm from the prefix of a stored hash
+    pub fn from_hash(hash: &str) -> Option<Self> {
+        if hash.starts_with("$2a$") || hash.starts_with("$2b$") || hash.starts_with("$2x$") || hash.starts_with("$2y$") {
+            Some(Self::Bcrypt)
+        } else if hash.starts_with("$argon2id$") {
+            Some(Self::Argon2id)
+        } else {
+            None
+        }
+    }
+}
+
+impl FromStr for HashAlgorithm {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "bcrypt" => Ok(Self::Bcrypt),
+            "argon2" | "argon2id" => Ok(Self::Argon2id),
+            _ => Err("expected bcrypt or argon2".to_string())
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct PasswordError(String);
+
+impl fmt::Display for PasswordError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Password hashing error: {}", self.0)
+    }
+}
+
+impl std::error::Error for PasswordError {}
+
+// trait for password hashing algorithms
+pub trait PasswordHasher: Send + Sync {
+    // algorithm produced by this hasher
+    fn algorithm(&self) -> HashAlgorithm;
+    // hash password with a freshly generated salt
+    fn hash(&self, pass: &str) -> Result<String, PasswordError>;
+    // verify password against a hash produced by this algorithm
+    fn verify(&self, pass: &str, hash: &str) -> Result<bool, PasswordError>;
+    // check if a hash produced by this algorithm should be regenerated
+    fn needs_rehash(&self, _hash: &str) -> bool {
+        false
+    }
+}
+
+pub struct BcryptHasher {
+    cost: u32
+}
+
+impl BcryptHasher {
+    pub fn new(cost: u32) -> Self {
+        Self { cost }
+    }
+}
+
+impl PasswordHasher for BcryptHasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Bcrypt
+    }
+    fn hash(&self, pass: &str) -> Result<String, PasswordError> {
+        bcrypt::hash(pass, self.cost).map_err(|error| PasswordError(error.to_string()))
+    }
+    fn verify(&self, pass: &str, hash: &str) -> Result<bool, PasswordError> {
+        bcrypt::verify(pass, hash).map_err(|error| PasswordError(error.to_string()))
+    }
+    fn needs_rehash(&self, hash: &str) -> bool {
+        // hashes sharing passwords.legacy_salt (PASSWORD_SALT) predate per-user salts
+        match LEGACY_SALT.get().and_then(Option::as_ref) {
+            Some(legacy_salt) => hash.get(7..29) == Some(legacy_salt.as_str()),
+            None => false
+        }
+    }
+}
+
+pub struct Argon2Hasher {
+    argon2: Argon2<'static>
+}
+
+impl Argon2Hasher {
+    pub fn new() -> Self {
+        Self { argon2: Argon2::default() }
+    }
+}
+
+impl PasswordHasher for Argon2Hasher {
+    fn algorithm(&self) -> HashAlgorithm {
+        HashAlgorithm::Argon2id
+    }
+    fn hash(&self, pass: &str) -> Result<String, PasswordError> {
+        let salt = SaltString::generate(&mut OsRng);
+        match self.argon2.hash_password(pass.as_bytes(), &salt) {
+            Ok(hash) => Ok(hash.to_string()),
+            Err(error) => Err(PasswordError(error.to_string()))
+        }
+    }
+    fn verify(&self, pass: &str, hash: &str) -> Result<bool, PasswordError> {
+        let parsed_hash = PasswordHash::new(hash).map_err(|error| PasswordError(error.to_string()))?;
+        Ok(self.argon2.verify_password(pass.as_bytes(), &parsed_hash).is_ok())
+    }
+    fn needs_rehash(&self, hash: &str) -> bool {
+        // rehash when stored parameters differ from the current defaults
+        match PasswordHash::new(hash) {
+            Ok(parsed_hash) => argon2::Params::try_from(&parsed_hash)
+                .map(|params| {
+                    // output length is only recorded in parsed hashes, so compare the cost parameters
+                    let current = self.argon2.params();
+                    (params.m_cost(), params.t_cost(), params.p_cost()) != (current.m_cost(), current.t_cost(), current.p_cost())
+                })
+                .unwrap_or(true),
+            Err(_) => true
+        }
+    }
+}
+
+// getter for the configured default hasher
+pub fn default_hasher() -> &'static dyn PasswordHasher {
+    DEFAULT_HASHER.get().expect("Passwords not loaded").as_ref()
+}
+
+// hash password with the configured default hasher
+pub fn hash_password(pass: &str) -> Result<String, PasswordError> {
+    default_hasher().hash(pass)
+}
+
+// verify password against a stored hash of any supported algorithm
+pub fn verify_password(pass: &str, hash: &str) -> Result<bool, PasswordError> {
+    match HashAlgorithm::from_hash(hash) {
+        Some(HashAlgorithm::Bcrypt) => BcryptHasher::new(DEFAULT_COST).verify(pass, hash),
+        Some(HashAlgorithm::Argon2id) => Argon2Hasher::new().verify(pass, hash),
+        None => Err(PasswordError("Unrecognized hash format".to_string()))
+    }
+}
+
+// check if a stored hash should be upgraded to the configured default hasher
+pub fn needs_rehash(hash: &str) -> bool {
+    let hasher = default_hasher();
+    match HashAlgorithm::from_hash(hash) {
+        Some(algorithm) if algorithm == hasher.algorithm() => hasher.needs_rehash(hash),
+        Some(_) => true,
+        None => false
+    }
+}
@@ -0,0 +1,106 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use tokio::sync::broadcast;
+use types::{chat::{Presence, PresenceStatus, ServerMessage}, user::User};
+
+// open connections of a user, each flagged whether it is idle
+struct UserPresence {
+    username: String,
+    connections: HashMap<u64, bool>,
+    since: i64
+}
+
+impl UserPresence {
+    fn status(&self) -> PresenceStatus {
+        if self.connections.is_empty() {
+            PresenceStatus::Offline
+        } else if self.connections.values().all(|idle| *idle) {
+            PresenceStatus::Idle
+        } else {
+            PresenceStatus::Online
+        }
+    }
+
+    fn presence(&self, user_uuid: &str) -> Presence {
+        Presence { user_uuid: user_uuid.to_string(), username: self.username.clone(), status: self.status(), since: self.since }
+    }
+}
+
+// users connected to the chat, a user stays online while any of their tabs or devices is open and active
+pub struct PresenceTracker {
+    user_set: Mutex<HashMap<String, UserPresence>>,
+    tx: broadcast::Sender<ServerMessage>
+}
+
+impl Default for PresenceTracker {
+    fn default() -> Self {
+        PresenceTracker { user_set: Mutex::new(HashMap::new()), tx: broadcast::channel(100).0 }
+    }
+}
+
+impl PresenceTracker {
+    // presence frames of every user whose status changes
+    pub fn subscribe(&self) -> broadcast::Receiver<ServerMessage> {
+        self.tx.subscribe()
+    }
+
+    pub fn connect(&self, user: &User, connection_id: u64) {
+        let mut user_set = self.user_set.lock().unwrap();
+        let user_presence = user_set.entry(user.uuid.clone()).or_insert_with(|| UserPresence {
+            username: user.username.clone(),
+            connections: HashMap::new(),
+            since: now()
+        });
+        let before = user_presence.status();
+        user_presence.connections.insert(connection_id, false);
+        self.changed(&user.uuid, user_presence, before);
+    }
+
+    pub fn disconnect(&self, user_uuid: &str, connection_id: u64) {
+        let mut user_set = self.user_set.lock().unwrap();
+        let Some(user_presence) = user_set.get_mut(user_uuid) else {
+            return;
+        };
+        let before = user_presence.status();
+        user_presence.connections.remove(&connection_id);
+        self.changed(user_uuid, user_presence, before);
+        if user_presence.connections.is_empty() {
+            user_set.remove(user_uuid);
+        }
+    }
+
+    pub fn set_idle(&self, user_uuid: &str, connection_id: u64, idle: bool) {
+        let mut user_set = self.user_set.lock().unwrap();
+        let Some(user_presence) = user_set.get_mut(user_uuid) else {
+            return;
+        };
+        let before = user_presence.status();
+        if let Some(connection_idle) = user_presence.connections.get_mut(&connection_id) {
+            *connection_idle = idle;
+        }
+        self.changed(user_uuid, user_presence, before);
+    }
+
+    // users online or idle, by username
+    pub fn snapshot(&self) -> Vec<Presence> {
+        let mut users: Vec<Presence> = self.user_set.lock().unwrap().iter()
+            .map(|(user_uuid, user_presence)| user_presence.presence(user_uuid))
+            .collect();
+        users.sort_by_key(|presence| presence.username.to_lowercase());
+        users
+    }
+
+    // sent while the lock is held so subscribers see the changes of a user in order
+    fn changed(&self, user_uuid: &str, user_presence: &mut UserPresence, before: PresenceStatus) {
+        if user_presence.status() == before {
+            return;
+        }
+        user_presence.since = now();
+        let _ = self.tx.send(ServerMessage::Presence(user_presence.presence(user_uuid)));
+    }
+}
+
+fn now() -> i64 {
+    jsonwebtoken::get_current_timestamp() as i64
+}
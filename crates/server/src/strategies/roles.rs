@@ -0,0 +1,99 @@
+use std::collections::BTreeMap;
+
+use sqlx::{any::AnyQueryResult, AnyPool};
+use types::{roles::Role, user::UserInfo};
+
+#[derive(Debug, sqlx::FromRow)]
+struct RolePermissionRow {
+    name: String,
+    description: String,
+    permission: Option<String>
+}
+
+pub async fn get_db_roles(pool: &AnyPool) -> Result<Vec<Role>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, RolePermissionRow>(
+        "SELECT \"roles\".name, \"roles\".description, \"permissions\".name AS permission
+        FROM \"roles\"
+        LEFT JOIN \"role_permissions\" ON \"role_permissions\".role_id = \"roles\".id
+        LEFT JOIN \"permissions\" ON \"permissions\".id = \"role_permissions\".permission_id
+        ORDER BY \"roles\".name;")
+        .fetch_all(pool).await?;
+    // group permission rows by role
+    let mut roles: BTreeMap<String, Role> = BTreeMap::new();
+    for row in rows {
+        let role = roles.entry(row.name.clone()).or_insert(Role {
+            name: row.name,
+            description: row.description,
+            permissions: Vec::new()
+        });
+        if let Some(permission) = row.permission {
+            role.permissions.push(permission);
+        }
+    }
+    Ok(roles.into_values().collect())
+}
+
+pub async fn get_db_user_roles(pool: &AnyPool, user_uuid: String) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT \"roles\".name FROM \"user_roles\"
+        JOIN \"roles\" ON \"roles\".id = \"user_roles\".role_id
+        WHERE \"user_roles\".user_uuid = $1
+        ORDER BY \"roles\".name;")
+        .bind(user_uuid)
+        .fetch_all(pool).await
+}
+
+// permissions granted to user by all of their roles
+pub async fn get_db_user_permissions(pool: &AnyPool, user_uuid: String) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar::<_, String>(
+        "SELECT DISTINCT \"permissions\".name FROM \"user_roles\"
+        JOIN \"role_permissions\" ON \"role_permissions\".role_id = \"user_roles\".role_id
+        JOIN \"permissions\" ON \"permissions\".id = \"role_permissions\".permission_id
+        WHERE \"user_roles\".user_uuid = $1
+        ORDER BY \"permissions\".name;")
+        .bind(user_uuid)
+        .fetch_all(pool).await
+}
+
+// grant role to user, returns false if no role has the given name
+pub async fn assign_db_user_role(pool: &AnyPool, user_uuid: String, role: String) -> Result<bool, sqlx::Error> {
+    let role_id = sqlx::query_scalar::<_, i32>("SELECT id FROM \"roles\" WHERE name = $1;")
+        .bind(role)
+        .fetch_optional(pool).await?;
+    let Some(role_id) = role_id else {
+        return Ok(false);
+    };
+    // skip users that already have the role
+    sqlx::query(
+        "INSERT INTO \"user_roles\" (user_uuid, role_id)
+        SELECT $1, $2 WHERE NOT EXISTS (
+            SELECT 1 FROM \"user_roles\" WHERE user_uuid = $1 AND role_id = $2);")
+        .bind(user_uuid)
+        .bind(role_id)
+        .execute(pool).await?;
+    Ok(true)
+}
+
+// remove role from user, returns false if the user did not have it
+pub async fn remove_db_user_role(pool: &AnyPool, user_uuid: String, role: String) -> Result<bool, sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM \"user_roles\" WHERE user_uuid = $1
+        AND role_id IN (SELECT id FROM \"roles\" WHERE name = $2);")
+        .bind(user_uuid)
+        .bind(role)
+        .execute(pool).await
+        .map(|result| result.rows_affected() > 0)
+}
+
+// attach roles and permissions of user to their info
+pub async fn user_info_with_access(pool: &AnyPool, user_info: UserInfo) -> Result<UserInfo, sqlx::Error> {
+    let roles = get_db_user_roles(pool, user_info.uuid.clone()).await?;
+    let permissions = get_db_user_permissions(pool, user_info.uuid.clone()).await?;
+    Ok(user_info.with_access(roles, permissions))
+}
+
+pub async fn delete_db_user_roles(pool: &AnyPool, user_uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"user_roles\" WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .execute(pool).await
+}
@@ -0,0 +1,149 @@
+use sqlx::{any::AnyQueryResult, AnyPool};
+use types::{auth::AuthErrorType, chat::RoomInfo};
+
+use crate::error::ApiError;
+
+const VISIBILITY_PUBLIC: &str = "public";
+const VISIBILITY_PRIVATE: &str = "private";
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Room {
+    pub id: i64,
+    pub name: String,
+    visibility: String,
+    pub created_at: i64
+}
+
+impl Room {
+    pub fn is_private(&self) -> bool {
+        self.visibility == VISIBILITY_PRIVATE
+    }
+}
+
+#[derive(Debug, sqlx::FromRow)]
+struct RoomMemberRow {
+    id: i64,
+    name: String,
+    visibility: String,
+    created_at: i64,
+    member_uuid: Option<String>
+}
+
+#[derive(Debug, sqlx::FromRow)]
+pub struct Membership {
+    pub user_uuid: String,
+    pub joined_at: i64
+}
+
+// create room with its creator as first member
+pub async fn insert_db_room(pool: &AnyPool, name: &str, private: bool, created_by: &str) -> Result<Room, sqlx::Error> {
+    let now = jsonwebtoken::get_current_timestamp() as i64;
+    let visibility = if private { VISIBILITY_PRIVATE } else { VISIBILITY_PUBLIC };
+    let room = sqlx::query_as::<_, Room>(
+        "INSERT INTO \"rooms\" (name, visibility, created_by, created_at)
+        VALUES ($1, $2, $3, $4) RETURNING id, name, visibility, created_at;")
+        .bind(name)
+        .bind(visibility)
+        .bind(created_by)
+        .bind(now)
+        .fetch_one(pool).await?;
+    insert_db_member(pool, room.id, created_by).await?;
+    Ok(room)
+}
+
+pub async fn find_db_room(pool: &AnyPool, room_id: i64) -> Result<Option<Room>, sqlx::Error> {
+    sqlx::query_as::<_, Room>(
+        "SELECT id, name, visibility, created_at FROM \"rooms\" WHERE id = $1;")
+        .bind(room_id)
+        .fetch_optional(pool).await
+}
+
+// public rooms and the private rooms the user is a member of
+pub async fn get_db_rooms(pool: &AnyPool, user_uuid: &str) -> Result<Vec<RoomInfo>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, RoomMemberRow>(
+        "SELECT \"rooms\".id, \"rooms\".name, \"rooms\".visibility, \"rooms\".created_at,
+            \"room_members\".user_uuid AS member_uuid
+        FROM \"rooms\"
+        LEFT JOIN \"room_members\" ON \"room_members\".room_id = \"rooms\".id AND \"room_members\".user_uuid = $1
+        WHERE \"rooms\".visibility = $2 OR \"room_members\".user_uuid IS NOT NULL
+        ORDER BY \"rooms\".name;")
+        .bind(user_uuid)
+        .bind(VISIBILITY_PUBLIC)
+        .fetch_all(pool).await?;
+    Ok(rows.into_iter().map(|row| RoomInfo {
+        id: row.id,
+        name: row.name,
+        private: row.visibility == VISIBILITY_PRIVATE,
+        member: row.member_uuid.is_some(),
+        created_at: row.created_at
+    }).collect())
+}
+
+pub async fn is_db_member(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<bool, sqlx::Error> {
+    let member = sqlx::query_scalar::<_, String>(
+        "SELECT user_uuid FROM \"room_members\" WHERE room_id = $1 AND user_uuid = $2;")
+        .bind(room_id)
+        .bind(user_uuid)
+        .fetch_optional(pool).await?;
+    Ok(member.is_some())
+}
+
+// adding an existing member keeps their original join time
+pub async fn insert_db_member(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO \"room_members\" (room_id, user_uuid, joined_at) VALUES ($1, $2, $3)
+        ON CONFLICT DO NOTHING;")
+        .bind(room_id)
+        .bind(user_uuid)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+}
+
+pub async fn delete_db_member(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"room_members\" WHERE room_id = $1 AND user_uuid = $2;")
+        .bind(room_id)
+        .bind(user_uuid)
+        .execute(pool).await
+}
+
+pub async fn get_db_members(pool: &AnyPool, room_id: i64) -> Result<Vec<Membership>, sqlx::Error> {
+    sqlx::query_as::<_, Membership>(
+        "SELECT user_uuid, joined_at FROM \"room_members\" WHERE room_id = $1 ORDER BY joined_at;")
+        .bind(room_id)
+        .fetch_all(pool).await
+}
+
+pub async fn delete_db_user_memberships(pool: &AnyPool, user_uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query("DELETE FROM \"room_members\" WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .execute(pool).await
+}
+
+// room the user may read, private rooms are hidden from non-members
+pub async fn readable_room(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<Room, ApiError> {
+    let Some(room) = find_db_room(pool, room_id).await? else {
+        return Err(ApiError::not_found("Room does not exist"));
+    };
+    if room.is_private() && !is_db_member(pool, room_id, user_uuid).await? {
+        return Err(ApiError::not_found("Room does not exist"));
+    }
+    Ok(room)
+}
+
+// join a public room, private rooms are hidden from everyone but the members that were added
+pub async fn join_room(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<Room, ApiError> {
+    let room = readable_room(pool, room_id, user_uuid).await?;
+    if !room.is_private() {
+        insert_db_member(pool, room_id, user_uuid).await?;
+    }
+    Ok(room)
+}
+
+// members may add other users to the room, which is how private rooms grow
+pub async fn member_room(pool: &AnyPool, room_id: i64, user_uuid: &str) -> Result<Room, ApiError> {
+    let room = readable_room(pool, room_id, user_uuid).await?;
+    if !is_db_member(pool, room_id, user_uuid).await? {
+        return Err(ApiError::auth(AuthErrorType::AccessDenied));
+    }
+    Ok(room)
+}
@@ -0,0 +1,76 @@
+use sqlx::{any::{AnyQueryResult, AnyRow}, AnyPool, FromRow, Row};
+
+#[derive(Debug)]
+pub struct Session {
+    pub family_id: String,
+    pub user_uuid: String,
+    pub rotated_at: Option<i64>,
+    pub revoked: bool
+}
+
+// the Any driver cannot decode SQLite booleans, so revoked is selected as an integer
+impl FromRow<'_, AnyRow> for Session {
+    fn from_row(row: &AnyRow) -> Result<Self, sqlx::Error> {
+        Ok(Self {
+            family_id: row.try_get("family_id")?,
+            user_uuid: row.try_get("user_uuid")?,
+            rotated_at: row.try_get("rotated_at")?,
+            revoked: row.try_get::<i32, _>("revoked")? != 0
+        })
+    }
+}
+
+impl Session {
+    // check if a rotated session was used again after the grace period, which covers concurrent refreshes from one client
+    pub fn is_reused(&self, grace: i64) -> bool {
+        match self.rotated_at {
+            Some(rotated_at) => jsonwebtoken::get_current_timestamp() as i64 - rotated_at > grace,
+            None => false
+        }
+    }
+}
+
+pub async fn insert_db_session(pool: &AnyPool, jti: String, family_id: String, user_uuid: String, expires_at: u64) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO \"sessions\" (jti, family_id, user_uuid, created_at, expires_at, revoked)
+        VALUES ($1, $2, $3, $4, $5, $6);")
+        .bind(jti)
+        .bind(family_id)
+        .bind(user_uuid)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .bind(expires_at as i64)
+        .bind(false)
+        .execute(pool).await
+}
+
+pub async fn get_db_session_by_jti(pool: &AnyPool, jti: String) -> Result<Session, sqlx::Error> {
+    sqlx::query_as::<_, Session>(
+        "SELECT family_id, user_uuid, rotated_at, CAST(revoked AS INTEGER) AS revoked FROM \"sessions\" WHERE jti = $1;")
+        .bind(jti)
+        .fetch_one(pool).await
+}
+
+// mark session as replaced by a newer token in the same family
+pub async fn rotate_db_session(pool: &AnyPool, jti: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"sessions\" SET rotated_at = $2 WHERE jti = $1 AND rotated_at IS NULL;")
+        .bind(jti)
+        .bind(jsonwebtoken::get_current_timestamp() as i64)
+        .execute(pool).await
+}
+
+pub async fn revoke_db_session_family(pool: &AnyPool, family_id: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"sessions\" SET revoked = $2 WHERE family_id = $1;")
+        .bind(family_id)
+        .bind(true)
+        .execute(pool).await
+}
+
+pub async fn revoke_db_user_sessions(pool: &AnyPool, user_uuid: String) -> Result<AnyQueryResult, sqlx::Error> {
+    sqlx::query(
+        "UPDATE \"sessions\" SET revoked = $2 WHERE user_uuid = $1;")
+        .bind(user_uuid)
+        .bind(true)
+        .execute(pool).await
+}
@@ -0,0 +1,57 @@
+use std::str::FromStr;
+
+use types::user::User;
+
+use crate::{config::Config, state::AppState};
+
+use super::{authentication::{Claims, EmailVerificationClaims}, email, jobs};
+
+// What unverified accounts are blocked from
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum VerificationPolicy {
+    // verification is optional
+    None,
+    // unverified accounts can log in but cannot join chat
+    Chat,
+    // unverified accounts cannot log in at all
+    Login
+}
+
+impl FromStr for VerificationPolicy {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "none" => Ok(VerificationPolicy::None),
+            "chat" => Ok(VerificationPolicy::Chat),
+            "login" => Ok(VerificationPolicy::Login),
+            _ => Err("expected none, chat or login".to_string())
+        }
+    }
+}
+
+// check if unverified user may log in
+pub fn login_allowed(config: &Config, user: &User) -> bool {
+    user.email_verified_at.is_some() || config.verification.policy != VerificationPolicy::Login
+}
+
+// check if unverified user may join chat, which login blocking also covers
+pub fn chat_allowed(config: &Config, user: &User) -> bool {
+    user.email_verified_at.is_some() || config.verification.policy == VerificationPolicy::None
+}
+
+// queue email with signed verification link to the user's current address
+pub async fn queue_verification_email(state: &AppState, user: &User) -> Result<(), String> {
+    let token = EmailVerificationClaims::new(state, user.uuid.clone()).await
+        .and_then(|claims| claims.generate_token())
+        .map_err(|error| format!("Could not create verification token: {:?}", error.body()))?;
+    let company = &state.config.company;
+    let verify_url = format!("{}/verify?key={}", company.domain, token.to_string());
+    let html = email::render_template("verify_template.html", &[
+        ("{COMPANY_NAME}", &company.name),
+        ("{VERIFY_EMAIL_URL}", &verify_url)
+    ])?;
+    match jobs::enqueue_email(&state.config, &state.pool, user.email.to_string(), format!("Verify your email for {}", company.name), html).await {
+        Ok(_) => Ok(()),
+        Err(error) => Err(format!("Could not queue verification email: {error}"))
+    }
+}
@@ -0,0 +1,99 @@
+use std::str::FromStr;
+
+use axum::{body::Body, extract::MatchedPath, http::Request, response::Response};
+use metrics_exporter_prometheus::{Matcher, PrometheusBuilder, PrometheusHandle};
+use tower_http::request_id::RequestId;
+use tracing::{field::Empty, Span};
+use tracing_subscriber::EnvFilter;
+
+use crate::config::LogConfig;
+
+// How log lines are written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LogFormat {
+    // multi-line output for reading in a terminal
+    Pretty,
+    // one JSON object per line for log collectors
+    Json
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_lowercase().as_str() {
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            _ => Err("expected pretty or json".to_string())
+        }
+    }
+}
+
+// install the global subscriber, logs go to stderr so subcommand output on stdout stays parseable
+pub fn init_tracing(log: &LogConfig) {
+    let subscriber = tracing_subscriber::fmt()
+        .with_env_filter(EnvFilter::new(&log.filter))
+        .with_writer(std::io::stderr);
+    match log.format {
+        LogFormat::Pretty => subscriber.pretty().init(),
+        LogFormat::Json => subscriber.json().flatten_event(true).with_current_span(true).with_span_list(false).init()
+    }
+}
+
+// request ID assigned by SetRequestIdLayer, or the one propagated by the client
+pub fn request_id<B>(request: &Request<B>) -> String {
+    request.extensions().get::<RequestId>()
+        .and_then(|request_id| request_id.header_value().to_str().ok())
+        .unwrap_or_default()
+        .to_string()
+}
+
+// span wrapping every HTTP request, user_uuid is filled in by token authentication
+pub fn make_request_span(request: &Request<Body>) -> Span {
+    let route = request.extensions().get::<MatchedPath>()
+        .map(MatchedPath::as_str)
+        .unwrap_or(request.uri().path());
+    tracing::info_span!(
+        "request",
+        request_id = %request_id(request),
+        method = %request.method(),
+        route,
+        user_uuid = Empty,
+        status = Empty,
+        latency_ms = Empty
+    )
+}
+
+pub fn on_response(response: &Response, latency: std::time::Duration, span: &Span) {
+    span.record("status", response.status().as_u16());
+    span.record("latency_ms", latency.as_millis() as u64);
+    if response.status().is_server_error() {
+        tracing::error!("Request failed");
+    } else {
+        tracing::info!("Request finished");
+    }
+}
+
+// latency histogram buckets in seconds, from fast cache-like responses to slow password hashing
+const LATENCY_BUCKETS: &[f64] = &[0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+// install the global metrics recorder, the handle renders the Prometheus text format
+pub fn init_metrics() -> PrometheusHandle {
+    let handle = PrometheusBuilder::new()
+        .set_buckets_for_metric(Matcher::Full("http_request_duration_seconds".to_string()), LATENCY_BUCKETS)
+        .expect("Latency buckets must not be empty")
+        .install_recorder()
+        .expect("Metrics recorder already installed");
+    metrics::describe_counter!("http_requests_total", "HTTP requests by method, route and status");
+    metrics::describe_histogram!("http_request_duration_seconds", metrics::Unit::Seconds, "HTTP request latency by method and route");
+    metrics::describe_counter!("auth_logins_total", "Login attempts by result");
+    metrics::describe_gauge!("websocket_connections", "Open websocket connections");
+    metrics::describe_gauge!("db_pool_connections", "Open database connections by pool");
+    metrics::describe_gauge!("db_pool_idle_connections", "Idle database connections by pool");
+    metrics::describe_gauge!("db_pool_max_connections", "Most database connections the pool opens by pool");
+    handle
+}
+
+// count login attempt, result is one of success, mfa_required, failure or locked
+pub fn record_login(result: &'static str) {
+    metrics::counter!("auth_logins_total", "result" => result).increment(1);
+}
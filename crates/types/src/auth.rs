@@ -1,6 +1,11 @@
+use std::str::FromStr;
+
 use http::StatusCode;
 use serde::{Deserialize, Serialize};
 
+// Response header carrying a rotated auth requester token
+pub const REQUESTER_TOKEN_HEADER: &str = "X-Requester-Token";
+
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 pub struct AuthToken {
     pub access_token: String,
@@ -32,71 +37,159 @@ impl AuthToken {
 }
 
 
-#[derive(Debug, Clone)]
-pub struct AuthError {
-    pub status: StatusCode,
-    pub body: AuthErrorBody
+// Auth category of ApiError, see types::error
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum AuthErrorType {
+    WrongCredentials,
+    TokenCreation,
+    UserAlreadyExists,
+    UserDoesNotExist,
+    InvalidToken,
+    AccessDenied,
+    InvalidEmail,
+    ResetLinkInvalid,
+    PasswordDoesNotMatch,
+    InvalidMfaCode,
+    MfaNotEnrolled,
+    MfaAlreadyEnabled,
+    RoleDoesNotExist,
+    EmailNotVerified,
+    EmailAlreadyVerified,
+    VerificationLinkInvalid,
+    TooManyRequests,
+    AccountLocked
 }
 
-impl AuthError {
-    pub fn default() -> Self {
-        Self {
-            status: StatusCode::SERVICE_UNAVAILABLE,
-            body: AuthErrorBody {
-                error_type: AuthErrorType::ServerError,
-                message: String::from("Generic auth error")
-            }
+impl AuthErrorType {
+    pub fn status(&self) -> StatusCode {
+        match self {
+            AuthErrorType::WrongCredentials => StatusCode::UNAUTHORIZED,
+            AuthErrorType::TokenCreation => StatusCode::INTERNAL_SERVER_ERROR,
+            AuthErrorType::UserAlreadyExists => StatusCode::CONFLICT,
+            AuthErrorType::UserDoesNotExist => StatusCode::NOT_FOUND,
+            AuthErrorType::InvalidToken => StatusCode::FORBIDDEN,
+            AuthErrorType::AccessDenied => StatusCode::FORBIDDEN,
+            AuthErrorType::InvalidEmail => StatusCode::BAD_REQUEST,
+            AuthErrorType::ResetLinkInvalid => StatusCode::BAD_REQUEST,
+            AuthErrorType::PasswordDoesNotMatch => StatusCode::BAD_REQUEST,
+            AuthErrorType::InvalidMfaCode => StatusCode::UNAUTHORIZED,
+            AuthErrorType::MfaNotEnrolled => StatusCode::BAD_REQUEST,
+            AuthErrorType::MfaAlreadyEnabled => StatusCode::CONFLICT,
+            AuthErrorType::RoleDoesNotExist => StatusCode::NOT_FOUND,
+            AuthErrorType::EmailNotVerified => StatusCode::FORBIDDEN,
+            AuthErrorType::EmailAlreadyVerified => StatusCode::CONFLICT,
+            AuthErrorType::VerificationLinkInvalid => StatusCode::BAD_REQUEST,
+            AuthErrorType::TooManyRequests => StatusCode::TOO_MANY_REQUESTS,
+            AuthErrorType::AccountLocked => StatusCode::LOCKED
         }
     }
-    pub fn from_error_type(error_type: AuthErrorType) -> Self {
-        let (status, message) = match error_type {
-            AuthErrorType::WrongCredentials => (StatusCode::UNAUTHORIZED, String::from("Wrong credentials")),
-            AuthErrorType::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, String::from("Token creation error")),
-            AuthErrorType::ServerError => (StatusCode::INTERNAL_SERVER_ERROR, String::from("Server error")),
-            AuthErrorType::UserAlreadyExists => (StatusCode::CONFLICT, String::from("Username or email taken")),
-            AuthErrorType::UserDoesNotExist => (StatusCode::NOT_FOUND, String::from("User does not exist")),
-            AuthErrorType::InvalidToken => (StatusCode::FORBIDDEN, String::from("Invalid token")),
-            AuthErrorType::AccessDenied => (StatusCode::FORBIDDEN, String::from("Access denied")),
-            AuthErrorType::MissingFields => (StatusCode::BAD_REQUEST, String::from("Missing required fields")),
-            AuthErrorType::BadRequest => (StatusCode::BAD_REQUEST, String::from("Bad request")),
-            AuthErrorType::InvalidEmail => (StatusCode::BAD_REQUEST, String::from("Email address is invalid")),
-            AuthErrorType::ResetLinkInvalid => (StatusCode::BAD_REQUEST, String::from("Reset link is invalid")),
-            AuthErrorType::PasswordDoesNotMatch => (StatusCode::BAD_REQUEST, String::from("Password does not match")),
-        };
-        Self {
-            status,
-            body: AuthErrorBody {
-                error_type,
-                message
-            }
+    pub fn message(&self) -> &'static str {
+        match self {
+            AuthErrorType::WrongCredentials => "Wrong credentials",
+            AuthErrorType::TokenCreation => "Token creation error",
+            AuthErrorType::UserAlreadyExists => "Username or email taken",
+            AuthErrorType::UserDoesNotExist => "User does not exist",
+            AuthErrorType::InvalidToken => "Invalid token",
+            AuthErrorType::AccessDenied => "Access denied",
+            AuthErrorType::InvalidEmail => "Email address is invalid",
+            AuthErrorType::ResetLinkInvalid => "Reset link is invalid",
+            AuthErrorType::PasswordDoesNotMatch => "Password does not match",
+            AuthErrorType::InvalidMfaCode => "Invalid verification code",
+            AuthErrorType::MfaNotEnrolled => "Two-factor authentication is not enrolled",
+            AuthErrorType::MfaAlreadyEnabled => "Two-factor authentication is already enabled",
+            AuthErrorType::RoleDoesNotExist => "Role does not exist",
+            AuthErrorType::EmailNotVerified => "Email address is not verified",
+            AuthErrorType::EmailAlreadyVerified => "Email address is already verified",
+            AuthErrorType::VerificationLinkInvalid => "Verification link is invalid",
+            AuthErrorType::TooManyRequests => "Too many requests, try again later",
+            AuthErrorType::AccountLocked => "Account is temporarily locked, try again later"
         }
     }
-    pub fn body(&self) -> AuthErrorBody {
-        self.body.to_owned()
+    // stable code used in ApiErrorCode::Auth
+    pub fn code(&self) -> &'static str {
+        match self {
+            AuthErrorType::WrongCredentials => "wrong_credentials",
+            AuthErrorType::TokenCreation => "token_creation",
+            AuthErrorType::UserAlreadyExists => "user_already_exists",
+            AuthErrorType::UserDoesNotExist => "user_does_not_exist",
+            AuthErrorType::InvalidToken => "invalid_token",
+            AuthErrorType::AccessDenied => "access_denied",
+            AuthErrorType::InvalidEmail => "invalid_email",
+            AuthErrorType::ResetLinkInvalid => "reset_link_invalid",
+            AuthErrorType::PasswordDoesNotMatch => "password_does_not_match",
+            AuthErrorType::InvalidMfaCode => "invalid_mfa_code",
+            AuthErrorType::MfaNotEnrolled => "mfa_not_enrolled",
+            AuthErrorType::MfaAlreadyEnabled => "mfa_already_enabled",
+            AuthErrorType::RoleDoesNotExist => "role_does_not_exist",
+            AuthErrorType::EmailNotVerified => "email_not_verified",
+            AuthErrorType::EmailAlreadyVerified => "email_already_verified",
+            AuthErrorType::VerificationLinkInvalid => "verification_link_invalid",
+            AuthErrorType::TooManyRequests => "too_many_requests",
+            AuthErrorType::AccountLocked => "account_locked"
+        }
     }
-    pub fn status(&self) -> StatusCode {
-        self.status.to_owned()
+}
+
+impl FromStr for AuthErrorType {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        match code {
+            "wrong_credentials" => Ok(AuthErrorType::WrongCredentials),
+            "token_creation" => Ok(AuthErrorType::TokenCreation),
+            "user_already_exists" => Ok(AuthErrorType::UserAlreadyExists),
+            "user_does_not_exist" => Ok(AuthErrorType::UserDoesNotExist),
+            "invalid_token" => Ok(AuthErrorType::InvalidToken),
+            "access_denied" => Ok(AuthErrorType::AccessDenied),
+            "invalid_email" => Ok(AuthErrorType::InvalidEmail),
+            "reset_link_invalid" => Ok(AuthErrorType::ResetLinkInvalid),
+            "password_does_not_match" => Ok(AuthErrorType::PasswordDoesNotMatch),
+            "invalid_mfa_code" => Ok(AuthErrorType::InvalidMfaCode),
+            "mfa_not_enrolled" => Ok(AuthErrorType::MfaNotEnrolled),
+            "mfa_already_enabled" => Ok(AuthErrorType::MfaAlreadyEnabled),
+            "role_does_not_exist" => Ok(AuthErrorType::RoleDoesNotExist),
+            "email_not_verified" => Ok(AuthErrorType::EmailNotVerified),
+            "email_already_verified" => Ok(AuthErrorType::EmailAlreadyVerified),
+            "verification_link_invalid" => Ok(AuthErrorType::VerificationLinkInvalid),
+            "too_many_requests" => Ok(AuthErrorType::TooManyRequests),
+            "account_locked" => Ok(AuthErrorType::AccountLocked),
+            _ => Err(format!("unknown auth error code {code}"))
+        }
     }
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub enum AuthErrorType {
-    WrongCredentials,
-    TokenCreation,
-    UserAlreadyExists,
-    UserDoesNotExist,
-    InvalidToken,
-    BadRequest,
-    ServerError,
-    AccessDenied,
-    MissingFields,
-    InvalidEmail,
-    ResetLinkInvalid,
-    PasswordDoesNotMatch
+// Response to a password login that still requires a second factor
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct MfaChallenge {
+    pub mfa_token: String
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
-pub struct AuthErrorBody {
-    pub error_type: AuthErrorType,
-    pub message: String
-}
\ No newline at end of file
+// Second login step exchanging an MFA pending token and code for a requester token
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MfaLogin {
+    pub mfa_token: String,
+    pub code: String
+}
+
+// TOTP or recovery code submitted to confirm or disable two-factor authentication
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct MfaCode {
+    pub code: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TotpStatus {
+    pub enabled: bool
+}
+
+// Unconfirmed TOTP secret with otpauth URI for QR display
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct TotpEnrollment {
+    pub secret: String,
+    pub otpauth_uri: String
+}
+
+// One-time recovery codes, only ever returned once when generated
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+pub struct RecoveryCodes {
+    pub codes: Vec<String>
+}
@@ -0,0 +1,182 @@
+use serde::{Deserialize, Serialize};
+
+use crate::{error::{ApiErrorCode, FieldError}, validation::{BreachedPasswords, Validate, Validator}};
+
+pub const CHAT_MESSAGE_MAX_LENGTH: usize = 2000;
+// most messages returned by a single history request
+pub const CHAT_HISTORY_MAX_LIMIT: i64 = 100;
+// created by the rooms migration, every account can join it
+pub const GENERAL_ROOM_ID: i64 = 1;
+
+// Frame sent by a chat client, serialized as JSON tagged by "type"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientMessage {
+    // first frame of every connection, carries the auth requester token
+    Auth { token: String },
+    // start receiving messages and presence of a room, joining public rooms also makes the user a member
+    Join { room: i64 },
+    Leave { room: i64 },
+    // client_id is picked by the client and echoed in the ack
+    Chat {
+        room: i64,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_id: Option<String>
+    },
+    // message only the recipient, given by uuid, and the sender can see
+    Direct {
+        to: String,
+        text: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_id: Option<String>
+    },
+    // the user stepped away from this connection, users are idle once all their connections are
+    Idle,
+    Active
+}
+
+// Chat message as stored and broadcast by the server, timestamps are unix seconds
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct ChatMessage {
+    // ascending in the order messages were accepted
+    pub id: i64,
+    pub room_id: i64,
+    pub sender_uuid: String,
+    pub username: String,
+    pub text: String,
+    pub sent_at: i64
+}
+
+// Frame sent by the server, serialized as JSON tagged by "type"
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ServerMessage {
+    Chat(ChatMessage),
+    // delivered to every connection of the recipient and the sender
+    Direct(DirectMessage),
+    // most recent messages of a room, oldest first, sent after joining it
+    History { room: i64, messages: Vec<ChatMessage> },
+    Join { room: i64, user_uuid: String, username: String, at: i64 },
+    Leave { room: i64, user_uuid: String, username: String, at: i64 },
+    // users online when the connection was authenticated, followed by presence frames as it changes
+    PresenceSnapshot { users: Vec<Presence> },
+    // broadcast to every connection when a user comes online, goes idle or goes offline
+    Presence(Presence),
+    // only sent to the sender, once its message was accepted
+    Ack {
+        id: i64,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        client_id: Option<String>,
+        sent_at: i64
+    },
+    // uses the codes of the HTTP API, the connection stays open unless it is closed right after
+    Error { code: ApiErrorCode, message: String },
+    System { text: String, at: i64 }
+}
+
+impl ServerMessage {
+    pub fn error(code: ApiErrorCode, message: impl Into<String>) -> Self {
+        ServerMessage::Error { code, message: message.into() }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    Online,
+    Idle,
+    Offline
+}
+
+// Presence of a user across all of their connections, since is when the status last changed
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Presence {
+    pub user_uuid: String,
+    pub username: String,
+    pub status: PresenceStatus,
+    pub since: i64
+}
+
+// Room as listed to a user, private rooms are only listed to members
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoomInfo {
+    pub id: i64,
+    pub name: String,
+    pub private: bool,
+    pub member: bool,
+    pub created_at: i64
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct CreateRoom {
+    pub name: String,
+    #[serde(default)]
+    pub private: bool
+}
+
+impl Validate for CreateRoom {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .room_name("name", &self.name)
+            .finish()
+    }
+}
+
+// Invitation of a user to a room by name or email
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct AddMember {
+    pub username: String
+}
+
+impl Validate for AddMember {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .required("username", &self.username, "Username")
+            .finish()
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct RoomMember {
+    pub user_uuid: String,
+    pub username: String,
+    pub joined_at: i64
+}
+
+// Message of a one-to-one conversation, username is the sender's
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct DirectMessage {
+    pub id: i64,
+    pub sender_uuid: String,
+    pub recipient_uuid: String,
+    pub username: String,
+    pub text: String,
+    pub sent_at: i64
+}
+
+// Conversation with another user as listed to the user, newest first
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Conversation {
+    pub user_uuid: String,
+    pub username: String,
+    pub last_message_at: i64,
+    // messages from the other user not yet read
+    pub unread: i64
+}
+
+// Opening a conversation with a user by name or email
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct StartConversation {
+    pub username: String
+}
+
+impl Validate for StartConversation {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .required("username", &self.username, "Username")
+            .finish()
+    }
+}
@@ -0,0 +1,159 @@
+use std::{fmt, str::FromStr};
+
+use http::StatusCode;
+use serde::{Deserialize, Serialize};
+
+use crate::auth::AuthErrorType;
+
+// Machine-readable error code, serialized as a stable string clients can match on
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[serde(into = "String", try_from = "String")]
+pub enum ApiErrorCode {
+    // authentication and account failures, serialized as auth.<type>
+    Auth(AuthErrorType),
+    // request body failed validation, see fields
+    Validation,
+    BadRequest,
+    NotFound,
+    Conflict,
+    // database unreachable or failing queries
+    Database,
+    // server could not be reached or answered with something unreadable
+    Unavailable,
+    Internal
+}
+
+impl fmt::Display for ApiErrorCode {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiErrorCode::Auth(error_type) => write!(formatter, "auth.{}", error_type.code()),
+            ApiErrorCode::Validation => formatter.write_str("validation"),
+            ApiErrorCode::BadRequest => formatter.write_str("bad_request"),
+            ApiErrorCode::NotFound => formatter.write_str("not_found"),
+            ApiErrorCode::Conflict => formatter.write_str("conflict"),
+            ApiErrorCode::Database => formatter.write_str("database"),
+            ApiErrorCode::Unavailable => formatter.write_str("unavailable"),
+            ApiErrorCode::Internal => formatter.write_str("internal")
+        }
+    }
+}
+
+impl FromStr for ApiErrorCode {
+    type Err = String;
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        if let Some(auth_code) = code.strip_prefix("auth.") {
+            return auth_code.parse().map(ApiErrorCode::Auth);
+        }
+        match code {
+            "validation" => Ok(ApiErrorCode::Validation),
+            "bad_request" => Ok(ApiErrorCode::BadRequest),
+            "not_found" => Ok(ApiErrorCode::NotFound),
+            "conflict" => Ok(ApiErrorCode::Conflict),
+            "database" => Ok(ApiErrorCode::Database),
+            "unavailable" => Ok(ApiErrorCode::Unavailable),
+            "internal" => Ok(ApiErrorCode::Internal),
+            _ => Err(format!("unknown error code {code}"))
+        }
+    }
+}
+
+impl From<ApiErrorCode> for String {
+    fn from(code: ApiErrorCode) -> Self {
+        code.to_string()
+    }
+}
+
+impl TryFrom<String> for ApiErrorCode {
+    type Error = String;
+    fn try_from(code: String) -> Result<Self, Self::Error> {
+        code.parse()
+    }
+}
+
+// Validation failure of a single request field
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ApiErrorBody {
+    pub code: ApiErrorCode,
+    pub message: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub fields: Vec<FieldError>,
+    // request ID to quote when reporting the error, set by the server
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>
+}
+
+// Error returned by every API endpoint, shared by server and frontend
+#[derive(Debug, Clone)]
+pub struct ApiError {
+    pub status: StatusCode,
+    pub body: ApiErrorBody
+}
+
+impl ApiError {
+    pub fn new(status: StatusCode, code: ApiErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: ApiErrorBody {
+                code,
+                message: message.into(),
+                fields: Vec::new(),
+                request_id: None
+            }
+        }
+    }
+    pub fn auth(error_type: AuthErrorType) -> Self {
+        let status = error_type.status();
+        let message = error_type.message();
+        Self::new(status, ApiErrorCode::Auth(error_type), message)
+    }
+    // message lists every field message so clients showing only the message still explain the problem
+    pub fn validation(fields: Vec<FieldError>) -> Self {
+        let message = fields.iter().map(|field| field.message.as_str()).collect::<Vec<_>>().join("; ");
+        let mut error = Self::new(StatusCode::UNPROCESSABLE_ENTITY, ApiErrorCode::Validation, message);
+        error.body.fields = fields;
+        error
+    }
+    pub fn bad_request(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::BAD_REQUEST, ApiErrorCode::BadRequest, message)
+    }
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::NOT_FOUND, ApiErrorCode::NotFound, message)
+    }
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(StatusCode::CONFLICT, ApiErrorCode::Conflict, message)
+    }
+    pub fn database() -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, ApiErrorCode::Database, "Database unavailable")
+    }
+    pub fn unavailable() -> Self {
+        Self::new(StatusCode::SERVICE_UNAVAILABLE, ApiErrorCode::Unavailable, "Server unavailable")
+    }
+    pub fn internal() -> Self {
+        Self::new(StatusCode::INTERNAL_SERVER_ERROR, ApiErrorCode::Internal, "Server error")
+    }
+    // auth error type, if the error is in the auth category
+    pub fn auth_type(&self) -> Option<&AuthErrorType> {
+        match &self.body.code {
+            ApiErrorCode::Auth(error_type) => Some(error_type),
+            _ => None
+        }
+    }
+    pub fn body(&self) -> ApiErrorBody {
+        self.body.to_owned()
+    }
+    pub fn status(&self) -> StatusCode {
+        self.status.to_owned()
+    }
+}
+
+impl From<AuthErrorType> for ApiError {
+    fn from(error_type: AuthErrorType) -> Self {
+        Self::auth(error_type)
+    }
+}
@@ -0,0 +1,16 @@
+use serde::{Deserialize, Serialize};
+
+// Background job as shown to admins
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
+pub struct JobInfo {
+    pub id: i32,
+    pub kind: String,
+    pub status: String,
+    pub attempts: i32,
+    pub max_attempts: i32,
+    pub run_at: i64,
+    pub last_error: Option<String>,
+    pub created_at: i64,
+    pub updated_at: i64
+}
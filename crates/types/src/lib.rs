@@ -1,2 +1,8 @@
 pub mod user;
-pub mod auth;
\ No newline at end of file
+pub mod auth;
+pub mod roles;
+pub mod jobs;
+pub mod status;
+pub mod error;
+pub mod validation;
+pub mod chat;
@@ -0,0 +1,25 @@
+use serde::{Deserialize, Serialize};
+
+// Permission names granted through roles
+pub const USERS_READ: &str = "users:read";
+pub const USERS_DELETE: &str = "users:delete";
+pub const ROLES_READ: &str = "roles:read";
+pub const ROLES_ASSIGN: &str = "roles:assign";
+pub const JOBS_READ: &str = "jobs:read";
+
+// Role seeded by migrations with every permission
+pub const ADMIN_ROLE: &str = "admin";
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Role {
+    pub name: String,
+    pub description: String,
+    pub permissions: Vec<String>
+}
+
+// Request body to grant or remove a role from a user
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct RoleAssignment {
+    pub user_uuid: String,
+    pub role: String
+}
@@ -0,0 +1,29 @@
+use serde::{Deserialize, Serialize};
+
+// Server and database schema state
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct ServerStatus {
+    pub version: String,
+    pub database: String,
+    // newest applied migration, None before any migration ran
+    pub schema_version: Option<i64>,
+    // newest migration embedded in the server
+    pub latest_schema_version: i64
+}
+
+// Result of each readiness check, the server is ready when all pass
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
+pub struct Readiness {
+    // pool can run a query
+    pub database: bool,
+    // every embedded migration is applied
+    pub migrations: bool,
+    // mailer is configured, delivery failures are retried by the job queue
+    pub mailer: bool
+}
+
+impl Readiness {
+    pub fn is_ready(&self) -> bool {
+        self.database && self.migrations && self.mailer
+    }
+}
@@ -1,10 +1,12 @@
-use std::{fmt, str::FromStr};
+use std::fmt;
 
 use email_address::EmailAddress;
 use serde::{Deserialize, Serialize};
 
+use crate::{error::FieldError, validation::{BreachedPasswords, Validate, Validator}};
+
 #[cfg(feature = "sqlx")]
-use sqlx::{any::AnyRow, FromRow, Row};
+use sqlx::{ColumnIndex, Decode, FromRow, Row, Type};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct User {
@@ -13,29 +15,32 @@ pub struct User {
     pub username: String,
     pub pass: String,
     pub email: EmailAddress,
-    pub is_admin: bool
+    pub email_verified_at: Option<i64>,
+    pub failed_logins: i32,
+    pub locked_until: Option<i64>
 }
 
+// decodes rows of any database so each user repository can share it
 #[cfg(feature = "sqlx")]
-impl <'r> FromRow<'r, AnyRow> for User {
-    fn from_row(row: &AnyRow) ->  Result<Self, sqlx::Error> {
+impl <'r, R: Row> FromRow<'r, R> for User
+where
+    &'r str: ColumnIndex<R>,
+    i32: Decode<'r, R::Database> + Type<R::Database>,
+    i64: Decode<'r, R::Database> + Type<R::Database>,
+    String: Decode<'r, R::Database> + Type<R::Database>,
+{
+    fn from_row(row: &'r R) ->  Result<Self, sqlx::Error> {
         let id: i32 = row.try_get("id")?;
         let uuid: String = row.try_get("uuid")?;
         let username: String = row.try_get("username")?;
         let pass: String = row.try_get("pass")?;
-        let email: EmailAddress = match row.try_get::<String, &str>("email") {
-            Ok(email_str) => {
-                EmailAddress::new_unchecked(email_str)
-            },
-            Err(e) => {
-                println!("{}", e);
-                EmailAddress::new_unchecked("")
-            }
-        };
-        let is_admin: bool = row.try_get("is_admin")?;
+        let email = EmailAddress::new_unchecked(row.try_get::<String, &str>("email")?);
+        let email_verified_at: Option<i64> = row.try_get("email_verified_at")?;
+        let failed_logins: i32 = row.try_get("failed_logins")?;
+        let locked_until: Option<i64> = row.try_get("locked_until")?;
 
         Ok(Self {
-            id, uuid, username, pass, email, is_admin
+            id, uuid, username, pass, email, email_verified_at, failed_logins, locked_until
         })
     }
 }
@@ -66,6 +71,16 @@ impl RegisterUser {
     }
 }
 
+impl Validate for RegisterUser {
+    fn validate_with(&self, breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .username("username", &self.username)
+            .password("pass", &self.pass, Some(&self.username), breached)
+            .email("email", &self.email)
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Default)]
 pub struct LoginUser {
     pub username: String,
@@ -90,16 +105,26 @@ impl LoginUser {
     }
 }
 
+// accounts may predate the current policy, so logins only require both fields
+impl Validate for LoginUser {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .required("username", &self.username, "Username or email")
+            .required("pass", &self.pass, "Password")
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ResetUser {
-    pub email_address: EmailAddress,
+    pub email_address: String,
     pub pass: String
 }
 
 impl ResetUser {
     pub fn new(&self) -> ResetUser {
         Self {
-            email_address: EmailAddress::new_unchecked(""),
+            email_address: String::new(),
             pass: String::new()
         }
     }
@@ -107,25 +132,117 @@ impl ResetUser {
         let mut new_self = self.clone();
         match key {
             "pass" => new_self.pass = value,
-            "email" => new_self.email_address = EmailAddress::from_str(&value).unwrap(),
+            "email" => new_self.email_address = value,
+            _ => return Err(format!("Key not found: {}", key))
+        }
+        Ok(new_self)
+    }
+}
+
+// the username is not part of the body, the server checks the password against it once the user is known
+impl Validate for ResetUser {
+    fn validate_with(&self, breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .email("email_address", &self.email_address)
+            .password("pass", &self.pass, None, breached)
+            .finish()
+    }
+}
+
+// Profile changes of the requesting user, absent fields stay unchanged
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct UpdateUser {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>
+}
+
+impl UpdateUser {
+    // empty inputs leave the field unchanged
+    pub fn update_field(&self, key: &str, value: String) -> Result<Self,String> {
+        let mut new_self = self.clone();
+        let value = if value.is_empty() { None } else { Some(value) };
+        match key {
+            "username" => new_self.username = value,
+            "email" => new_self.email = value,
+            _ => return Err(format!("Key not found: {}", key))
+        }
+        Ok(new_self)
+    }
+}
+
+impl Validate for UpdateUser {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        let mut validator = Validator::new();
+        validator.check("username", self.username.is_some() || self.email.is_some(), "Nothing to change");
+        if let Some(username) = &self.username {
+            validator.username("username", username);
+        }
+        if let Some(email) = &self.email {
+            validator.email("email", email);
+        }
+        validator.finish()
+    }
+}
+
+// Password change of the requesting user, confirmed with the current password
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct ChangePassword {
+    pub current_pass: String,
+    pub pass: String
+}
+
+impl ChangePassword {
+    pub fn update_field(&self, key: &str, value: String) -> Result<Self,String> {
+        let mut new_self = self.clone();
+        match key {
+            "current_pass" => new_self.current_pass = value,
+            "pass" => new_self.pass = value,
             _ => return Err(format!("Key not found: {}", key))
         }
         Ok(new_self)
     }
 }
 
+// the server also checks the new password against the username
+impl Validate for ChangePassword {
+    fn validate_with(&self, breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .required("current_pass", &self.current_pass, "Current password")
+            .password("pass", &self.pass, None, breached)
+            .check("pass", self.pass != self.current_pass, "New password must differ from the current password")
+            .finish()
+    }
+}
+
+// Self-deletion of the requesting user, confirmed with the current password
+#[derive(Clone, Debug, Serialize, Deserialize, Default, PartialEq)]
+pub struct DeleteUser {
+    pub pass: String
+}
+
+impl Validate for DeleteUser {
+    fn validate_with(&self, _breached: &BreachedPasswords) -> Result<(), Vec<FieldError>> {
+        Validator::new()
+            .required("pass", &self.pass, "Password")
+            .finish()
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Default)]
-#[cfg_attr(feature = "sqlx", derive(sqlx::FromRow))]
 pub struct UserInfo {
     pub uuid: String,
     pub username: String,
     pub email: String,
-    pub is_admin: bool
+    pub email_verified: bool,
+    pub roles: Vec<String>,
+    pub permissions: Vec<String>
 }
 
 impl fmt::Display for UserInfo {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        write!(f, "UUID: {}\nUsername: {}\nEmail: {}\nIs Admin: {}", self.uuid, self.username, self.email, self.is_admin)
+        write!(f, "UUID: {}\nUsername: {}\nEmail: {}\nRoles: {}", self.uuid, self.username, self.email, self.roles.join(", "))
     }
 }
 
@@ -135,7 +252,9 @@ impl UserInfo {
             uuid: user.uuid,
             username: user.username,
             email: user.email.to_string(),
-            is_admin: user.is_admin
+            email_verified: user.email_verified_at.is_some(),
+            roles: Vec::new(),
+            permissions: Vec::new()
         }
     }
     pub fn new() -> Self {
@@ -143,7 +262,18 @@ impl UserInfo {
             uuid: String::new(),
             username: String::new(),
             email: String::new(),
-            is_admin: false
+            email_verified: false,
+            roles: Vec::new(),
+            permissions: Vec::new()
         }
     }
+    // attach roles and the permissions they grant
+    pub fn with_access(mut self, roles: Vec<String>, permissions: Vec<String>) -> Self {
+        self.roles = roles;
+        self.permissions = permissions;
+        self
+    }
+    pub fn has_permission(&self, permission: &str) -> bool {
+        self.permissions.iter().any(|granted| granted == permission)
+    }
 }
\ No newline at end of file
@@ -0,0 +1,205 @@
+use std::collections::HashSet;
+
+use email_address::EmailAddress;
+
+use crate::error::FieldError;
+
+// users.username is a VARCHAR(24)
+pub const USERNAME_MIN_LENGTH: usize = 3;
+pub const USERNAME_MAX_LENGTH: usize = 24;
+// users.email is a VARCHAR(254)
+pub const EMAIL_MAX_LENGTH: usize = 254;
+pub const PASSWORD_MIN_LENGTH: usize = 8;
+pub const PASSWORD_MAX_LENGTH: usize = 128;
+// rooms.name is a VARCHAR(32)
+pub const ROOM_NAME_MAX_LENGTH: usize = 32;
+
+// Passwords known from breaches, rejected regardless of their length
+#[derive(Debug, Clone, Default)]
+pub struct BreachedPasswords(HashSet<String>);
+
+impl BreachedPasswords {
+    // one password per line, blank lines and lines starting with # are skipped
+    pub fn from_list(list: &str) -> Self {
+        Self(list.lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(str::to_lowercase)
+            .collect())
+    }
+    pub fn contains(&self, pass: &str) -> bool {
+        self.0.contains(&pass.to_lowercase())
+    }
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+// Request bodies checked field by field before they reach the server logic
+pub trait Validate {
+    // collect an error for every invalid field
+    fn validate_with(&self, breached: &BreachedPasswords) -> Result<(), Vec<FieldError>>;
+    // validation without a breached password list, as the frontend runs it
+    fn validate(&self) -> Result<(), Vec<FieldError>> {
+        self.validate_with(&BreachedPasswords::default())
+    }
+}
+
+// Collects field errors, keeping only the first failed rule of each field
+#[derive(Debug, Default)]
+pub struct Validator {
+    errors: Vec<FieldError>
+}
+
+impl Validator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn check(&mut self, field: &str, valid: bool, message: impl Into<String>) -> &mut Self {
+        if !valid && !self.errors.iter().any(|error| error.field == field) {
+            self.errors.push(FieldError { field: field.to_string(), message: message.into() });
+        }
+        self
+    }
+    pub fn required(&mut self, field: &str, value: &str, label: &str) -> &mut Self {
+        self.check(field, !value.trim().is_empty(), format!("{label} is required"))
+    }
+    pub fn username(&mut self, field: &str, username: &str) -> &mut Self {
+        let length = username.chars().count();
+        self.required(field, username, "Username")
+            .check(field, (USERNAME_MIN_LENGTH..=USERNAME_MAX_LENGTH).contains(&length),
+                format!("Username must be {USERNAME_MIN_LENGTH} to {USERNAME_MAX_LENGTH} characters long"))
+            .check(field, username.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.')),
+                "Username may only contain letters, digits, '_', '-' and '.'")
+    }
+    pub fn email(&mut self, field: &str, email: &str) -> &mut Self {
+        self.required(field, email, "Email")
+            .check(field, email.len() <= EMAIL_MAX_LENGTH, format!("Email must be at most {EMAIL_MAX_LENGTH} characters long"))
+            .check(field, EmailAddress::is_valid(email), "Email address is invalid")
+    }
+    // password policy, username is None when it is not known to the caller
+    pub fn password(&mut self, field: &str, pass: &str, username: Option<&str>, breached: &BreachedPasswords) -> &mut Self {
+        let length = pass.chars().count();
+        self.required(field, pass, "Password")
+            .check(field, length >= PASSWORD_MIN_LENGTH, format!("Password must be at least {PASSWORD_MIN_LENGTH} characters long"))
+            .check(field, length <= PASSWORD_MAX_LENGTH, format!("Password must be at most {PASSWORD_MAX_LENGTH} characters long"))
+            .check(field, username.is_none_or(|username| !pass.eq_ignore_ascii_case(username)), "Password must not be the username")
+            .check(field, !breached.contains(pass), "Password appears in a list of breached passwords")
+    }
+    pub fn room_name(&mut self, field: &str, name: &str) -> &mut Self {
+        self.required(field, name, "Room name")
+            .check(field, name.chars().count() <= ROOM_NAME_MAX_LENGTH, format!("Room name must be at most {ROOM_NAME_MAX_LENGTH} characters long"))
+            .check(field, name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-')),
+                "Room name may only contain letters, digits, '_' and '-'")
+    }
+    pub fn finish(&mut self) -> Result<(), Vec<FieldError>> {
+        if self.errors.is_empty() {
+            Ok(())
+        } else {
+            Err(std::mem::take(&mut self.errors))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // message of the error for field, None if the field passed
+    fn message(result: Result<(), Vec<FieldError>>, field: &str) -> Option<String> {
+        result.err()?.into_iter().find(|error| error.field == field).map(|error| error.message)
+    }
+
+    fn password(pass: &str, username: Option<&str>, breached: &BreachedPasswords) -> Option<String> {
+        message(Validator::new().password("pass", pass, username, breached).finish(), "pass")
+    }
+
+    #[test]
+    fn username_rules() {
+        let username = |name: &str| message(Validator::new().username("username", name).finish(), "username");
+        assert_eq!(username("alice_b.c-1"), None);
+        assert_eq!(username("  ").as_deref(), Some("Username is required"));
+        assert_eq!(username("al").as_deref(), Some("Username must be 3 to 24 characters long"));
+        assert_eq!(username(&"a".repeat(25)).as_deref(), Some("Username must be 3 to 24 characters long"));
+        assert_eq!(username(&"a".repeat(24)), None);
+        assert_eq!(username("alice smith").as_deref(), Some("Username may only contain letters, digits, '_', '-' and '.'"));
+        assert_eq!(username("élise").as_deref(), Some("Username may only contain letters, digits, '_', '-' and '.'"));
+    }
+
+    #[test]
+    fn email_rules() {
+        let email = |address: &str| message(Validator::new().email("email", address).finish(), "email");
+        assert_eq!(email("alice@example.com"), None);
+        assert_eq!(email("").as_deref(), Some("Email is required"));
+        assert_eq!(email("alice.example.com").as_deref(), Some("Email address is invalid"));
+        let long = format!("{}@example.com", "a".repeat(EMAIL_MAX_LENGTH));
+        assert_eq!(email(&long).as_deref(), Some("Email must be at most 254 characters long"));
+    }
+
+    #[test]
+    fn password_length() {
+        let breached = BreachedPasswords::default();
+        assert_eq!(password("", None, &breached).as_deref(), Some("Password is required"));
+        assert_eq!(password("short", None, &breached).as_deref(), Some("Password must be at least 8 characters long"));
+        assert_eq!(password(&"p".repeat(PASSWORD_MIN_LENGTH), None, &breached), None);
+        assert_eq!(password(&"p".repeat(PASSWORD_MAX_LENGTH), None, &breached), None);
+        assert_eq!(password(&"p".repeat(PASSWORD_MAX_LENGTH + 1), None, &breached).as_deref(), Some("Password must be at most 128 characters long"));
+        // length counts characters, not bytes
+        assert_eq!(password("pässwör", None, &breached).as_deref(), Some("Password must be at least 8 characters long"));
+    }
+
+    #[test]
+    fn password_not_username() {
+        let breached = BreachedPasswords::default();
+        assert_eq!(password("Alice-Smith", Some("alice-smith"), &breached).as_deref(), Some("Password must not be the username"));
+        assert_eq!(password("alice-smith", None, &breached), None);
+        assert_eq!(password("alice-smith-2", Some("alice-smith"), &breached), None);
+    }
+
+    #[test]
+    fn password_breached() {
+        let breached = BreachedPasswords::from_list("# common passwords\n\nPassword1\n  letmein123  \n");
+        assert_eq!(breached.len(), 2);
+        assert_eq!(password("password1", None, &breached).as_deref(), Some("Password appears in a list of breached passwords"));
+        assert_eq!(password("LetMeIn123", None, &breached).as_deref(), Some("Password appears in a list of breached passwords"));
+        assert_eq!(password("# common passwords", None, &breached), None);
+        assert_eq!(password("password2", None, &breached), None);
+    }
+
+    #[test]
+    fn room_name_rules() {
+        let room_name = |name: &str| message(Validator::new().room_name("name", name).finish(), "name");
+        assert_eq!(room_name("general-2_b"), None);
+        assert_eq!(room_name("").as_deref(), Some("Room name is required"));
+        assert_eq!(room_name(&"r".repeat(ROOM_NAME_MAX_LENGTH + 1)).as_deref(), Some("Room name must be at most 32 characters long"));
+        assert_eq!(room_name("the lounge").as_deref(), Some("Room name may only contain letters, digits, '_' and '-'"));
+    }
+
+    #[test]
+    fn keeps_first_failure_per_field() {
+        let breached = BreachedPasswords::from_list("al");
+        let errors = Validator::new()
+            .username("username", "a!")
+            .password("pass", "al", Some("al"), &breached)
+            .email("email", "alice@example.com")
+            .check("pass", false, "Later rule")
+            .finish()
+            .unwrap_err();
+        // one error per failing field, in the order the fields were checked
+        let fields: Vec<(&str, &str)> = errors.iter().map(|error| (error.field.as_str(), error.message.as_str())).collect();
+        assert_eq!(fields, [
+            ("username", "Username must be 3 to 24 characters long"),
+            ("pass", "Password must be at least 8 characters long")
+        ]);
+    }
+
+    #[test]
+    fn finish_resets_errors() {
+        let mut validator = Validator::new();
+        assert!(validator.check("field", false, "Invalid").finish().is_err());
+        assert_eq!(validator.finish(), Ok(()));
+    }
+}
@@ -0,0 +1,2 @@
+-- Revert pass column to bcrypt hash length
+ALTER TABLE "users" ALTER COLUMN pass TYPE VARCHAR(60);
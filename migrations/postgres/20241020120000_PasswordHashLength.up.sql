@@ -0,0 +1,2 @@
+-- Widen pass column to fit Argon2id PHC strings
+ALTER TABLE "users" ALTER COLUMN pass TYPE VARCHAR(255);
@@ -0,0 +1,3 @@
+-- Add down migration script here
+DROP TABLE "recovery_codes";
+DROP TABLE "user_totp";
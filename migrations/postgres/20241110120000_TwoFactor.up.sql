@@ -0,0 +1,16 @@
+-- Add migration script here
+CREATE TABLE "user_totp" (
+    id SERIAL PRIMARY KEY UNIQUE,
+    user_uuid VARCHAR(36) UNIQUE NOT NULL,
+    secret VARCHAR(64) NOT NULL,
+    enabled BOOLEAN NOT NULL DEFAULT FALSE,
+    last_used_step BIGINT,
+    created_at BIGINT NOT NULL
+);
+CREATE TABLE "recovery_codes" (
+    id SERIAL PRIMARY KEY UNIQUE,
+    user_uuid VARCHAR(36) NOT NULL,
+    code_hash VARCHAR(64) NOT NULL,
+    created_at BIGINT NOT NULL
+);
+CREATE INDEX recovery_codes_user_uuid_idx ON "recovery_codes" (user_uuid);
@@ -0,0 +1,28 @@
+-- Replace is_admin flag with roles granting named permissions
+CREATE TABLE "roles" (
+    id SERIAL PRIMARY KEY UNIQUE,
+    name VARCHAR(64) UNIQUE NOT NULL,
+    description VARCHAR(255) NOT NULL DEFAULT ''
+);
+CREATE TABLE "permissions" (
+    id SERIAL PRIMARY KEY UNIQUE,
+    name VARCHAR(64) UNIQUE NOT NULL
+);
+CREATE TABLE "role_permissions" (
+    role_id INTEGER NOT NULL REFERENCES "roles" (id) ON DELETE CASCADE,
+    permission_id INTEGER NOT NULL REFERENCES "permissions" (id) ON DELETE CASCADE,
+    PRIMARY KEY (role_id, permission_id)
+);
+CREATE TABLE "user_roles" (
+    user_uuid VARCHAR(36) NOT NULL,
+    role_id INTEGER NOT NULL REFERENCES "roles" (id) ON DELETE CASCADE,
+    PRIMARY KEY (user_uuid, role_id)
+);
+INSERT INTO "permissions" (name) VALUES ('users:read'), ('users:delete'), ('roles:read'), ('roles:assign');
+INSERT INTO "roles" (name, description) VALUES ('admin', 'Full access to user and role management');
+INSERT INTO "role_permissions" (role_id, permission_id)
+    SELECT "roles".id, "permissions".id FROM "roles", "permissions" WHERE "roles".name = 'admin';
+-- Existing admins keep their access through the admin role
+INSERT INTO "user_roles" (user_uuid, role_id)
+    SELECT "users".uuid, "roles".id FROM "users", "roles" WHERE "users".is_admin AND "roles".name = 'admin';
+ALTER TABLE "users" DROP COLUMN is_admin;
@@ -0,0 +1,18 @@
+-- Durable queue of background jobs such as outbound email
+CREATE TABLE "jobs" (
+    id SERIAL PRIMARY KEY UNIQUE,
+    kind VARCHAR(32) NOT NULL,
+    payload TEXT NOT NULL,
+    status VARCHAR(16) NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 0,
+    max_attempts INTEGER NOT NULL,
+    run_at BIGINT NOT NULL,
+    last_error TEXT,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+CREATE INDEX jobs_status_run_at_idx ON "jobs" (status, run_at);
+INSERT INTO "permissions" (name) VALUES ('jobs:read');
+INSERT INTO "role_permissions" (role_id, permission_id)
+    SELECT "roles".id, "permissions".id FROM "roles", "permissions"
+    WHERE "roles".name = 'admin' AND "permissions".name = 'jobs:read';
@@ -0,0 +1,3 @@
+-- Count consecutive failed logins to lock accounts for an escalating period
+ALTER TABLE "users" ADD COLUMN failed_logins INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE "users" ADD COLUMN locked_until BIGINT;
@@ -0,0 +1,19 @@
+-- Named chat rooms, private rooms are only visible to their members
+CREATE TABLE "rooms" (
+    id BIGSERIAL PRIMARY KEY UNIQUE,
+    name VARCHAR(32) UNIQUE NOT NULL,
+    visibility VARCHAR(16) NOT NULL,
+    created_by VARCHAR(36),
+    created_at BIGINT NOT NULL
+);
+CREATE TABLE "room_members" (
+    room_id BIGINT NOT NULL REFERENCES "rooms" (id) ON DELETE CASCADE,
+    user_uuid VARCHAR(36) NOT NULL,
+    joined_at BIGINT NOT NULL,
+    PRIMARY KEY (room_id, user_uuid)
+);
+-- Messages from before rooms existed end up in general
+INSERT INTO "rooms" (name, visibility, created_at) VALUES ('general', 'public', 0);
+ALTER TABLE "messages" ADD COLUMN room_id BIGINT NOT NULL DEFAULT 1;
+ALTER TABLE "messages" ALTER COLUMN room_id DROP DEFAULT;
+CREATE INDEX messages_room_id_id_idx ON "messages" (room_id, id);
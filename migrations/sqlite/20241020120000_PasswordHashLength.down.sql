@@ -0,0 +1,2 @@
+-- SQLite does not enforce VARCHAR lengths, nothing to revert
+SELECT 1;
@@ -0,0 +1,2 @@
+-- SQLite does not enforce VARCHAR lengths, pass column already fits Argon2id PHC strings
+SELECT 1;
@@ -0,0 +1,9 @@
+-- Add migration script here
+CREATE TABLE "password_resets" (
+    id INTEGER PRIMARY KEY UNIQUE,
+    key_hash VARCHAR(64) UNIQUE NOT NULL,
+    email VARCHAR(254) NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL
+);
+CREATE INDEX password_resets_expires_at_idx ON "password_resets" (expires_at);
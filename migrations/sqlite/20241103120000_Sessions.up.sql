@@ -0,0 +1,13 @@
+-- Add migration script here
+CREATE TABLE "sessions" (
+    id INTEGER PRIMARY KEY UNIQUE,
+    jti VARCHAR(36) UNIQUE NOT NULL,
+    family_id VARCHAR(36) NOT NULL,
+    user_uuid VARCHAR(36) NOT NULL,
+    created_at INTEGER NOT NULL,
+    expires_at INTEGER NOT NULL,
+    rotated_at INTEGER,
+    revoked BOOLEAN NOT NULL DEFAULT FALSE
+);
+CREATE INDEX sessions_family_id_idx ON "sessions" (family_id);
+CREATE INDEX sessions_user_uuid_idx ON "sessions" (user_uuid);
@@ -0,0 +1,8 @@
+-- Add down migration script here
+ALTER TABLE "users" ADD COLUMN is_admin BOOLEAN;
+UPDATE "users" SET is_admin = "users".uuid IN (
+    SELECT user_uuid FROM "user_roles" JOIN "roles" ON "roles".id = "user_roles".role_id WHERE "roles".name = 'admin');
+DROP TABLE "user_roles";
+DROP TABLE "role_permissions";
+DROP TABLE "permissions";
+DROP TABLE "roles";
@@ -0,0 +1,4 @@
+-- Track when a user confirmed ownership of their email address
+ALTER TABLE "users" ADD COLUMN email_verified_at INTEGER;
+-- Accounts created before verification existed are treated as verified
+UPDATE "users" SET email_verified_at = CAST(strftime('%s', 'now') AS INTEGER);
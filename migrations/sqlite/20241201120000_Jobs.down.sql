@@ -0,0 +1,4 @@
+-- Add down migration script here
+DELETE FROM "role_permissions" WHERE permission_id IN (SELECT id FROM "permissions" WHERE name = 'jobs:read');
+DELETE FROM "permissions" WHERE name = 'jobs:read';
+DROP TABLE "jobs";
@@ -0,0 +1,8 @@
+-- Chat history, username is kept as sent so history survives renames and deleted accounts
+CREATE TABLE "messages" (
+    id INTEGER PRIMARY KEY UNIQUE,
+    sender_uuid VARCHAR(36) NOT NULL,
+    username VARCHAR(24) NOT NULL,
+    text TEXT NOT NULL,
+    sent_at INTEGER NOT NULL
+);
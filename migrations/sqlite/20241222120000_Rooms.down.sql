@@ -0,0 +1,5 @@
+-- Add down migration script here
+DROP INDEX messages_room_id_id_idx;
+ALTER TABLE "messages" DROP COLUMN room_id;
+DROP TABLE "room_members";
+DROP TABLE "rooms";
@@ -0,0 +1,18 @@
+-- Named chat rooms, private rooms are only visible to their members
+CREATE TABLE "rooms" (
+    id INTEGER PRIMARY KEY UNIQUE,
+    name VARCHAR(32) UNIQUE NOT NULL,
+    visibility VARCHAR(16) NOT NULL,
+    created_by VARCHAR(36),
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE "room_members" (
+    room_id INTEGER NOT NULL REFERENCES "rooms" (id) ON DELETE CASCADE,
+    user_uuid VARCHAR(36) NOT NULL,
+    joined_at INTEGER NOT NULL,
+    PRIMARY KEY (room_id, user_uuid)
+);
+-- Messages from before rooms existed end up in general
+INSERT INTO "rooms" (name, visibility, created_at) VALUES ('general', 'public', 0);
+ALTER TABLE "messages" ADD COLUMN room_id INTEGER NOT NULL DEFAULT 1;
+CREATE INDEX messages_room_id_id_idx ON "messages" (room_id, id);
@@ -0,0 +1,12 @@
+-- One-to-one messages, read_at stays empty until the recipient opened the conversation
+CREATE TABLE "direct_messages" (
+    id INTEGER PRIMARY KEY UNIQUE,
+    sender_uuid VARCHAR(36) NOT NULL,
+    recipient_uuid VARCHAR(36) NOT NULL,
+    username VARCHAR(24) NOT NULL,
+    text TEXT NOT NULL,
+    sent_at INTEGER NOT NULL,
+    read_at INTEGER
+);
+CREATE INDEX direct_messages_sender_recipient_idx ON "direct_messages" (sender_uuid, recipient_uuid, id);
+CREATE INDEX direct_messages_recipient_read_idx ON "direct_messages" (recipient_uuid, read_at);
@@ -0,0 +1,2 @@
+-- Time a worker claimed a running job, jobs are only taken back once their lease ran out
+ALTER TABLE "jobs" ADD COLUMN claimed_at INTEGER;